@@ -43,100 +43,34 @@ fn parse_grid(string_list: &Vec<String>) -> gridlib::Grid<ParsedSpace> {
     return gridlib::Grid::new(width, height, values);
 }
 
-fn get_parts_adjacent_to_symbols(grid: &gridlib::Grid<ParsedSpace>) -> Vec<PartNumber> {
-    let mut result: Vec<PartNumber> = vec![];
-    let mut cur_number: Option<PartNumber> = None;
-    let mut cur_y = 0;
-    let mut cur_part_num = false;
-    for coord in grid.coord_iter() {
-        if coord.y != cur_y {
-            // We switched rows
-            // First if there is a number we should add, do so
-            if let Some(n) = cur_number {
-                if cur_part_num {
-                    result.push(n);
-                }
-            }
-            // set back to defaults with the new y.
-            cur_y = coord.y;
-            cur_number = None;
-            cur_part_num = false;
-        }
-        let v = grid.get_value(coord).unwrap();
-        match v {
-            ParsedSpace::Blank => {
-                if cur_part_num {
-                    // we aren't on a number anymore
-                    if let Some(n) = cur_number {
-                        if cur_part_num {
-                            result.push(n);
-                        }
-                    }
-                    cur_part_num = false;
-                }
-                // cur_number should always be reset
-                cur_number = None;
-            }
-            ParsedSpace::Numeral(new_ones_place) => {
-                if let Some(x) = cur_number {
-                    cur_number = Some(x * 10 + new_ones_place);
-                } else {
-                    cur_number = Some(new_ones_place);
-                }
-            }
-            ParsedSpace::Symbol(_) => {
-                if cur_part_num {
-                    // we aren't on a number anymore
-                    if let Some(n) = cur_number {
-                        if cur_part_num {
-                            result.push(n);
-                        }
-                    }
-                    cur_part_num = false;
-                }
-                // cur_number should always be reset
-                cur_number = None;
-            }
-        }
-        if let Some(_) = cur_number {
-            if !cur_part_num {
-                // Check if we should be a part number
-                for possible_loc in grid.get_adjacent_coordinates(coord) {
-                    if cur_part_num {
-                        break;
-                    }
-                    let z = grid.get_value(possible_loc).unwrap();
-                    match z {
-                        ParsedSpace::Symbol(_) => {
-                            cur_part_num = true;
-                        }
-                        _ => {}
-                    }
-                }
-                for possible_loc in grid.get_diag_adjacent_coordinates(coord) {
-                    if cur_part_num {
-                        break;
-                    }
-                    let z = grid.get_value(possible_loc).unwrap();
-                    match z {
-                        ParsedSpace::Symbol(_) => {
-                            cur_part_num = true;
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        }
-    }
-
-    // Check if we have a last value, if so add it
-    if let Some(n) = cur_number {
-        if cur_part_num {
-            result.push(n);
-        }
-    }
+// Each number on the grid, with the cells it covers.
+fn find_numbers(grid: &gridlib::Grid<ParsedSpace>) -> Vec<(PartNumber, gridlib::Span)> {
+    let spans = gridlib::scan_spans(grid, gridlib::Orientation::Horizontal, |v| {
+        return matches!(v, ParsedSpace::Numeral(_));
+    });
+    return spans
+        .into_iter()
+        .map(|span| {
+            let number = span.values(grid).into_iter().fold(0, |total, v| match v {
+                ParsedSpace::Numeral(digit) => total * 10 + digit,
+                _ => panic!("Span should only have numerals"),
+            });
+            return (number, span);
+        })
+        .collect();
+}
 
-    return result;
+fn get_parts_adjacent_to_symbols(grid: &gridlib::Grid<ParsedSpace>) -> Vec<PartNumber> {
+    return find_numbers(grid)
+        .into_iter()
+        .filter(|(_, span)| {
+            return span
+                .neighbours(grid, gridlib::Connectivity::Eight)
+                .into_iter()
+                .any(|c| matches!(grid.get_value(c).unwrap(), ParsedSpace::Symbol(_)));
+        })
+        .map(|(number, _)| number)
+        .collect();
 }
 
 /// Add up all parts on the grid that are adjacent to a symbol thats not a period.
@@ -166,97 +100,13 @@ type GearMap = HashMap<SymbolCoord, Vec<PartNumber>>;
 
 fn get_parts_adjacent_to_symbol(grid: &gridlib::Grid<ParsedSpace>, symbol: char) -> GearMap {
     let mut result: GearMap = GearMap::new();
-    let mut cur_number: Option<PartNumber> = None;
-    let mut cur_y = 0;
-    let mut gear_symbol_coord: Option<SymbolCoord> = None;
-    for coord in grid.coord_iter() {
-        if coord.y != cur_y {
-            // We switched rows
-            // First if there is a number we should add, do so
-            if let Some(n) = cur_number {
-                if let Some(gear_coord) = gear_symbol_coord {
-                    let cur_vector = result.entry(gear_coord).or_insert(vec![]);
-                    cur_vector.push(n);
-                }
-            }
-            // set back to defaults with the new y.
-            cur_y = coord.y;
-            cur_number = None;
-            gear_symbol_coord = None;
-        }
-        let v = grid.get_value(coord).unwrap();
-        match v {
-            ParsedSpace::Blank => {
-                if let Some(n) = cur_number {
-                    if let Some(gear_coord) = gear_symbol_coord {
-                        let cur_vector = result.entry(gear_coord).or_insert(vec![]);
-                        cur_vector.push(n);
-                    }
-                }
-                gear_symbol_coord = None;
-                cur_number = None;
-            }
-            ParsedSpace::Numeral(new_ones_place) => {
-                if let Some(x) = cur_number {
-                    cur_number = Some(x * 10 + new_ones_place);
-                } else {
-                    cur_number = Some(new_ones_place);
-                }
-            }
-            ParsedSpace::Symbol(_) => {
-                if let Some(n) = cur_number {
-                    if let Some(gear_coord) = gear_symbol_coord {
-                        let cur_vector = result.entry(gear_coord).or_insert(vec![]);
-                        cur_vector.push(n);
-                    }
-                }
-                gear_symbol_coord = None;
-                cur_number = None;
+    for (number, span) in find_numbers(grid) {
+        for coord in span.neighbours(grid, gridlib::Connectivity::Eight) {
+            if grid.get_value(coord).unwrap() == ParsedSpace::Symbol(symbol) {
+                result.entry(coord).or_insert(vec![]).push(number);
             }
         }
-        if let Some(_) = cur_number {
-            if let None = gear_symbol_coord {
-                // Check if we should be a part number
-                for possible_loc in grid.get_adjacent_coordinates(coord) {
-                    if let Some(_) = gear_symbol_coord {
-                        break;
-                    }
-                    let z = grid.get_value(possible_loc).unwrap();
-                    match z {
-                        ParsedSpace::Symbol(s) => {
-                            if s == symbol {
-                                gear_symbol_coord = Some(possible_loc);
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-                for possible_loc in grid.get_diag_adjacent_coordinates(coord) {
-                    if let Some(_) = gear_symbol_coord {
-                        break;
-                    }
-                    let z = grid.get_value(possible_loc).unwrap();
-                    match z {
-                        ParsedSpace::Symbol(s) => {
-                            if s == symbol {
-                                gear_symbol_coord = Some(possible_loc);
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        }
-    }
-
-    // Check if we have a last value, if so add it
-    if let Some(n) = cur_number {
-        if let Some(gear_coord) = gear_symbol_coord {
-            let cur_vector = result.entry(gear_coord).or_insert(vec![]);
-            cur_vector.push(n);
-        }
     }
-
     return result;
 }
 
@@ -2,6 +2,8 @@ extern crate filelib;
 
 pub use filelib::load;
 pub use filelib::split_lines_by_blanks;
+use mathlib::Interval;
+use std::fmt::{Display, Formatter};
 
 type IdNum = u64;
 
@@ -54,25 +56,81 @@ pub fn example_input() -> Vec<Vec<String>> {
 }
 
 #[derive(PartialEq, Debug, Copy, Clone)]
-enum AlmanacItem {
-    Seed,
-    Soil,
-    Fertilizer,
-    Water,
-    Light,
-    Temperature,
-    Humidity,
-    Location,
+struct Segment {
+    src: Interval<IdNum>,
+    dst_start: IdNum,
 }
 
+impl Segment {
+    fn identity(src: Interval<IdNum>) -> Segment {
+        return Segment {
+            src: src,
+            dst_start: src.get_start(),
+        };
+    }
+
+    fn convert_id(&self, id: IdNum) -> IdNum {
+        return id - self.src.get_start() + self.dst_start;
+    }
+
+    fn dst(&self) -> Interval<IdNum> {
+        return self.src.rebase(self.src.get_start(), self.dst_start);
+    }
+
+    // Wide enough that ids near IdNum::MAX can't overflow.
+    fn offset(&self) -> i128 {
+        return self.dst_start as i128 - self.src.get_start() as i128;
+    }
+}
+
+// Sort out a list of segments so they cover every id exactly once.
+// Gaps are filled with identity segments, and touching segments with the same offset are merged.
+fn normalize_segments(mapped: Vec<Segment>) -> Vec<Segment> {
+    let mut sorted = mapped;
+    sorted.sort_by_key(|s| s.src.get_start());
+
+    let mut segments: Vec<Segment> = vec![];
+    // None once we have covered IdNum::MAX
+    let mut next_id: Option<IdNum> = Some(0);
+    for segment in sorted {
+        let next = next_id.unwrap_or(IdNum::MAX);
+        assert!(
+            next_id.is_some() && segment.src.get_start() >= next,
+            "Overlapping source ranges at {}",
+            segment.src.get_start()
+        );
+        if segment.src.get_start() > next {
+            segments.push(Segment::identity(Interval::new(
+                next,
+                segment.src.get_start() - 1,
+            )));
+        }
+        segments.push(segment);
+        next_id = segment.src.get_end().checked_add(1);
+    }
+    if let Some(next) = next_id {
+        segments.push(Segment::identity(Interval::new(next, IdNum::MAX)));
+    }
+
+    let mut merged: Vec<Segment> = vec![];
+    for segment in segments {
+        if let Some(last) = merged.last_mut() {
+            if last.offset() == segment.offset() {
+                last.src = Interval::new(last.src.get_start(), segment.src.get_end());
+                continue;
+            }
+        }
+        merged.push(segment);
+    }
+    return merged;
+}
+
+// Piecewise map from one item to another, stored as sorted segments covering every id.
 #[derive(PartialEq, Debug, Clone)]
 struct RangeMap {
-    src: AlmanacItem,
-    dst: AlmanacItem,
-    src_start_ids: Vec<IdNum>,
-    src_end_ids: Vec<IdNum>,
-    dst_start_ids: Vec<IdNum>,
-    dst_end_ids: Vec<IdNum>,
+    src: String,
+    dst: String,
+    segments: Vec<Segment>,
 }
 
 impl RangeMap {
@@ -80,157 +138,238 @@ impl RangeMap {
         src_starts: Vec<IdNum>,
         dst_starts: Vec<IdNum>,
         range_size: Vec<IdNum>,
-        src_type: AlmanacItem,
-        dst_type: AlmanacItem,
+        src_type: &str,
+        dst_type: &str,
     ) -> RangeMap {
-        let src_ends = src_starts
-            .iter()
-            .zip(range_size.clone())
-            .map(|(src_id, cur_range)| src_id + cur_range - 1)
-            .collect();
-        let dst_ends = dst_starts
+        let mapped = src_starts
             .iter()
+            .zip(dst_starts)
             .zip(range_size)
-            .map(|(dst_id, cur_range)| dst_id + cur_range - 1)
+            .filter_map(|((src_id, dst_id), cur_range)| {
+                return Interval::from_len(*src_id, cur_range).map(|src| Segment {
+                    src: src,
+                    dst_start: dst_id,
+                });
+            })
             .collect();
         return RangeMap {
-            src: src_type,
-            dst: dst_type,
-            src_start_ids: src_starts.clone(),
-            src_end_ids: src_ends,
-            dst_start_ids: dst_starts.clone(),
-            dst_end_ids: dst_ends,
+            src: src_type.to_string(),
+            dst: dst_type.to_string(),
+            segments: normalize_segments(mapped),
         };
     }
 
+    fn segment_index(&self, id: IdNum) -> usize {
+        // Segments cover every id, so this always lands on one.
+        return self.segments.partition_point(|s| s.src.get_end() < id);
+    }
+
     fn convert_id(&self, id: IdNum) -> IdNum {
-        for (index, (start_id, end_id)) in self
-            .src_start_ids
-            .iter()
-            .zip(self.src_end_ids.clone())
-            .enumerate()
-        {
-            if id >= *start_id && id <= end_id {
-                let offset = id - *start_id;
-                return self.dst_start_ids[index] + offset;
+        return self.segments[self.segment_index(id)].convert_id(id);
+    }
+
+    // Pairs of (source piece, segment it falls in), in source order
+    fn pieces(&self, range: Interval<IdNum>) -> Vec<(Interval<IdNum>, Segment)> {
+        let mut result = vec![];
+        for segment in self.segments[self.segment_index(range.get_start())..].iter() {
+            if segment.src.get_start() > range.get_end() {
+                break;
+            }
+            if let Some(overlap) = range.intersect(&segment.src) {
+                result.push((overlap, *segment));
             }
         }
-        // If not mapped, return same
-        return id;
-    }
-
-    fn convert_range(&self, start: IdNum, end: IdNum) -> (Vec<IdNum>, Vec<IdNum>) {
-        // possiblities
-        //               R: |------------|
-        //          |----------| [1]
-        //                      |--| [2]
-        //                             |------| [3]
-        //          |--| [4]
-        //                                   |--| [4]
-        //          |-------------------------------| [5]
-        let mut start_remaining = vec![start];
-        let mut end_remaining = vec![end];
-        let mut mapped_start = vec![];
-        let mut mapped_end = vec![];
-
-        while let Some(s_r) = start_remaining.pop() {
-            let e_r = end_remaining.pop().unwrap();
-            let mut map_found = false;
-
-            for (index, (start_id, end_id)) in self
-                .src_start_ids
-                .iter()
-                .zip(self.src_end_ids.clone())
-                .enumerate()
-            {
-                if e_r < *start_id || s_r > end_id {
-                    // We are outside the range entirely [4]
-                    continue;
-                }
-                if s_r >= *start_id && e_r <= end_id {
-                    // We are inside the range [2], just map directly
-                    map_found = true;
-                    let offset = s_r - *start_id;
-                    let end_offset = e_r - *start_id;
-                    let m_s = self.dst_start_ids[index] + offset;
-                    let m_e = self.dst_start_ids[index] + end_offset;
-                    mapped_start.push(m_s);
-                    mapped_end.push(m_e);
-                    break;
-                }
-                if s_r < *start_id && e_r > end_id {
-                    // We are a larger range
-                    map_found = true;
-                    let left_end = *start_id - 1;
-                    let right_start = end_id + 1;
-                    let m_s = self.dst_start_ids[index];
-                    let m_e = self.dst_end_ids[index];
-                    start_remaining.push(s_r);
-                    start_remaining.push(right_start);
-                    end_remaining.push(left_end);
-                    end_remaining.push(e_r);
-                    mapped_start.push(m_s);
-                    mapped_end.push(m_e);
-                    break;
-                }
-                if s_r < *start_id {
-                    // We extend into the range [1]
-                    map_found = true;
-                    let left_end = *start_id - 1;
-                    let end_offset = e_r - start_id;
-                    let m_s = self.dst_start_ids[index];
-                    let m_e = self.dst_start_ids[index] + end_offset;
-                    mapped_start.push(m_s);
-                    mapped_end.push(m_e);
-                    start_remaining.push(s_r);
-                    end_remaining.push(left_end);
-                    break;
-                }
-                // One possiblty left, we extend past the range [3].
-                map_found = true;
-                let right_start = end_id + 1;
-                let offset = s_r - *start_id;
-                let m_s = self.dst_start_ids[index] + offset;
-                let m_e = self.dst_end_ids[index];
-                mapped_start.push(m_s);
-                mapped_end.push(m_e);
-                start_remaining.push(right_start);
-                end_remaining.push(e_r);
-                break;
+        return result;
+    }
+
+    // Each segment only shifts ids, so the lowest output of a piece is at its start.
+    fn min_in_range(&self, range: Interval<IdNum>) -> IdNum {
+        return self
+            .pieces(range)
+            .iter()
+            .map(|(piece, segment)| segment.convert_id(piece.get_start()))
+            .min()
+            .unwrap();
+    }
+
+    // Apply self, then next, as one map.
+    fn compose(&self, next: &RangeMap) -> RangeMap {
+        assert_eq!(self.dst, next.src);
+        let mut segments = vec![];
+        for segment in self.segments.iter() {
+            // Cut our output by where next changes, then pull each piece back to our input.
+            for (piece, next_segment) in next.pieces(segment.dst()) {
+                segments.push(Segment {
+                    src: piece.rebase(segment.dst_start, segment.src.get_start()),
+                    dst_start: next_segment.convert_id(piece.get_start()),
+                });
             }
+        }
+        return RangeMap {
+            src: self.src.clone(),
+            dst: next.dst.clone(),
+            segments: normalize_segments(segments),
+        };
+    }
+
+    // Only possible if every id is produced exactly once.
+    fn invert(&self) -> Option<RangeMap> {
+        let mut inverted: Vec<Segment> = self
+            .segments
+            .iter()
+            .map(|s| Segment {
+                src: s.dst(),
+                dst_start: s.src.get_start(),
+            })
+            .collect();
+        inverted.sort_by_key(|s| s.src.get_start());
 
-            if !map_found {
-                // These weren't found, so we just map this range to the same number
-                mapped_end.push(e_r);
-                mapped_start.push(s_r);
+        let mut next_id: Option<IdNum> = Some(0);
+        for segment in inverted.iter() {
+            if next_id != Some(segment.src.get_start()) {
+                return None;
             }
+            next_id = segment.src.get_end().checked_add(1);
+        }
+        if next_id.is_some() {
+            return None;
         }
-        return (mapped_start, mapped_end);
+
+        return Some(RangeMap {
+            src: self.dst.clone(),
+            dst: self.src.clone(),
+            segments: normalize_segments(inverted),
+        });
+    }
+
+    // Maps nothing, for converting a category to itself.
+    fn identity(category: &str) -> RangeMap {
+        return RangeMap::new(vec![], vec![], vec![], category, category);
+    }
+}
+
+/// Problems turning one category into another.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AlmanacError {
+    /// A map header that isn't `<a>-to-<b> map:`.
+    BadHeader(String),
+    /// No map mentions this category.
+    UnknownCategory(String),
+    /// The maps never lead from one category to the other.
+    NoPath { from: String, to: String },
+    /// More than one chain of maps leads from one category to the other.
+    AmbiguousPath { from: String, to: String },
+}
+
+impl Display for AlmanacError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            AlmanacError::BadHeader(line) => write!(f, "bad map header: {}", line),
+            AlmanacError::UnknownCategory(name) => write!(f, "unknown category {}", name),
+            AlmanacError::NoPath { from, to } => write!(f, "no maps lead from {} to {}", from, to),
+            AlmanacError::AmbiguousPath { from, to } => {
+                write!(
+                    f,
+                    "more than one chain of maps leads from {} to {}",
+                    from, to
+                )
+            }
+        };
     }
 }
 
-// I would really like the type system to enforce this, but I can't think of a way that isn't super painful.
 #[derive(PartialEq, Debug, Clone)]
 struct Almanac {
-    to_plant: Vec<IdNum>,
-    seed_to_soil: RangeMap,
-    soil_to_fertilizer: RangeMap,
-    fertilizer_to_water: RangeMap,
-    water_to_light: RangeMap,
-    light_to_temperature: RangeMap,
-    temperature_to_humidity: RangeMap,
-    humidity_to_location: RangeMap,
+    seeds: Vec<IdNum>,
+    maps: Vec<RangeMap>,
+}
+
+impl Almanac {
+    fn has_category(&self, name: &str) -> bool {
+        return self.maps.iter().any(|m| m.src == name || m.dst == name);
+    }
+
+    // Depth first through the maps, stopping early once a second path is found.
+    fn find_paths(
+        &self,
+        current: &str,
+        to: &str,
+        visited: &mut Vec<String>,
+        path: &mut Vec<usize>,
+        found: &mut Vec<Vec<usize>>,
+    ) {
+        if found.len() > 1 {
+            return;
+        }
+        if current == to {
+            found.push(path.clone());
+            return;
+        }
+        visited.push(current.to_string());
+        for (index, map) in self.maps.iter().enumerate() {
+            if map.src != current || visited.contains(&map.dst) {
+                continue;
+            }
+            path.push(index);
+            self.find_paths(&map.dst, to, visited, path, found);
+            path.pop();
+        }
+        visited.pop();
+    }
+
+    // The single chain of map indexes leading from one category to another.
+    fn path(&self, from: &str, to: &str) -> Result<Vec<usize>, AlmanacError> {
+        for name in [from, to] {
+            if !self.has_category(name) {
+                return Err(AlmanacError::UnknownCategory(name.to_string()));
+            }
+        }
+        let mut found = vec![];
+        self.find_paths(from, to, &mut vec![], &mut vec![], &mut found);
+        return match found.len() {
+            0 => Err(AlmanacError::NoPath {
+                from: from.to_string(),
+                to: to.to_string(),
+            }),
+            1 => Ok(found.pop().unwrap()),
+            _ => Err(AlmanacError::AmbiguousPath {
+                from: from.to_string(),
+                to: to.to_string(),
+            }),
+        };
+    }
+
+    // All the maps along the path, composed into one.
+    fn converter(&self, from: &str, to: &str) -> Result<RangeMap, AlmanacError> {
+        let mut result = RangeMap::identity(from);
+        for index in self.path(from, to)? {
+            result = result.compose(&self.maps[index]);
+        }
+        return Ok(result);
+    }
+
+    fn seed_ranges(&self) -> Vec<Interval<IdNum>> {
+        return self
+            .seeds
+            .chunks(2)
+            .filter_map(|pair| Interval::from_len(pair[0], pair[1]))
+            .collect();
+    }
+}
+
+fn parse_header(line: &str) -> Result<(String, String), AlmanacError> {
+    let bad = || AlmanacError::BadHeader(line.to_string());
+    let names = line.strip_suffix(" map:").ok_or_else(bad)?;
+    let (src, dst) = names.split_once("-to-").ok_or_else(bad)?;
+    if src.is_empty() || dst.is_empty() {
+        return Err(bad());
+    }
+    return Ok((src.to_string(), dst.to_string()));
 }
 
-fn parse_almanac(string_list: &Vec<Vec<String>>) -> Almanac {
-    let mut to_plant: Vec<IdNum> = vec![];
-    let mut seed_to_soil: Option<RangeMap> = None;
-    let mut soil_to_fertilizer: Option<RangeMap> = None;
-    let mut fertilizer_to_water: Option<RangeMap> = None;
-    let mut water_to_light: Option<RangeMap> = None;
-    let mut light_to_temperature: Option<RangeMap> = None;
-    let mut temperature_to_humidity: Option<RangeMap> = None;
-    let mut humidity_to_location: Option<RangeMap> = None;
+fn parse_almanac(string_list: &Vec<Vec<String>>) -> Result<Almanac, AlmanacError> {
+    let mut seeds: Vec<IdNum> = vec![];
+    let mut maps: Vec<RangeMap> = vec![];
     for grouping in string_list {
         if grouping.len() == 0 {
             // Ignore anything empty, probably a loading issue
@@ -239,15 +378,12 @@ fn parse_almanac(string_list: &Vec<Vec<String>>) -> Almanac {
         let first_line = grouping.first().unwrap();
         if first_line.ends_with(":") {
             // We are in a section
+            let (src_type, dst_type) = parse_header(first_line)?;
             let mut src_ids: Vec<IdNum> = vec![];
             let mut dst_ids: Vec<IdNum> = vec![];
             let mut ranges: Vec<IdNum> = vec![];
 
-            // get all the numbers first.
-            for line in grouping {
-                if line == first_line {
-                    continue;
-                }
+            for line in grouping.iter().skip(1) {
                 let (dst, rest) = line.split_once(" ").unwrap();
                 let (src, range) = rest.split_once(" ").unwrap();
                 let dst_num: IdNum = dst.parse().unwrap();
@@ -257,65 +393,9 @@ fn parse_almanac(string_list: &Vec<Vec<String>>) -> Almanac {
                 dst_ids.push(dst_num);
                 ranges.push(range_num);
             }
-
-            // Now determine case
-            if first_line.starts_with("seed") {
-                seed_to_soil = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Seed,
-                    AlmanacItem::Soil,
-                ));
-            } else if first_line.starts_with("soil") {
-                soil_to_fertilizer = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Soil,
-                    AlmanacItem::Fertilizer,
-                ));
-            } else if first_line.starts_with("fertilizer") {
-                fertilizer_to_water = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Fertilizer,
-                    AlmanacItem::Water,
-                ));
-            } else if first_line.starts_with("water") {
-                water_to_light = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Water,
-                    AlmanacItem::Light,
-                ));
-            } else if first_line.starts_with("light") {
-                light_to_temperature = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Light,
-                    AlmanacItem::Temperature,
-                ));
-            } else if first_line.starts_with("temperature") {
-                temperature_to_humidity = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Temperature,
-                    AlmanacItem::Humidity,
-                ));
-            } else if first_line.starts_with("humidity") {
-                humidity_to_location = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Humidity,
-                    AlmanacItem::Location,
-                ));
-            }
+            maps.push(RangeMap::new(
+                src_ids, dst_ids, ranges, &src_type, &dst_type,
+            ));
         } else {
             // We are initial seeds
             let (_, seed_nums) = first_line.split_once("seeds: ").unwrap();
@@ -324,36 +404,41 @@ fn parse_almanac(string_list: &Vec<Vec<String>>) -> Almanac {
                     continue;
                 }
                 let as_num: IdNum = num.parse().unwrap();
-                to_plant.push(as_num);
+                seeds.push(as_num);
             }
         }
     }
 
-    return Almanac {
-        to_plant: to_plant,
-        seed_to_soil: seed_to_soil.unwrap(),
-        soil_to_fertilizer: soil_to_fertilizer.unwrap(),
-        fertilizer_to_water: fertilizer_to_water.unwrap(),
-        water_to_light: water_to_light.unwrap(),
-        light_to_temperature: light_to_temperature.unwrap(),
-        temperature_to_humidity: temperature_to_humidity.unwrap(),
-        humidity_to_location: humidity_to_location.unwrap(),
-    };
+    return Ok(Almanac {
+        seeds: seeds,
+        maps: maps,
+    });
 }
 
-fn almanac_to_locations(alm: &Almanac) -> Vec<IdNum> {
-    let mut result = vec![];
-    for seed in alm.to_plant.iter() {
-        let soil = alm.seed_to_soil.convert_id(*seed);
-        let fertilizer = alm.soil_to_fertilizer.convert_id(soil);
-        let water = alm.fertilizer_to_water.convert_id(fertilizer);
-        let light = alm.water_to_light.convert_id(water);
-        let temp = alm.light_to_temperature.convert_id(light);
-        let humidity = alm.temperature_to_humidity.convert_id(temp);
-        let location = alm.humidity_to_location.convert_id(humidity);
-        result.push(location);
-    }
-    return result;
+/// Convert an id of one category to another, following whatever maps connect them.
+/// ```
+/// use day05::AlmanacError;
+/// let input = day05::example_input();
+/// assert_eq!(day05::convert_id(&input, "seed", "soil", 79), Ok(81));
+/// assert_eq!(day05::convert_id(&input, "soil", "location", 81), Ok(82));
+/// assert_eq!(day05::convert_id(&input, "water", "water", 5), Ok(5));
+/// assert_eq!(
+///     day05::convert_id(&input, "location", "seed", 82),
+///     Err(AlmanacError::NoPath { from: "location".to_string(), to: "seed".to_string() })
+/// );
+/// assert_eq!(
+///     day05::convert_id(&input, "seed", "dirt", 82),
+///     Err(AlmanacError::UnknownCategory("dirt".to_string()))
+/// );
+/// ```
+pub fn convert_id(
+    string_list: &Vec<Vec<String>>,
+    from: &str,
+    to: &str,
+    id: IdNum,
+) -> Result<IdNum, AlmanacError> {
+    let alm = parse_almanac(string_list)?;
+    return Ok(alm.converter(from, to)?.convert_id(id));
 }
 
 /// Get lowest number location from this parsed nonsense.
@@ -362,249 +447,166 @@ fn almanac_to_locations(alm: &Almanac) -> Vec<IdNum> {
 /// assert_eq!(day05::puzzle_a(&input), 35);
 /// ```
 pub fn puzzle_a(string_list: &Vec<Vec<String>>) -> IdNum {
-    let alm = parse_almanac(string_list);
-    let locations = almanac_to_locations(&alm);
-    return *locations.iter().min().unwrap();
+    let alm = parse_almanac(string_list).unwrap();
+    let seed_to_location = alm.converter("seed", "location").unwrap();
+    return alm
+        .seeds
+        .iter()
+        .map(|seed| seed_to_location.convert_id(*seed))
+        .min()
+        .unwrap();
 }
 
-#[derive(PartialEq, Debug, Clone)]
-struct AlmanacRange {
-    to_plant_start: Vec<IdNum>,
-    to_plant_end: Vec<IdNum>,
-    seed_to_soil: RangeMap,
-    soil_to_fertilizer: RangeMap,
-    fertilizer_to_water: RangeMap,
-    water_to_light: RangeMap,
-    light_to_temperature: RangeMap,
-    temperature_to_humidity: RangeMap,
-    humidity_to_location: RangeMap,
+/// Find the lowest number when seeds are pairs of ranges.
+/// ```
+/// let input = day05::example_input();
+/// assert_eq!(day05::puzzle_b(&input), 46);
+/// ```
+pub fn puzzle_b(string_list: &Vec<Vec<String>>) -> IdNum {
+    let alm = parse_almanac(string_list).unwrap();
+    let seed_to_location = alm.converter("seed", "location").unwrap();
+    return alm
+        .seed_ranges()
+        .iter()
+        .map(|range| seed_to_location.min_in_range(*range))
+        .min()
+        .unwrap();
 }
 
-fn parse_almanac_range(string_list: &Vec<Vec<String>>) -> AlmanacRange {
-    let mut to_plant: Vec<IdNum> = vec![];
-    let mut to_plant_ends: Vec<IdNum> = vec![];
-    let mut seed_to_soil: Option<RangeMap> = None;
-    let mut soil_to_fertilizer: Option<RangeMap> = None;
-    let mut fertilizer_to_water: Option<RangeMap> = None;
-    let mut water_to_light: Option<RangeMap> = None;
-    let mut light_to_temperature: Option<RangeMap> = None;
-    let mut temperature_to_humidity: Option<RangeMap> = None;
-    let mut humidity_to_location: Option<RangeMap> = None;
-    for grouping in string_list {
-        if grouping.len() == 0 {
-            // Ignore anything empty, probably a loading issue
-            continue;
-        }
-        let first_line = grouping.first().unwrap();
-        if first_line.ends_with(":") {
-            // We are in a section
-            let mut src_ids: Vec<IdNum> = vec![];
-            let mut dst_ids: Vec<IdNum> = vec![];
-            let mut ranges: Vec<IdNum> = vec![];
+/// Work backwards from a location to the seed that ends up there.
+///
+/// None if the maps can't be reversed, which happens if two ids map to the same place.
+/// ```
+/// let input = day05::example_input();
+/// assert_eq!(day05::seed_for_location(&input, 35), Some(13));
+/// assert_eq!(day05::seed_for_location(&input, 46), Some(82));
+/// ```
+pub fn seed_for_location(string_list: &Vec<Vec<String>>, location: IdNum) -> Option<IdNum> {
+    let alm = parse_almanac(string_list).ok()?;
+    let location_to_seed = alm.converter("seed", "location").ok()?.invert()?;
+    return Some(location_to_seed.convert_id(location));
+}
 
-            // get all the numbers first.
-            for line in grouping {
-                if line == first_line {
-                    continue;
-                }
-                let (dst, rest) = line.split_once(" ").unwrap();
-                let (src, range) = rest.split_once(" ").unwrap();
-                let dst_num: IdNum = dst.parse().unwrap();
-                let src_num: IdNum = src.parse().unwrap();
-                let range_num: IdNum = range.parse().unwrap();
-                src_ids.push(src_num);
-                dst_ids.push(dst_num);
-                ranges.push(range_num);
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            // Now determine case
-            if first_line.starts_with("seed") {
-                seed_to_soil = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Seed,
-                    AlmanacItem::Soil,
-                ));
-            } else if first_line.starts_with("soil") {
-                soil_to_fertilizer = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Soil,
-                    AlmanacItem::Fertilizer,
-                ));
-            } else if first_line.starts_with("fertilizer") {
-                fertilizer_to_water = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Fertilizer,
-                    AlmanacItem::Water,
-                ));
-            } else if first_line.starts_with("water") {
-                water_to_light = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Water,
-                    AlmanacItem::Light,
-                ));
-            } else if first_line.starts_with("light") {
-                light_to_temperature = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Light,
-                    AlmanacItem::Temperature,
-                ));
-            } else if first_line.starts_with("temperature") {
-                temperature_to_humidity = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Temperature,
-                    AlmanacItem::Humidity,
-                ));
-            } else if first_line.starts_with("humidity") {
-                humidity_to_location = Some(RangeMap::new(
-                    src_ids,
-                    dst_ids,
-                    ranges,
-                    AlmanacItem::Humidity,
-                    AlmanacItem::Location,
-                ));
-            }
-        } else {
-            // We are initial seeds
-            let (_, seed_nums) = first_line.split_once("seeds: ").unwrap();
-            let mut range_start: Option<IdNum> = None;
-            for num in seed_nums.split(" ") {
-                if num.len() == 0 {
-                    continue;
-                }
-                let as_num: IdNum = num.parse().unwrap();
-                // initial condition
-                if range_start == None {
-                    range_start = Some(as_num);
-                    continue;
-                }
-                let range_end = range_start.unwrap() + as_num - 1;
-                to_plant.push(range_start.unwrap());
-                to_plant_ends.push(range_end);
-                range_start = None;
+    fn seed_to_soil() -> RangeMap {
+        return RangeMap::new(vec![98, 50], vec![50, 52], vec![2, 48], "seed", "soil");
+    }
+
+    #[test]
+    fn test_convert_id() {
+        let map = seed_to_soil();
+        assert_eq!(map.convert_id(0), 0);
+        assert_eq!(map.convert_id(49), 49);
+        assert_eq!(map.convert_id(50), 52);
+        assert_eq!(map.convert_id(97), 99);
+        assert_eq!(map.convert_id(98), 50);
+        assert_eq!(map.convert_id(99), 51);
+        assert_eq!(map.convert_id(100), 100);
+        assert_eq!(map.convert_id(IdNum::MAX), IdNum::MAX);
+    }
+
+    #[test]
+    fn test_segments_sorted_and_merged() {
+        // 10..20 -> 30..40 and 20..25 -> 40..45 are one shift
+        let map = RangeMap::new(vec![20, 10], vec![40, 30], vec![5, 10], "seed", "soil");
+        assert_eq!(
+            map.segments,
+            vec![
+                Segment::identity(Interval::new(0, 9)),
+                Segment {
+                    src: Interval::new(10, 24),
+                    dst_start: 30
+                },
+                Segment::identity(Interval::new(25, IdNum::MAX)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compose_matches_sequence() {
+        let alm = parse_almanac(&example_input()).unwrap();
+        let composed = alm.converter("seed", "location").unwrap();
+        for seed in 0..120 {
+            let mut id = seed;
+            for map in alm.maps.iter() {
+                id = map.convert_id(id);
             }
+            assert_eq!(composed.convert_id(seed), id);
         }
     }
 
-    return AlmanacRange {
-        to_plant_start: to_plant,
-        to_plant_end: to_plant_ends,
-        seed_to_soil: seed_to_soil.unwrap(),
-        soil_to_fertilizer: soil_to_fertilizer.unwrap(),
-        fertilizer_to_water: fertilizer_to_water.unwrap(),
-        water_to_light: water_to_light.unwrap(),
-        light_to_temperature: light_to_temperature.unwrap(),
-        temperature_to_humidity: temperature_to_humidity.unwrap(),
-        humidity_to_location: humidity_to_location.unwrap(),
-    };
-}
+    #[test]
+    fn test_invert() {
+        let map = seed_to_soil();
+        let inverse = map.invert().unwrap();
+        assert_eq!(inverse.src, "soil");
+        for id in 0..120 {
+            assert_eq!(inverse.convert_id(map.convert_id(id)), id);
+        }
+        // 0..10 and 10..20 both land on 0..10
+        let squashed = RangeMap::new(vec![10], vec![0], vec![10], "seed", "soil");
+        assert_eq!(squashed.invert(), None);
+    }
 
-/// Find the lowest number when seeds are pairs of ranges.
-/// ```
-/// let input = day05::example_input();
-/// assert_eq!(day05::puzzle_b(&input), 46);
-/// ```
-pub fn puzzle_b(string_list: &Vec<Vec<String>>) -> IdNum {
-    let alm = parse_almanac_range(string_list);
-    let mut current_range_starts: Vec<IdNum> = alm.to_plant_start.clone();
-    let mut current_range_ends: Vec<IdNum> = alm.to_plant_end.clone();
-    let mut next_range_s = vec![];
-    let mut next_range_e = vec![];
-
-    while let Some(cur_start) = current_range_starts.pop() {
-        let cur_end = current_range_ends.pop().unwrap();
-        let (next_starts, next_ends) = alm.seed_to_soil.convert_range(cur_start, cur_end);
-        assert_eq!(next_starts.len(), next_ends.len());
-        next_range_s.extend(next_starts);
-        next_range_e.extend(next_ends);
-    }
-    current_range_starts = next_range_s;
-    current_range_ends = next_range_e;
-    next_range_s = vec![];
-    next_range_e = vec![];
-
-    while let Some(cur_start) = current_range_starts.pop() {
-        let cur_end = current_range_ends.pop().unwrap();
-        let (next_starts, next_ends) = alm.soil_to_fertilizer.convert_range(cur_start, cur_end);
-        assert_eq!(next_starts.len(), next_ends.len());
-        next_range_s.extend(next_starts);
-        next_range_e.extend(next_ends);
-    }
-    current_range_starts = next_range_s;
-    current_range_ends = next_range_e;
-    next_range_s = vec![];
-    next_range_e = vec![];
-
-    while let Some(cur_start) = current_range_starts.pop() {
-        let cur_end = current_range_ends.pop().unwrap();
-        let (next_starts, next_ends) = alm.fertilizer_to_water.convert_range(cur_start, cur_end);
-        assert_eq!(next_starts.len(), next_ends.len());
-        next_range_s.extend(next_starts);
-        next_range_e.extend(next_ends);
-    }
-    current_range_starts = next_range_s;
-    current_range_ends = next_range_e;
-    next_range_s = vec![];
-    next_range_e = vec![];
-
-    while let Some(cur_start) = current_range_starts.pop() {
-        let cur_end = current_range_ends.pop().unwrap();
-        let (next_starts, next_ends) = alm.water_to_light.convert_range(cur_start, cur_end);
-        assert_eq!(next_starts.len(), next_ends.len());
-        next_range_s.extend(next_starts);
-        next_range_e.extend(next_ends);
-    }
-    current_range_starts = next_range_s;
-    current_range_ends = next_range_e;
-    next_range_s = vec![];
-    next_range_e = vec![];
-
-    while let Some(cur_start) = current_range_starts.pop() {
-        let cur_end = current_range_ends.pop().unwrap();
-        let (next_starts, next_ends) = alm.light_to_temperature.convert_range(cur_start, cur_end);
-        assert_eq!(next_starts.len(), next_ends.len());
-        next_range_s.extend(next_starts);
-        next_range_e.extend(next_ends);
-    }
-    current_range_starts = next_range_s;
-    current_range_ends = next_range_e;
-    next_range_s = vec![];
-    next_range_e = vec![];
-
-    while let Some(cur_start) = current_range_starts.pop() {
-        let cur_end = current_range_ends.pop().unwrap();
-        let (next_starts, next_ends) = alm
-            .temperature_to_humidity
-            .convert_range(cur_start, cur_end);
-        assert_eq!(next_starts.len(), next_ends.len());
-        next_range_s.extend(next_starts);
-        next_range_e.extend(next_ends);
-    }
-    current_range_starts = next_range_s;
-    current_range_ends = next_range_e;
-    next_range_s = vec![];
-    next_range_e = vec![];
-
-    while let Some(cur_start) = current_range_starts.pop() {
-        let cur_end = current_range_ends.pop().unwrap();
-        let (next_starts, next_ends) = alm.humidity_to_location.convert_range(cur_start, cur_end);
-        assert_eq!(next_starts.len(), next_ends.len());
-        next_range_s.extend(next_starts);
-        next_range_e.extend(next_ends);
-    }
-    current_range_starts = next_range_s;
-
-    // The starts are lower then the ends, so we can just return the lowest start.
-    return *current_range_starts.iter().min().unwrap();
+    #[test]
+    fn test_min_in_range() {
+        let map = seed_to_soil();
+        assert_eq!(map.min_in_range(Interval::new(60, 99)), 50);
+        assert_eq!(map.min_in_range(Interval::new(40, 60)), 40);
+        assert_eq!(map.min_in_range(Interval::new(55, 60)), 57);
+    }
+
+    fn to_groups(groups: Vec<Vec<&str>>) -> Vec<Vec<String>> {
+        return groups
+            .iter()
+            .map(|g| g.iter().map(|s| s.to_string()).collect())
+            .collect();
+    }
+
+    #[test]
+    fn test_maps_out_of_order() {
+        let input = to_groups(vec![
+            vec!["seeds: 1 5"],
+            vec!["b-to-c map:", "100 0 10"],
+            vec!["a-to-b map:", "0 1 1"],
+        ]);
+        assert_eq!(convert_id(&input, "a", "c", 1), Ok(100));
+        assert_eq!(convert_id(&input, "a", "c", 5), Ok(105));
+    }
+
+    #[test]
+    fn test_ambiguous_path() {
+        let input = to_groups(vec![
+            vec!["seeds: 1"],
+            vec!["a-to-b map:", "0 1 1"],
+            vec!["b-to-c map:", "100 0 10"],
+            vec!["a-to-c map:", "100 0 10"],
+        ]);
+        let error = convert_id(&input, "a", "c", 1).unwrap_err();
+        assert_eq!(
+            error,
+            AlmanacError::AmbiguousPath {
+                from: "a".to_string(),
+                to: "c".to_string()
+            }
+        );
+        assert_eq!(
+            format!("{}", error),
+            "more than one chain of maps leads from a to c"
+        );
+        // Only one way from b though.
+        assert_eq!(convert_id(&input, "b", "c", 1), Ok(101));
+    }
+
+    #[test]
+    fn test_bad_header() {
+        let input = to_groups(vec![vec!["seeds: 1"], vec!["a to b map:", "0 1 1"]]);
+        assert_eq!(
+            parse_almanac(&input),
+            Err(AlmanacError::BadHeader("a to b map:".to_string()))
+        );
+    }
 }
@@ -3,52 +3,11 @@ extern crate filelib;
 pub use filelib::load_no_blanks;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-
-// Sorts such that Ace comes first.
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, PartialEq, Eq, Hash)]
-enum CamelCard {
-    Ace,
-    King,
-    Queen,
-    Jack,
-    Ten,
-    Nine,
-    Eight,
-    Seven,
-    Six,
-    Five,
-    Four,
-    Three,
-    Two,
-    Joker,
-}
-
-fn card_from_char(c: char, with_joker: bool) -> CamelCard {
-    let mut j_card = CamelCard::Jack;
-    if with_joker {
-        j_card = CamelCard::Joker;
-    }
-    return match c {
-        'A' => CamelCard::Ace,
-        'K' => CamelCard::King,
-        'Q' => CamelCard::Queen,
-        'J' => j_card,
-        'T' => CamelCard::Ten,
-        '9' => CamelCard::Nine,
-        '8' => CamelCard::Eight,
-        '7' => CamelCard::Seven,
-        '6' => CamelCard::Six,
-        '5' => CamelCard::Five,
-        '4' => CamelCard::Four,
-        '3' => CamelCard::Three,
-        '2' => CamelCard::Two,
-        _ => panic!("Bad character passed in"),
-    };
-}
+use std::collections::HashSet;
 
 // Sorts such that Five of a kind comes first
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, PartialEq, Eq)]
-enum HandType {
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, PartialEq, Eq, Hash)]
+pub enum HandType {
     FiveOfKind,
     FourOfKind,
     FullHouse,
@@ -58,93 +17,217 @@ enum HandType {
     HighCard,
 }
 
-fn find_hand_type(cards: &Vec<CamelCard>) -> HandType {
-    let mut seen: HashMap<CamelCard, u8> = HashMap::new();
-    let mut jokers = 0;
-    for card in cards {
-        if *card == CamelCard::Joker {
-            jokers += 1;
-            continue;
-        }
-        *seen.entry(*card).or_insert(0_u8) += 1_u8;
+impl HandType {
+    /// Every hand type, best first.
+    pub fn all() -> Vec<HandType> {
+        return vec![
+            HandType::FiveOfKind,
+            HandType::FourOfKind,
+            HandType::FullHouse,
+            HandType::ThreeOfKind,
+            HandType::TwoPair,
+            HandType::OnePair,
+            HandType::HighCard,
+        ];
     }
-    if jokers == 0 {
-        if seen.len() == 1 {
-            // Must be five of a kind
-            return HandType::FiveOfKind;
+
+    // Sizes of the groups of matching cards, largest first.
+    fn groups(&self) -> Vec<usize> {
+        return match self {
+            HandType::FiveOfKind => vec![5],
+            HandType::FourOfKind => vec![4, 1],
+            HandType::FullHouse => vec![3, 2],
+            HandType::ThreeOfKind => vec![3, 1, 1],
+            HandType::TwoPair => vec![2, 2, 1],
+            HandType::OnePair => vec![2, 1, 1, 1],
+            HandType::HighCard => vec![1, 1, 1, 1, 1],
+        };
+    }
+}
+
+/// How to order hands of the same type.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tiebreak {
+    /// Compare the first card, then the second, and so on, as dealt.
+    DealtOrder,
+    /// Compare the best card, then the next best, and so on.
+    BestCard,
+}
+
+/// Everything needed to rank hands in a game of Camel Cards, or a variant of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandRules {
+    // Strongest first
+    rank_order: Vec<char>,
+    wildcards: HashSet<char>,
+    // Best first, a hand is the first one it can make.
+    categories: Vec<HandType>,
+    tiebreak: Tiebreak,
+}
+
+impl HandRules {
+    /// `rank_order` lists every card, strongest first, wildcards included at their tiebreak strength.
+    ///
+    /// A hand takes the first of `categories` it can make, or ranks below all of them if none.
+    /// Larger groups can stand in for smaller ones, so with FiveOfKind left out, AAAAA is a FourOfKind.
+    pub fn new(
+        rank_order: &str,
+        wildcards: &str,
+        categories: Vec<HandType>,
+        tiebreak: Tiebreak,
+    ) -> HandRules {
+        let wildcard_set: HashSet<char> = wildcards.chars().collect();
+        for c in wildcard_set.iter() {
+            assert!(rank_order.contains(*c), "Wildcard {} has no rank", c);
         }
-        if seen.len() == 2 {
-            // Either four of a kind, or full house.
-            let first_value = seen.values().nth(0).unwrap();
-            if *first_value == 1_u8 || *first_value == 4_u8 {
-                return HandType::FourOfKind;
+        return HandRules {
+            rank_order: rank_order.chars().collect(),
+            wildcards: wildcard_set,
+            categories: categories,
+            tiebreak: tiebreak,
+        };
+    }
+
+    /// The rules for part a.
+    pub fn standard() -> HandRules {
+        return HandRules::new("AKQJT98765432", "", HandType::all(), Tiebreak::DealtOrder);
+    }
+
+    /// The rules for part b, J is a wildcard but the weakest card for ties.
+    pub fn with_jokers() -> HandRules {
+        return HandRules::new("AKQT98765432J", "J", HandType::all(), Tiebreak::DealtOrder);
+    }
+
+    // 0 is the strongest card
+    fn rank(&self, c: char) -> usize {
+        return self
+            .rank_order
+            .iter()
+            .position(|r| *r == c)
+            .expect("Bad character passed in");
+    }
+
+    /// Best hand type these cards can make, None if they make none of the categories.
+    /// ```
+    /// use day07::{HandRules, HandType, Tiebreak};
+    /// let jokers = HandRules::with_jokers();
+    /// assert_eq!(jokers.classify("QJJQ2"), Some(HandType::FourOfKind));
+    /// let no_five = HandRules::new(
+    ///     "AKQJT98765432",
+    ///     "J",
+    ///     vec![HandType::FourOfKind, HandType::FullHouse, HandType::ThreeOfKind],
+    ///     Tiebreak::DealtOrder,
+    /// );
+    /// assert_eq!(no_five.classify("AAAAA"), Some(HandType::FourOfKind));
+    /// assert_eq!(no_five.classify("AKQ32"), None);
+    /// ```
+    pub fn classify(&self, cards: &str) -> Option<HandType> {
+        let mut seen: HashMap<char, usize> = HashMap::new();
+        let mut wildcards = 0;
+        for card in cards.chars() {
+            // Make sure every card is known, even if it's wild.
+            self.rank(card);
+            if self.wildcards.contains(&card) {
+                wildcards += 1;
+                continue;
             }
-            return HandType::FullHouse;
+            *seen.entry(card).or_insert(0) += 1;
         }
-        if seen.len() == 3 {
-            // Two pair, three of a kind
-            // we can tell by looking for a value of three
-            if seen
-                .values()
-                .filter(|&x| *x == 3_u8)
-                .collect::<Vec<_>>()
-                .len()
-                == 1
-            {
-                return HandType::ThreeOfKind;
+        let candidates = wildcard_groups(seen.values().copied().collect(), wildcards);
+        for category in self.categories.iter() {
+            let wanted = category.groups();
+            if candidates.iter().any(|c| can_split_into(c, &wanted)) {
+                return Some(*category);
             }
-            return HandType::TwoPair;
         }
-        if seen.len() == 4 {
-            // One pair
-            return HandType::OnePair;
+        return None;
+    }
+
+    fn parse_hand(&self, line: &str) -> Hand {
+        let (cards_to_parse, value) = line.split_once(" ").unwrap();
+        let bid: u32 = value.trim().parse().unwrap();
+        let hand_type = self.classify(cards_to_parse);
+        let strength = match hand_type {
+            Some(t) => self.categories.iter().position(|c| *c == t).unwrap(),
+            None => self.categories.len(),
+        };
+        let mut tiebreak: Vec<usize> = cards_to_parse.chars().map(|c| self.rank(c)).collect();
+        if self.tiebreak == Tiebreak::BestCard {
+            tiebreak.sort();
+        }
+        return Hand {
+            bid: bid,
+            hand_type: hand_type,
+            strength: strength,
+            tiebreak: tiebreak,
+        };
+    }
+}
+
+// Every way of adding wildcards to the groups of matching cards, groups sorted largest first.
+fn wildcard_groups(groups: Vec<usize>, wildcards: usize) -> Vec<Vec<usize>> {
+    if wildcards == 0 {
+        let mut sorted = groups;
+        sorted.sort_by(|a, b| b.cmp(a));
+        return vec![sorted];
+    }
+    let mut result: Vec<Vec<usize>> = vec![];
+    // Join an existing group, or start a new one.
+    for index in 0..=groups.len() {
+        let mut next = groups.clone();
+        if index == groups.len() {
+            next.push(1);
+        } else {
+            next[index] += 1;
         }
-        return HandType::HighCard;
-    }
-
-    // With jokers logic
-    if seen.len() == 1 {
-        // all jokers just add to five of a kind.
-        return HandType::FiveOfKind;
-    }
-    if seen.len() == 2 {
-        // Four scenarios:
-        // JJJAB -> Four of kind
-        // JJAAB -> Four of kind
-        // JAABB -> FullHouse
-        // JAAAB -> Four of kind
-        // This becomes four of a kind unless both values equal and equal to 2.
-        let first_value = seen.values().nth(0).unwrap();
-        let second_value = seen.values().nth(1).unwrap();
-        if *first_value == *second_value && *first_value == 2_u8 {
-            return HandType::FullHouse;
+        for candidate in wildcard_groups(next, wildcards - 1) {
+            if !result.contains(&candidate) {
+                result.push(candidate);
+            }
         }
-        return HandType::FourOfKind;
     }
-    if seen.len() == 3 {
-        // JJABC -> Three of a kind, since its better than two pair.
-        // JAABC -> Three of a kind
-        // Just get highest count.
-        return HandType::ThreeOfKind;
+    return result;
+}
+
+// Can the groups we have be broken up to make exactly the groups we want?
+fn can_split_into(have: &Vec<usize>, want: &Vec<usize>) -> bool {
+    if have.iter().sum::<usize>() != want.iter().sum::<usize>() {
+        return false;
+    }
+    let mut remaining = have.clone();
+    return fill_groups(&mut remaining, want);
+}
+
+fn fill_groups(remaining: &mut Vec<usize>, want: &[usize]) -> bool {
+    if want.is_empty() {
+        return true;
     }
-    if seen.len() == 4 {
-        // JABCD -> One pair
-        return HandType::OnePair;
+    for index in 0..remaining.len() {
+        if remaining[index] >= want[0] {
+            remaining[index] -= want[0];
+            let found = fill_groups(remaining, &want[1..]);
+            remaining[index] += want[0];
+            if found {
+                return true;
+            }
+        }
     }
-    // Must be five jokers
-    return HandType::FiveOfKind;
+    return false;
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Hand {
     bid: u32,
-    cards: Vec<CamelCard>,
-    hand_type: HandType,
+    hand_type: Option<HandType>,
+    // Position of the hand type in the rules, 0 is best
+    strength: usize,
+    // Card ranks to compare on a tie, 0 is best
+    tiebreak: Vec<usize>,
 }
 
 impl Ord for Hand {
     fn cmp(&self, other: &Self) -> Ordering {
-        (self.hand_type, self.cards.clone()).cmp(&(other.hand_type, other.cards.clone()))
+        (self.strength, &self.tiebreak).cmp(&(other.strength, &other.tiebreak))
     }
 }
 
@@ -154,32 +237,17 @@ impl PartialOrd for Hand {
     }
 }
 
-fn parse_hands(string_list: &Vec<String>, joker: bool) -> Vec<Hand> {
-    let mut result: Vec<Hand> = vec![];
-    for line in string_list {
-        let (cards_to_parse, value) = line.split_once(" ").unwrap();
-        let bid: u32 = value.trim().parse().unwrap();
-        let cards: Vec<CamelCard> = cards_to_parse
-            .chars()
-            .map(|c| card_from_char(c, joker))
-            .collect();
-        let hand_type = find_hand_type(&cards);
-        let hand = Hand {
-            bid: bid,
-            cards: cards,
-            hand_type: hand_type,
-        };
-        result.push(hand);
-    }
-    return result;
+fn parse_hands(string_list: &Vec<String>, rules: &HandRules) -> Vec<Hand> {
+    return string_list.iter().map(|l| rules.parse_hand(l)).collect();
 }
 
 fn usize_to_u32(i: usize) -> u32 {
     return i.try_into().unwrap();
 }
 
-/// Order the hands by their rank, then multiply with their bid. Rank is determined by strength.
+/// Order the hands by their rank under the given rules, then multiply with their bid.
 /// ```
+/// use day07::{HandRules, HandType, Tiebreak};
 /// let vec1: Vec<String> = vec![
 ///     "32T3K 765",
 ///     "T55J5 684",
@@ -187,10 +255,13 @@ fn usize_to_u32(i: usize) -> u32 {
 ///     "KTJJT 220",
 ///     "QQQJA 483"
 /// ].iter().map(|s| s.to_string()).collect();
-/// assert_eq!(day07::puzzle_a(&vec1), 6440);
+/// assert_eq!(day07::total_winnings(&vec1, &HandRules::standard()), 6440);
+/// // Best card first: KK776 beats KTJJT, but T555J loses to QQQJA.
+/// let best_card = HandRules::new("AKQJT98765432", "", HandType::all(), Tiebreak::BestCard);
+/// assert_eq!(day07::total_winnings(&vec1, &best_card), 765 + 220 * 2 + 28 * 3 + 684 * 4 + 483 * 5);
 /// ```
-pub fn puzzle_a(string_list: &Vec<String>) -> u32 {
-    let mut hands = parse_hands(string_list, false);
+pub fn total_winnings(string_list: &Vec<String>, rules: &HandRules) -> u32 {
+    let mut hands = parse_hands(string_list, rules);
     hands.sort();
     return hands
         .iter()
@@ -200,6 +271,21 @@ pub fn puzzle_a(string_list: &Vec<String>) -> u32 {
         .sum();
 }
 
+/// Order the hands by their rank, then multiply with their bid. Rank is determined by strength.
+/// ```
+/// let vec1: Vec<String> = vec![
+///     "32T3K 765",
+///     "T55J5 684",
+///     "KK677 28",
+///     "KTJJT 220",
+///     "QQQJA 483"
+/// ].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(day07::puzzle_a(&vec1), 6440);
+/// ```
+pub fn puzzle_a(string_list: &Vec<String>) -> u32 {
+    return total_winnings(string_list, &HandRules::standard());
+}
+
 /// As 1, but parse as jokers instead.
 /// ```
 /// let vec1: Vec<String> = vec![
@@ -212,15 +298,7 @@ pub fn puzzle_a(string_list: &Vec<String>) -> u32 {
 /// assert_eq!(day07::puzzle_b(&vec1), 5905);
 /// ```
 pub fn puzzle_b(string_list: &Vec<String>) -> u32 {
-    let mut hands = parse_hands(string_list, true);
-    hands.sort();
-    println!("{:#?}", hands);
-    return hands
-        .iter()
-        .rev()
-        .enumerate()
-        .map(|(index, hand)| usize_to_u32(index + 1) * hand.bid)
-        .sum();
+    return total_winnings(string_list, &HandRules::with_jokers());
     // Answer should be 243101568
 }
 
@@ -228,329 +306,172 @@ pub fn puzzle_b(string_list: &Vec<String>) -> u32 {
 mod tests {
     use super::*;
 
+    fn to_lines(lines: Vec<&str>) -> Vec<String> {
+        return lines.iter().map(|s| s.to_string()).collect();
+    }
+
     #[test]
     fn tst_parse() {
-        let vec1: Vec<String> = vec!["QQQJA 483"].iter().map(|s| s.to_string()).collect();
-        let hands = parse_hands(&vec1, false);
+        let vec1 = to_lines(vec!["QQQJA 483"]);
+        let hands = parse_hands(&vec1, &HandRules::standard());
         let hand = hands.first().unwrap();
-        assert_eq!(hand.hand_type, HandType::ThreeOfKind);
+        assert_eq!(hand.hand_type, Some(HandType::ThreeOfKind));
         assert_eq!(hand.bid, 483);
     }
 
     #[test]
     fn tst_parse_joker() {
-        let vec1: Vec<String> = vec!["QQQJA 483"].iter().map(|s| s.to_string()).collect();
-        let hands = parse_hands(&vec1, true);
+        let vec1 = to_lines(vec!["QQQJA 483"]);
+        let hands = parse_hands(&vec1, &HandRules::with_jokers());
         let hand = hands.first().unwrap();
-        assert_eq!(hand.hand_type, HandType::FourOfKind);
+        assert_eq!(hand.hand_type, Some(HandType::FourOfKind));
         assert_eq!(hand.bid, 483);
     }
 
     #[test]
     fn tst_card_type_four_joker() {
-        let hand = vec![
-            CamelCard::Three,
-            CamelCard::Joker,
-            CamelCard::Joker,
-            CamelCard::Joker,
-            CamelCard::Joker,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::FiveOfKind);
+        let rules = HandRules::with_jokers();
+        assert_eq!(rules.classify("3JJJJ"), Some(HandType::FiveOfKind));
     }
 
     #[test]
     fn tst_card_type_three_joker() {
-        let hand = vec![
-            CamelCard::Three,
-            CamelCard::Two,
-            CamelCard::Joker,
-            CamelCard::Joker,
-            CamelCard::Joker,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::FourOfKind);
+        let rules = HandRules::with_jokers();
+        assert_eq!(rules.classify("32JJJ"), Some(HandType::FourOfKind));
     }
 
+    #[test]
     fn tst_card_type_three_joker_to_five() {
-        let hand = vec![
-            CamelCard::Three,
-            CamelCard::Three,
-            CamelCard::Joker,
-            CamelCard::Joker,
-            CamelCard::Joker,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::FiveOfKind);
+        let rules = HandRules::with_jokers();
+        assert_eq!(rules.classify("33JJJ"), Some(HandType::FiveOfKind));
     }
 
     #[test]
     fn tst_card_type_two_joker_to_four() {
-        let hand = vec![
-            CamelCard::Three,
-            CamelCard::Two,
-            CamelCard::Three,
-            CamelCard::Joker,
-            CamelCard::Joker,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::FourOfKind);
+        let rules = HandRules::with_jokers();
+        assert_eq!(rules.classify("323JJ"), Some(HandType::FourOfKind));
     }
 
     #[test]
     fn tst_card_type_two_joker_to_three() {
-        let hand = vec![
-            CamelCard::Three,
-            CamelCard::Two,
-            CamelCard::Four,
-            CamelCard::Joker,
-            CamelCard::Joker,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::ThreeOfKind);
+        let rules = HandRules::with_jokers();
+        assert_eq!(rules.classify("324JJ"), Some(HandType::ThreeOfKind));
     }
 
     #[test]
     fn tst_card_type_one_joker_to_full() {
-        let hand = vec![
-            CamelCard::Three,
-            CamelCard::Two,
-            CamelCard::Three,
-            CamelCard::Two,
-            CamelCard::Joker,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::FullHouse);
+        let rules = HandRules::with_jokers();
+        assert_eq!(rules.classify("3232J"), Some(HandType::FullHouse));
     }
 
     #[test]
     fn tst_card_type_one_joker_to_pair() {
-        let hand = vec![
-            CamelCard::Three,
-            CamelCard::Two,
-            CamelCard::Four,
-            CamelCard::Five,
-            CamelCard::Joker,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::OnePair);
+        let rules = HandRules::with_jokers();
+        assert_eq!(rules.classify("3245J"), Some(HandType::OnePair));
     }
 
     #[test]
     fn tst_card_type_high_card() {
-        let hand = vec![
-            CamelCard::Three,
-            CamelCard::Two,
-            CamelCard::Four,
-            CamelCard::Five,
-            CamelCard::Nine,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::HighCard);
+        let rules = HandRules::standard();
+        assert_eq!(rules.classify("32459"), Some(HandType::HighCard));
     }
 
     #[test]
     fn tst_card_type_plain_four() {
-        let hand = vec![
-            CamelCard::Three,
-            CamelCard::Three,
-            CamelCard::Three,
-            CamelCard::Three,
-            CamelCard::Nine,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::FourOfKind);
+        let rules = HandRules::standard();
+        assert_eq!(rules.classify("33339"), Some(HandType::FourOfKind));
     }
 
     #[test]
     fn tst_card_type_plain_five() {
-        let hand = vec![
-            CamelCard::Three,
-            CamelCard::Three,
-            CamelCard::Three,
-            CamelCard::Three,
-            CamelCard::Three,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::FiveOfKind);
+        let rules = HandRules::standard();
+        assert_eq!(rules.classify("33333"), Some(HandType::FiveOfKind));
     }
 
     #[test]
     fn tst_card_type_fullhouse() {
-        let hand = vec![
-            CamelCard::Three,
-            CamelCard::Three,
-            CamelCard::Three,
-            CamelCard::Two,
-            CamelCard::Two,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::FullHouse);
+        let rules = HandRules::standard();
+        assert_eq!(rules.classify("33322"), Some(HandType::FullHouse));
+        assert_eq!(rules.classify("2J3J3"), Some(HandType::TwoPair));
     }
 
     #[test]
     fn tst_card_type_five_jokers() {
-        let hand = vec![
-            CamelCard::Joker,
-            CamelCard::Joker,
-            CamelCard::Joker,
-            CamelCard::Joker,
-            CamelCard::Joker,
-        ];
-        assert_eq!(find_hand_type(&hand), HandType::FiveOfKind);
+        let rules = HandRules::with_jokers();
+        assert_eq!(rules.classify("JJJJJ"), Some(HandType::FiveOfKind));
     }
 
     #[test]
-    fn tst_sort() {
-        let hand_one = Hand {
-            bid: 765,
-            cards: vec![
-                CamelCard::Three,
-                CamelCard::Two,
-                CamelCard::Ten,
-                CamelCard::Three,
-                CamelCard::King,
-            ],
-            hand_type: HandType::OnePair,
-        };
-
-        let hand_two = Hand {
-            bid: 684,
-            cards: vec![
-                CamelCard::Ten,
-                CamelCard::Five,
-                CamelCard::Five,
-                CamelCard::Jack,
-                CamelCard::Five,
+    fn tst_card_type_no_four_prefers_full_house() {
+        // Both jokers on the pair would be four of a kind, which isn't allowed.
+        let rules = HandRules::new(
+            "AKQT98765432J",
+            "J",
+            vec![
+                HandType::FiveOfKind,
+                HandType::FullHouse,
+                HandType::ThreeOfKind,
             ],
-            hand_type: HandType::ThreeOfKind,
-        };
-
-        let hand_three = Hand {
-            bid: 28,
-            cards: vec![
-                CamelCard::King,
-                CamelCard::King,
-                CamelCard::Six,
-                CamelCard::Seven,
-                CamelCard::Seven,
-            ],
-            hand_type: HandType::TwoPair,
-        };
-
-        let hand_four = Hand {
-            bid: 220,
-            cards: vec![
-                CamelCard::King,
-                CamelCard::Ten,
-                CamelCard::Jack,
-                CamelCard::Jack,
-                CamelCard::Ten,
-            ],
-            hand_type: HandType::TwoPair,
-        };
-
-        let hand_five = Hand {
-            bid: 483,
-            cards: vec![
-                CamelCard::Queen,
-                CamelCard::Queen,
-                CamelCard::Queen,
-                CamelCard::Jack,
-                CamelCard::Ace,
-            ],
-            hand_type: HandType::ThreeOfKind,
-        };
+            Tiebreak::DealtOrder,
+        );
+        assert_eq!(rules.classify("AAKJJ"), Some(HandType::FullHouse));
+    }
 
-        let mut hands = vec![
-            hand_one.clone(),
-            hand_two.clone(),
-            hand_three.clone(),
-            hand_four.clone(),
-            hand_five.clone(),
-        ];
+    #[test]
+    fn tst_sort() {
+        let vec1 = to_lines(vec![
+            "32T3K 765",
+            "T55J5 684",
+            "KK677 28",
+            "KTJJT 220",
+            "QQQJA 483",
+        ]);
+        let mut hands = parse_hands(&vec1, &HandRules::standard());
         hands.sort();
-        assert_eq!(
-            hands,
-            vec![hand_five, hand_two, hand_three, hand_four, hand_one]
-        )
+        let bids: Vec<u32> = hands.iter().map(|h| h.bid).collect();
+        assert_eq!(bids, vec![483, 684, 28, 220, 765]);
     }
 
     #[test]
     fn tst_sort_joker() {
-        let hand_one = Hand {
-            bid: 765,
-            cards: vec![
-                CamelCard::Three,
-                CamelCard::Two,
-                CamelCard::Ten,
-                CamelCard::Three,
-                CamelCard::King,
-            ],
-            hand_type: HandType::OnePair,
-        };
-
-        let hand_two = Hand {
-            bid: 684,
-            cards: vec![
-                CamelCard::Ten,
-                CamelCard::Five,
-                CamelCard::Five,
-                CamelCard::Joker,
-                CamelCard::Five,
-            ],
-            hand_type: HandType::FourOfKind,
-        };
-
-        let hand_three = Hand {
-            bid: 28,
-            cards: vec![
-                CamelCard::King,
-                CamelCard::King,
-                CamelCard::Six,
-                CamelCard::Seven,
-                CamelCard::Seven,
-            ],
-            hand_type: HandType::TwoPair,
-        };
-
-        let hand_four = Hand {
-            bid: 220,
-            cards: vec![
-                CamelCard::King,
-                CamelCard::Ten,
-                CamelCard::Joker,
-                CamelCard::Joker,
-                CamelCard::Ten,
-            ],
-            hand_type: HandType::FourOfKind,
-        };
-
-        let hand_five = Hand {
-            bid: 483,
-            cards: vec![
-                CamelCard::Queen,
-                CamelCard::Queen,
-                CamelCard::Queen,
-                CamelCard::Joker,
-                CamelCard::Ace,
-            ],
-            hand_type: HandType::FourOfKind,
-        };
+        let vec1 = to_lines(vec![
+            "32T3K 765",
+            "T55J5 684",
+            "KK677 28",
+            "KTJJT 220",
+            "QQQJA 483",
+        ]);
+        let mut hands = parse_hands(&vec1, &HandRules::with_jokers());
+        hands.sort();
+        let bids: Vec<u32> = hands.iter().map(|h| h.bid).collect();
+        assert_eq!(bids, vec![220, 483, 684, 28, 765]);
+    }
 
-        let mut hands = vec![
-            hand_one.clone(),
-            hand_two.clone(),
-            hand_three.clone(),
-            hand_four.clone(),
-            hand_five.clone(),
-        ];
+    #[test]
+    fn tst_unranked_hands_sort_last() {
+        let rules = HandRules::new(
+            "AKQJT98765432",
+            "",
+            vec![HandType::OnePair],
+            Tiebreak::DealtOrder,
+        );
+        let mut hands = parse_hands(&to_lines(vec!["AKQJT 1", "22345 2"]), &rules);
         hands.sort();
-        assert_eq!(
-            hands,
-            vec![hand_four, hand_five, hand_two, hand_three, hand_one]
-        )
+        assert_eq!(hands[0].bid, 2);
+        assert_eq!(hands[1].hand_type, None);
     }
 
     #[test]
     fn additional_char_scenarios() {
-        assert_eq!(CamelCard::Nine, card_from_char('9', false));
-        assert_eq!(CamelCard::Four, card_from_char('4', false));
-        assert_eq!(CamelCard::Eight, card_from_char('8', false));
+        let rules = HandRules::standard();
+        assert_eq!(rules.rank('9'), 5);
+        assert_eq!(rules.rank('4'), 10);
+        assert_eq!(rules.rank('8'), 6);
     }
 
     #[test]
     #[should_panic]
     fn invalid_character_in_parsing() {
-        card_from_char('z', false);
+        HandRules::standard().classify("z2345");
     }
 }
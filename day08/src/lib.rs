@@ -2,7 +2,10 @@ extern crate filelib;
 
 pub use filelib::load_no_blanks;
 use num::Integer;
+use parallellib::parallel_map;
+pub use parallellib::set_jobs_from_args;
 use petgraph::graph::Graph;
+use petgraph::graph::NodeIndex;
 use petgraph::visit::EdgeRef;
 use std::collections::HashSet;
 
@@ -123,9 +126,12 @@ pub fn puzzle_a(string_list: &Vec<String>) -> u64 {
 /// ```
 pub fn puzzle_b(string_list: &Vec<String>) -> u64 {
     let (ins, graph) = parse_instructions(string_list);
-    let mut path_amounts = vec![];
-    for origin in graph.node_indices().filter(|i| graph[*i].ends_with("A")) {
-        let mut cur_node = origin;
+    let origins: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|i| graph[*i].ends_with("A"))
+        .collect();
+    let path_amounts = parallel_map(&origins, |origin| {
+        let mut cur_node = *origin;
         let mut count = 0;
         for dir in ins.iter().cycle() {
             if graph[cur_node].ends_with("Z") {
@@ -142,8 +148,8 @@ pub fn puzzle_b(string_list: &Vec<String>) -> u64 {
                 }
             }
         }
-        path_amounts.push(count);
-    }
+        return count;
+    });
 
     return path_amounts.into_iter().reduce(|a, b| a.lcm(&b)).unwrap();
 }
@@ -1,8 +1,10 @@
 use day08::load_no_blanks;
 use day08::puzzle_a;
 use day08::puzzle_b;
+use day08::set_jobs_from_args;
 
 fn main() {
+    set_jobs_from_args();
     let filename = "input";
     let lines = load_no_blanks(filename);
 
@@ -1,13 +1,9 @@
 extern crate filelib;
 
 pub use filelib::load_no_blanks;
-use petgraph::graph::Graph;
-use petgraph::graph::NodeIndex;
-use petgraph::visit::Bfs;
-use petgraph::visit::Dfs;
-use petgraph::Directed;
-use std::collections::HashMap;
-use std::collections::HashSet;
+use gridlib::Grid;
+use gridlib::GridCoordinate;
+use gridlib::TileTable;
 
 const DEBUG: bool = false;
 
@@ -27,151 +23,38 @@ impl std::fmt::Display for Coord {
     }
 }
 
-struct GraphManager {
-    graph: Graph<Coord, u32, Directed>,
-    coord_to_nodes: HashMap<Coord, NodeIndex<u32>>,
-    edges: HashSet<(Coord, Coord)>,
-}
-
-impl GraphManager {
-    fn has_edge(&self, from: Coord, to: Coord) -> bool {
-        if self.edges.contains(&(from, to)) {
-            return true;
-        }
-        return false;
-    }
-
-    fn add(&mut self, x: i64, y: i64) {
-        let src_coord = Coord { x: x, y: y };
-        if !self.coord_to_nodes.contains_key(&src_coord) {
-            let src = self.graph.add_node(src_coord);
-            self.coord_to_nodes.insert(src_coord, src);
-        }
-    }
-
-    fn add_target(&mut self, x: i64, y: i64, x_t: i64, y_t: i64) {
-        let src_coord = Coord { x: x, y: y };
-        let tgt_coord = Coord { x: x_t, y: y_t };
-        if self.has_edge(src_coord, tgt_coord) {
-            return;
-        }
-        let tgt: NodeIndex<u32>;
-        if !self.coord_to_nodes.contains_key(&tgt_coord) {
-            tgt = self.graph.add_node(tgt_coord);
-            self.coord_to_nodes.insert(tgt_coord, tgt);
-        } else {
-            tgt = *self.coord_to_nodes.get(&tgt_coord).unwrap();
-        }
-        let src: NodeIndex<u32> = *self.coord_to_nodes.get(&src_coord).unwrap();
-        self.graph.add_edge(src, tgt, 1);
-        self.edges.insert((src_coord, tgt_coord));
-    }
-
-    fn add_north_of(&mut self, x: i64, y: i64) {
-        self.add_target(x, y, x, y - 1);
-    }
-
-    fn add_south_of(&mut self, x: i64, y: i64) {
-        self.add_target(x, y, x, y + 1);
-    }
-
-    fn add_east_of(&mut self, x: i64, y: i64) {
-        self.add_target(x, y, x + 1, y);
-    }
-
-    fn add_west_of(&mut self, x: i64, y: i64) {
-        self.add_target(x, y, x - 1, y);
-    }
-}
-
-fn parse_pipes(string_list: &Vec<String>) -> (Graph<Coord, u32, Directed, u32>, Coord) {
-    let graph: Graph<Coord, u32, Directed, u32> = Graph::new();
-    let mut origin_x: i64 = 0;
-    let mut origin_y: i64 = 0;
-    let mut manager = GraphManager {
-        graph: graph,
-        coord_to_nodes: HashMap::new(),
-        edges: HashSet::new(),
-    };
-    // Unfortunately can't just add blindly undirected.
-    // This can happen:
-    // 7
-    // F
-    // What can I do about this. Make it directed.
+fn parse_pipes(string_list: &Vec<String>) -> (Grid<char>, GridCoordinate) {
+    let table = TileTable::pipes();
+    let width = string_list[0].len();
+    let mut values = vec![];
+    let mut origin = None;
     for (y, line) in string_list.iter().enumerate() {
         for (x, c) in line.chars().enumerate() {
             if DEBUG {
                 println!("Parsing {}", c);
             }
-            match c {
-                '.' => continue,
-                '|' => {
-                    manager.add(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_north_of(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_south_of(x.try_into().unwrap(), y.try_into().unwrap());
-                }
-                '-' => {
-                    manager.add(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_east_of(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_west_of(x.try_into().unwrap(), y.try_into().unwrap());
-                }
-                'L' => {
-                    manager.add(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_east_of(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_north_of(x.try_into().unwrap(), y.try_into().unwrap());
-                }
-                'J' => {
-                    manager.add(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_west_of(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_north_of(x.try_into().unwrap(), y.try_into().unwrap());
-                }
-                '7' => {
-                    manager.add(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_west_of(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_south_of(x.try_into().unwrap(), y.try_into().unwrap());
-                }
-                'F' => {
-                    manager.add(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_east_of(x.try_into().unwrap(), y.try_into().unwrap());
-                    manager.add_south_of(x.try_into().unwrap(), y.try_into().unwrap());
-                }
-                'S' => {
-                    origin_x = x.try_into().unwrap();
-                    origin_y = y.try_into().unwrap();
-                }
-                _ => panic!("Unknown char '{}'", c),
-            }
-        }
-    }
-
-    // The start is guaranteed to have exactly two connections to it, find those and add the reverse
-    for (a, b) in manager.edges.clone().iter() {
-        // These are TO the origin
-        if b.x == origin_x && b.y == origin_y {
-            if a.x > origin_x {
-                manager.add_east_of(origin_x, origin_y);
-            }
-            if a.x < origin_x {
-                manager.add_west_of(origin_x, origin_y);
-            }
-            if a.y < origin_y {
-                manager.add_north_of(origin_x, origin_y);
-            }
-            if a.x > origin_y {
-                manager.add_south_of(origin_x, origin_y);
+            if c == 'S' {
+                origin = Some(GridCoordinate::new(x, y));
+            } else if table.connections(c).is_none() {
+                panic!("Unknown char '{}'", c);
             }
+            values.push(c);
         }
     }
-
     return (
-        manager.graph,
-        Coord {
-            x: origin_x,
-            y: origin_y,
-        },
+        Grid::new(width, string_list.len(), values),
+        origin.expect("No start"),
     );
 }
 
+// The loop through S, in order, starting at S.
+fn find_loop(string_list: &Vec<String>) -> Vec<GridCoordinate> {
+    let (grid, origin) = parse_pipes(string_list);
+    return TileTable::pipes()
+        .extract_loop(&grid, origin)
+        .expect("S is not on a loop");
+}
+
 /// How many steps along the loop does it take to get from S to the point farthest from the starting position?
 /// So we need to think how we represent this, if you think of it as the space you are in is a node, and
 /// the input represents its connections (eg: "-" means its connected to x - 1 and x + 1), then each node
@@ -182,7 +65,7 @@ fn parse_pipes(string_list: &Vec<String>) -> (Graph<Coord, u32, Directed, u32>,
 /// Listed in the problem is that there is a SINGLE loop, which means we can probably path find.
 /// Specifically, from the origin there will be two points it can talk to. If we needed to prove this exists
 /// we could use DFS, but because WE KNOW it exists, we just need to BFS, since we can't leave it.
-/// Half the length of the BFS should be the answer.
+/// Half the length of the loop should be the answer.
 /// ```
 /// let vec1: Vec<String> = vec![
 ///     "..F7.",
@@ -194,16 +77,7 @@ fn parse_pipes(string_list: &Vec<String>) -> (Graph<Coord, u32, Directed, u32>,
 /// assert_eq!(day10::puzzle_a(&vec1), 8);
 /// ```
 pub fn puzzle_a(string_list: &Vec<String>) -> u32 {
-    let (graph, start_coord) = parse_pipes(string_list);
-    let src = graph
-        .node_indices()
-        .find(|i| graph[*i] == start_coord)
-        .unwrap();
-    let mut length = 0;
-    let mut bfs = Bfs::new(&graph, src);
-    while let Some(_) = bfs.next(&graph) {
-        length += 1;
-    }
+    let length: u32 = find_loop(string_list).len().try_into().unwrap();
     if DEBUG {
         println!("Length: {}", length);
     }
@@ -254,28 +128,23 @@ fn shoelace_theory(coords_in_loops: &Vec<Coord>, list_len: i64) -> i64 {
 ///    "L.L7LFJ|||||FJL7||LJ",
 ///    "L7JLJL-JLJLJL--JLJ.L",
 /// ].iter().map(|s| s.to_string()).collect();
-/// //assert_eq!(day10::puzzle_b(&vec1), 10);
+/// assert_eq!(day10::puzzle_b(&vec1), 10);
 /// ```
 pub fn puzzle_b(string_list: &Vec<String>) -> i64 {
-    let (graph, start_coord) = parse_pipes(string_list);
-    let src = graph
-        .node_indices()
-        .find(|i| graph[*i] == start_coord)
-        .unwrap();
-    let mut coords_in_loops = vec![start_coord];
-    // use Dfs instead of BFS here
-    // This keeps a "direction" which I need for shoelace theorem below.
-    let mut dfs = Dfs::new(&graph, src);
-    while let Some(nx) = dfs.next(&graph) {
-        let cur_coord: Coord = graph[nx];
-        coords_in_loops.push(cur_coord);
-    }
+    // The loop comes back in order, which the shoelace theorem below needs.
+    let coords_in_loops: Vec<Coord> = find_loop(string_list)
+        .into_iter()
+        .map(|c| Coord {
+            x: c.x.try_into().unwrap(),
+            y: c.y.try_into().unwrap(),
+        })
+        .collect();
 
     // Shoe lace therom gets us part way, but it overestimates.
     let i64_list_len: i64 = string_list.len().try_into().unwrap();
 
-    // This is twice the area by default.
-    let total_area = shoelace_theory(&coords_in_loops, i64_list_len) / 2;
+    // This is twice the area by default, negative if the loop runs clockwise.
+    let total_area = shoelace_theory(&coords_in_loops, i64_list_len).abs() / 2;
 
     if DEBUG {
         println!("{} total area", total_area);
@@ -292,48 +161,8 @@ pub fn puzzle_b(string_list: &Vec<String>) -> i64 {
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    #[test]
-    fn test_graph_manager_edges() {
-        let graph: Graph<Coord, u32, Directed, u32> = Graph::new();
-        let mut gm = GraphManager {
-            graph: graph,
-            coord_to_nodes: HashMap::new(),
-            edges: HashSet::new(),
-        };
-        gm.add(1, 2);
-        gm.add(2, 2);
-        assert_eq!(
-            gm.has_edge(Coord { x: 1, y: 2 }, Coord { x: 2, y: 2 }),
-            false
-        );
-        assert_eq!(
-            gm.has_edge(Coord { x: 2, y: 2 }, Coord { x: 1, y: 2 }),
-            false
-        );
-        gm.add_east_of(1, 2);
-        assert_eq!(
-            gm.has_edge(Coord { x: 1, y: 2 }, Coord { x: 2, y: 2 }),
-            true
-        );
-        assert_eq!(
-            gm.has_edge(Coord { x: 2, y: 2 }, Coord { x: 1, y: 2 }),
-            false
-        );
-        gm.add_west_of(2, 2);
-        assert_eq!(
-            gm.has_edge(Coord { x: 1, y: 2 }, Coord { x: 2, y: 2 }),
-            true
-        );
-        assert_eq!(
-            gm.has_edge(Coord { x: 2, y: 2 }, Coord { x: 1, y: 2 }),
-            true
-        );
-        assert_eq!(
-            gm.has_edge(Coord { x: 1, y: 2 }, Coord { x: 7, y: 2 }),
-            false
-        );
-    }
+    use gridlib::fill_from_outside_upscaled;
+    use gridlib::GridTraversable;
 
     #[test]
     fn test_square_loop() {
@@ -405,4 +234,70 @@ mod tests {
         ];
         assert_eq!(shoelace_theory(&coords, y_offset), 33);
     }
+
+    // Count enclosed tiles a different way, filling in from outside with every tile blown
+    // up three times so the fill squeezes between pipes.
+    fn enclosed_by_fill(string_list: &Vec<String>) -> usize {
+        let (grid, origin) = parse_pipes(string_list);
+        let table = TileTable::pipes();
+        let on_loop = find_loop(string_list);
+        let mut values = vec!['.'; grid.get_width() * grid.get_height()];
+        for c in on_loop.iter() {
+            values[c.y * grid.get_width() + c.x] = grid.get_value(*c).unwrap();
+        }
+        values[origin.y * grid.get_width() + origin.x] = table.infer_tile(&grid, origin).unwrap();
+        let loop_only = Grid::new(grid.get_width(), grid.get_height(), values);
+        let outside =
+            fill_from_outside_upscaled(&loop_only, 3, |c, x, y| table.blocks_subcell(c, x, y));
+        return loop_only
+            .coord_iter()
+            .filter(|c| loop_only.get_value(*c).unwrap() == '.' && !outside.get_value(*c).unwrap())
+            .count();
+    }
+
+    #[test]
+    fn test_enclosed_matches_fill() {
+        let examples: Vec<Vec<&str>> = vec![
+            vec![
+                "...........",
+                ".S-------7.",
+                ".|F-----7|.",
+                ".||.....||.",
+                ".||.....||.",
+                ".|L-7.F-J|.",
+                ".|..|.|..|.",
+                ".L--J.L--J.",
+                "...........",
+            ],
+            vec![
+                ".F----7F7F7F7F-7....",
+                ".|F--7||||||||FJ....",
+                ".||.FJ||||||||L7....",
+                "FJL7L7LJLJ||LJ.L-7..",
+                "L--J.L7...LJS7F-7L7.",
+                "....F-J..F7FJ|L7L7L7",
+                "....L7.F7||L7|.L7L7|",
+                ".....|FJLJ|FJ|F7|.LJ",
+                "....FJL-7.||.||||...",
+                "....L---J.LJ.LJLJ...",
+            ],
+            vec![
+                "FF7FSF7F7F7F7F7F---7",
+                "L|LJ||||||||||||F--J",
+                "FL-7LJLJ||||||LJL-77",
+                "F--JF--7||LJLJ7F7FJ-",
+                "L---JF-JLJ.||-FJLJJ7",
+                "|F|F-JF---7F7-L7L|7|",
+                "|FFJF7L7F-JF7|JL---7",
+                "7-L-JL7||F7|L7F-7F7|",
+                "L.L7LFJ|||||FJL7||LJ",
+                "L7JLJL-JLJLJL--JLJ.L",
+            ],
+        ];
+        for (example, expected) in examples.into_iter().zip([4, 8, 10]) {
+            let lines: Vec<String> = example.iter().map(|s| s.to_string()).collect();
+            assert_eq!(puzzle_b(&lines), expected);
+            assert_eq!(enclosed_by_fill(&lines) as i64, expected);
+        }
+    }
 }
@@ -1,15 +1,55 @@
 extern crate filelib;
 
 pub use filelib::load_no_blanks;
+use gridlib::Grid;
+use gridlib::GridCoordinate;
+use gridlib::GridTraversable;
+use parallellib::parallel_map;
+pub use parallellib::set_jobs_from_args;
 use std::collections::HashMap;
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, PartialEq, Eq, Hash)]
-enum SpringState {
+pub enum SpringState {
     Operational,
     Damaged,
     Unknown,
 }
 
+fn state_from_char(c: char) -> SpringState {
+    return match c {
+        '.' => SpringState::Operational,
+        '#' => SpringState::Damaged,
+        '?' => SpringState::Unknown,
+        _ => panic!("Parse error '{}'", c),
+    };
+}
+
+fn state_to_char(state: &SpringState) -> char {
+    return match state {
+        SpringState::Operational => '.',
+        SpringState::Damaged => '#',
+        SpringState::Unknown => '?',
+    };
+}
+
+/// Write states back out the way the input has them.
+/// ```
+/// use day12::SpringState;
+/// let states = vec![SpringState::Damaged, SpringState::Operational, SpringState::Unknown];
+/// assert_eq!(day12::states_to_string(&states), "#.?");
+/// ```
+pub fn states_to_string(states: &Vec<SpringState>) -> String {
+    return states.iter().map(state_to_char).collect();
+}
+
+/// Parse a single line, such as "???.### 1,1,3", into its states and damaged groups.
+pub fn parse_line(line: &str) -> (Vec<SpringState>, Vec<u32>) {
+    let (cur_state_s, group_s) = line.split_once(" ").unwrap();
+    let cur_states = cur_state_s.chars().map(state_from_char).collect();
+    let cur_groups = group_s.split(",").map(|n| n.parse().unwrap()).collect();
+    return (cur_states, cur_groups);
+}
+
 // One vec of states (first output)
 // One vec of groupings of damaged strings per line (second output)
 // These can be zipped together to get a single line
@@ -18,24 +58,7 @@ fn parse_state(string_list: &Vec<String>) -> (Vec<Vec<SpringState>>, Vec<Vec<u32
     let mut groups = vec![];
 
     for line in string_list {
-        let (cur_state_s, group_s) = line.split_once(" ").unwrap();
-        let mut cur_states = vec![];
-        for c in cur_state_s.chars() {
-            let s = match c {
-                '.' => SpringState::Operational,
-                '#' => SpringState::Damaged,
-                '?' => SpringState::Unknown,
-                _ => panic!("Parse error '{}'", c),
-            };
-            cur_states.push(s);
-        }
-
-        let mut cur_groups = vec![];
-        for num in group_s.split(",") {
-            let as_u32: u32 = num.parse().unwrap();
-            cur_groups.push(as_u32);
-        }
-
+        let (cur_states, cur_groups) = parse_line(line);
         groups.push(cur_groups);
         states.push(cur_states);
     }
@@ -98,6 +121,13 @@ fn is_line_solved(state: &Vec<SpringState>, group: &Vec<u32>) -> bool {
 }
 
 fn solve_possibliites(state: &Vec<SpringState>, group: &Vec<u32>) -> u32 {
+    return brute_force_arrangements(state, group)
+        .len()
+        .try_into()
+        .unwrap();
+}
+
+fn brute_force_arrangements(state: &Vec<SpringState>, group: &Vec<u32>) -> Vec<Vec<SpringState>> {
     // Brute force solution would be to flip every Unknown to operational or damaged, until it passes group, then
     // count those.
     // We can figure out how many loops that would be, each has two possible states, so 2^n
@@ -107,7 +137,7 @@ fn solve_possibliites(state: &Vec<SpringState>, group: &Vec<u32>) -> u32 {
         .filter(|x| *x == SpringState::Unknown)
         .collect::<Vec<_>>()
         .len();
-    let mut solves = 0;
+    let mut solves = vec![];
     for i in 0..2_usize.pow(count.try_into().unwrap()) {
         // Flip based on bit mask
         // 0 = Operational
@@ -133,7 +163,7 @@ fn solve_possibliites(state: &Vec<SpringState>, group: &Vec<u32>) -> u32 {
             .collect();
         if is_line_solved(&possible_solution, group) {
             //println!("Solved: {:?}", possible_solution);
-            solves += 1;
+            solves.push(possible_solution);
         }
     }
     return solves;
@@ -169,24 +199,7 @@ fn parse_state_unfold(
     let mut groups = vec![];
 
     for line in string_list {
-        let (cur_state_s, group_s) = line.split_once(" ").unwrap();
-        let mut cur_states = vec![];
-        for c in cur_state_s.chars() {
-            let s = match c {
-                '.' => SpringState::Operational,
-                '#' => SpringState::Damaged,
-                '?' => SpringState::Unknown,
-                _ => panic!("Parse error '{}'", c),
-            };
-            cur_states.push(s);
-        }
-
-        let mut cur_groups = vec![];
-        for num in group_s.split(",") {
-            let as_u32: u32 = num.parse().unwrap();
-            cur_groups.push(as_u32);
-        }
-
+        let (cur_states, cur_groups) = parse_line(line);
         let mut unfolded_groups = vec![];
         let mut unfolded_states = vec![];
         for i in 0..multiply_by {
@@ -302,6 +315,327 @@ fn initial_recursive_solve(state: &Vec<SpringState>, group: &Vec<u32>) -> u64 {
     return recrusive_solve(state, group, 0, 0, 0, &mut cache);
 }
 
+/// Every valid arrangement of one line, from all Operational-first to all Damaged-first.
+///
+/// The number of arrangements from each point is counted up front, so any one of them
+/// can be built directly by its index without walking the others.
+/// ```
+/// let (state, group) = day12::parse_line("?###???????? 3,2,1");
+/// let arrangements = day12::Arrangements::new(&state, &group);
+/// assert_eq!(arrangements.total(), Some(10));
+/// let first = arrangements.nth_arrangement(0).unwrap();
+/// assert_eq!(day12::states_to_string(&first), ".###....##.#");
+/// let last = arrangements.nth_arrangement(9).unwrap();
+/// assert_eq!(day12::states_to_string(&last), ".###.##.#...");
+/// assert_eq!(arrangements.nth_arrangement(10), None);
+/// let all: Vec<String> = arrangements.map(|a| day12::states_to_string(&a)).collect();
+/// assert_eq!(all.len(), 10);
+/// assert_eq!(all[1], ".###...##..#");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Arrangements {
+    state: Vec<SpringState>,
+    group: Vec<u32>,
+    // counts[i][g] = ways to fill state[i..] with group[g..], when state[i] starts fresh.
+    counts: Vec<Vec<Option<u64>>>,
+    next_index: u64,
+}
+
+impl Arrangements {
+    pub fn new(state: &Vec<SpringState>, group: &Vec<u32>) -> Arrangements {
+        return Arrangements {
+            state: state.clone(),
+            group: group.clone(),
+            counts: suffix_counts(state, group),
+            next_index: 0,
+        };
+    }
+
+    /// Total number of arrangements, None if there are too many to fit in a u64.
+    pub fn total(&self) -> Option<u64> {
+        return self.counts[0][0];
+    }
+
+    /// Build the arrangement at this index, None if there aren't that many. A total too
+    /// big for a u64 is more than any index.
+    pub fn nth_arrangement(&self, index: u64) -> Option<Vec<SpringState>> {
+        if let Some(total) = self.total() {
+            if index >= total {
+                return None;
+            }
+        }
+        let n = self.state.len();
+        let mut remaining = index;
+        let mut result = vec![];
+        let mut i = 0;
+        let mut g = 0;
+        while i < n {
+            // Operational sorts first, so every arrangement starting that way comes before the rest.
+            if self.state[i] != SpringState::Damaged {
+                let operational_count = self.counts[i + 1][g];
+                let starts_operational = match operational_count {
+                    Some(count) => remaining < count,
+                    // Too many to fit in a u64, so more than any index.
+                    None => true,
+                };
+                if starts_operational {
+                    result.push(SpringState::Operational);
+                    i += 1;
+                    continue;
+                }
+                remaining -= operational_count.unwrap();
+            }
+            // Otherwise group g must start here, followed by a gap.
+            let len = self.group[g] as usize;
+            result.extend(vec![SpringState::Damaged; len]);
+            if i + len < n {
+                result.push(SpringState::Operational);
+            }
+            i += len + 1;
+            g += 1;
+        }
+        return Some(result);
+    }
+}
+
+impl Iterator for Arrangements {
+    type Item = Vec<SpringState>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.nth_arrangement(self.next_index)?;
+        self.next_index += 1;
+        return Some(result);
+    }
+}
+
+// counts[i][g] = ways to fill state[i..] with group[g..], when state[i] starts fresh.
+// None once the count no longer fits in a u64, which only happens for huge lines.
+fn suffix_counts(state: &Vec<SpringState>, group: &Vec<u32>) -> Vec<Vec<Option<u64>>> {
+    let n = state.len();
+    let m = group.len();
+    // Two past the end, as a group ending on the last state skips its trailing gap.
+    let mut counts = vec![vec![Some(0_u64); m + 1]; n + 2];
+    counts[n][m] = Some(1);
+    counts[n + 1][m] = Some(1);
+    for i in (0..n).rev() {
+        for g in 0..=m {
+            let mut total = Some(0_u64);
+            if state[i] != SpringState::Damaged {
+                total = add_counts(total, counts[i + 1][g]);
+            }
+            if g < m && group_fits(state, i, group[g]) {
+                total = add_counts(total, counts[i + group[g] as usize + 1][g + 1]);
+            }
+            counts[i][g] = total;
+        }
+    }
+    return counts;
+}
+
+fn add_counts(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    return a?.checked_add(b?);
+}
+
+// Can a run of len damaged start at index, with a gap (or the end) after it?
+fn group_fits(state: &Vec<SpringState>, index: usize, len: u32) -> bool {
+    let end = index + len as usize;
+    if end > state.len() {
+        return false;
+    }
+    if state[index..end].contains(&SpringState::Operational) {
+        return false;
+    }
+    return end == state.len() || state[end] != SpringState::Damaged;
+}
+
+/// Fill in every cell of a line that is the same in all of its arrangements.
+///
+/// None if the line has no arrangements at all.
+/// ```
+/// let (state, group) = day12::parse_line("????????? 7");
+/// let solved = day12::solve_line(&state, &group).unwrap();
+/// assert_eq!(day12::states_to_string(&solved), "??#####??");
+/// let (state, group) = day12::parse_line("#.# 2");
+/// assert_eq!(day12::solve_line(&state, &group), None);
+/// ```
+pub fn solve_line(state: &Vec<SpringState>, group: &Vec<u32>) -> Option<Vec<SpringState>> {
+    let n = state.len();
+    let m = group.len();
+    let counts = suffix_counts(state, group);
+    if counts[0][0] == Some(0) {
+        return None;
+    }
+
+    // reachable[i][g]: some valid prefix places g groups and leaves state[i] starting fresh.
+    let mut reachable = vec![vec![false; m + 1]; n + 2];
+    reachable[0][0] = true;
+    let mut can_operate = vec![false; n];
+    let mut can_damage = vec![false; n];
+    for i in 0..n {
+        for g in 0..=m {
+            if !reachable[i][g] {
+                continue;
+            }
+            if state[i] != SpringState::Damaged && counts[i + 1][g] != Some(0) {
+                can_operate[i] = true;
+                reachable[i + 1][g] = true;
+            }
+            if g < m && group_fits(state, i, group[g]) {
+                let end = i + group[g] as usize;
+                if counts[end + 1][g + 1] != Some(0) {
+                    for cell in can_damage[i..end].iter_mut() {
+                        *cell = true;
+                    }
+                    if end < n {
+                        can_operate[end] = true;
+                    }
+                    reachable[end + 1][g + 1] = true;
+                }
+            }
+        }
+    }
+
+    return Some(
+        can_operate
+            .iter()
+            .zip(can_damage.iter())
+            .map(|(operate, damage)| match (operate, damage) {
+                (true, false) => SpringState::Operational,
+                (false, true) => SpringState::Damaged,
+                _ => SpringState::Unknown,
+            })
+            .collect(),
+    );
+}
+
+/// Result of solving a nonogram.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NonogramSolution {
+    NoSolution,
+    Unique(Grid<SpringState>),
+    /// Two of the solutions, there may be more.
+    Multiple(Grid<SpringState>, Grid<SpringState>),
+}
+
+fn get_line(grid: &Grid<SpringState>, index: usize, is_row: bool) -> Vec<SpringState> {
+    if is_row {
+        return (0..grid.get_width())
+            .map(|x| grid.get_value(GridCoordinate::new(x, index)).unwrap())
+            .collect();
+    }
+    return (0..grid.get_height())
+        .map(|y| grid.get_value(GridCoordinate::new(index, y)).unwrap())
+        .collect();
+}
+
+fn set_line(grid: &mut Grid<SpringState>, index: usize, is_row: bool, line: &Vec<SpringState>) {
+    for (i, state) in line.iter().enumerate() {
+        let coord = match is_row {
+            true => GridCoordinate::new(i, index),
+            false => GridCoordinate::new(index, i),
+        };
+        grid.set_value(coord, *state);
+    }
+}
+
+// Solve lines until nothing changes, false if a line became impossible.
+fn propagate(
+    grid: &mut Grid<SpringState>,
+    row_clues: &Vec<Vec<u32>>,
+    col_clues: &Vec<Vec<u32>>,
+) -> bool {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (clues, is_row) in [(row_clues, true), (col_clues, false)] {
+            for (index, clue) in clues.iter().enumerate() {
+                let line = get_line(grid, index, is_row);
+                let solved = match solve_line(&line, clue) {
+                    Some(solved) => solved,
+                    None => return false,
+                };
+                if solved != line {
+                    set_line(grid, index, is_row, &solved);
+                    changed = true;
+                }
+            }
+        }
+    }
+    return true;
+}
+
+fn search_nonogram(
+    grid: Grid<SpringState>,
+    row_clues: &Vec<Vec<u32>>,
+    col_clues: &Vec<Vec<u32>>,
+    solutions: &mut Vec<Grid<SpringState>>,
+) {
+    let mut grid = grid;
+    if solutions.len() > 1 || !propagate(&mut grid, row_clues, col_clues) {
+        return;
+    }
+    let unknown = grid
+        .coord_iter()
+        .find(|c| grid.get_value(*c) == Some(SpringState::Unknown));
+    let coord = match unknown {
+        Some(coord) => coord,
+        None => {
+            solutions.push(grid);
+            return;
+        }
+    };
+    // Propagation is stuck, so guess.
+    for guess in [SpringState::Damaged, SpringState::Operational] {
+        let mut next = grid.clone();
+        next.set_value(coord, guess);
+        search_nonogram(next, row_clues, col_clues, solutions);
+    }
+}
+
+/// Solve a nonogram, using the spring line solver on every row and column.
+///
+/// Rows are listed top to bottom, columns left to right. Damaged is a filled cell.
+/// ```
+/// use day12::NonogramSolution;
+/// let rows = vec![vec![1, 1], vec![3], vec![1]];
+/// let cols = vec![vec![2], vec![2], vec![2]];
+/// match day12::solve_nonogram(&rows, &cols) {
+///     NonogramSolution::Unique(grid) => {
+///         assert_eq!(day12::grid_to_strings(&grid), vec!["#.#", "###", ".#."]);
+///     }
+///     _ => panic!("Expected one solution"),
+/// }
+///
+/// // Either diagonal works
+/// let rows = vec![vec![1], vec![1]];
+/// let cols = vec![vec![1], vec![1]];
+/// assert!(matches!(day12::solve_nonogram(&rows, &cols), NonogramSolution::Multiple(_, _)));
+///
+/// let rows = vec![vec![2], vec![]];
+/// let cols = vec![vec![], vec![1]];
+/// assert_eq!(day12::solve_nonogram(&rows, &cols), NonogramSolution::NoSolution);
+/// ```
+pub fn solve_nonogram(row_clues: &Vec<Vec<u32>>, col_clues: &Vec<Vec<u32>>) -> NonogramSolution {
+    let width = col_clues.len();
+    let height = row_clues.len();
+    let grid = Grid::new(width, height, vec![SpringState::Unknown; width * height]);
+    let mut solutions = vec![];
+    search_nonogram(grid, row_clues, col_clues, &mut solutions);
+    let mut found = solutions.into_iter();
+    return match (found.next(), found.next()) {
+        (None, _) => NonogramSolution::NoSolution,
+        (Some(only), None) => NonogramSolution::Unique(only),
+        (Some(first), Some(second)) => NonogramSolution::Multiple(first, second),
+    };
+}
+
+/// Each row of the grid as a string, like the input.
+pub fn grid_to_strings(grid: &Grid<SpringState>) -> Vec<String> {
+    return (0..grid.get_height())
+        .map(|y| states_to_string(&get_line(grid, y, true)))
+        .collect();
+}
+
 /// Unfold the input, get sum of arrangements.
 /// Unfold is done by multiplying by 5.
 /// ```
@@ -317,11 +651,10 @@ fn initial_recursive_solve(state: &Vec<SpringState>, group: &Vec<u32>) -> u64 {
 /// ```
 pub fn puzzle_b(string_list: &Vec<String>) -> u64 {
     let (state, groups) = parse_state_unfold(string_list, 5);
-    let possibilities: Vec<u64> = state
-        .iter()
-        .zip(groups.iter())
-        .map(|(cur_state, cur_group)| initial_recursive_solve(cur_state, cur_group))
-        .collect();
+    let lines: Vec<(&Vec<SpringState>, &Vec<u32>)> = state.iter().zip(groups.iter()).collect();
+    let possibilities: Vec<u64> = parallel_map(&lines, |(cur_state, cur_group)| {
+        initial_recursive_solve(cur_state, cur_group)
+    });
     return possibilities.into_iter().sum();
 }
 
@@ -421,4 +754,117 @@ mod tests {
         result = is_line_solved(&line, &states);
         assert_eq!(result, true);
     }
+
+    fn example_lines() -> Vec<String> {
+        return [
+            "???.### 1,1,3",
+            ".??..??...?##. 1,1,3",
+            "?#?#?#?#?#?#?#? 1,3,1,6",
+            "????.#...#... 4,1,1",
+            "????.######..#####. 1,6,5",
+            "?###???????? 3,2,1",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    }
+
+    #[test]
+    fn test_arrangements_match_brute_force() {
+        for line in example_lines() {
+            let (state, group) = parse_line(&line);
+            let mut brute = brute_force_arrangements(&state, &group);
+            brute.sort();
+            let lazy: Vec<Vec<SpringState>> = Arrangements::new(&state, &group).collect();
+            // Lazy ones come out already sorted
+            assert_eq!(lazy, brute, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_arrangements_count_matches_recursive() {
+        let (states, groups) = parse_state_unfold(&example_lines(), 5);
+        for (state, group) in states.iter().zip(groups.iter()) {
+            let arrangements = Arrangements::new(state, group);
+            let total = arrangements.total().unwrap();
+            assert_eq!(total, initial_recursive_solve(state, group));
+            let last = arrangements.nth_arrangement(total - 1);
+            assert!(is_line_solved(&last.unwrap(), group));
+        }
+    }
+
+    #[test]
+    fn test_arrangements_none_possible() {
+        let (state, group) = parse_line("#.# 2");
+        let mut arrangements = Arrangements::new(&state, &group);
+        assert_eq!(arrangements.total(), Some(0));
+        assert_eq!(arrangements.next(), None);
+    }
+
+    #[test]
+    fn test_arrangements_overflow() {
+        // 50 single springs in 200 cells is C(151, 50) ways, far more than a u64 holds.
+        let line = format!("{} {}", "?".repeat(200), vec!["1"; 50].join(","));
+        let (state, group) = parse_line(&line);
+        let mut arrangements = Arrangements::new(&state, &group);
+        assert_eq!(arrangements.total(), None);
+        // Still built lazily, all the working springs first.
+        let first = format!("{}{}#", ".".repeat(101), "#.".repeat(49));
+        let nth = arrangements.nth_arrangement(0).unwrap();
+        assert_eq!(states_to_string(&nth), first);
+        assert_eq!(states_to_string(&arrangements.next().unwrap()), first);
+        let second = arrangements.next().unwrap();
+        assert!(is_line_solved(&second, &group));
+        assert_ne!(states_to_string(&second), first);
+        // Still solvable, as only whether a count is zero matters there.
+        let solved = solve_line(&state, &group).unwrap();
+        assert!(solved.iter().all(|s| *s == SpringState::Unknown));
+    }
+
+    #[test]
+    fn test_solve_line_matches_arrangements() {
+        for line in example_lines() {
+            let (state, group) = parse_line(&line);
+            let arrangements: Vec<Vec<SpringState>> = Arrangements::new(&state, &group).collect();
+            let solved = solve_line(&state, &group).unwrap();
+            for (i, cell) in solved.iter().enumerate() {
+                let first = arrangements[0][i];
+                let all_same = arrangements.iter().all(|a| a[i] == first);
+                if all_same {
+                    assert_eq!(*cell, first, "{} at {}", line, i);
+                } else {
+                    assert_eq!(*cell, SpringState::Unknown, "{} at {}", line, i);
+                }
+            }
+        }
+    }
+
+    fn clues_of(line: &Vec<SpringState>) -> Vec<u32> {
+        return states_to_string(line)
+            .split('.')
+            .filter(|run| !run.is_empty())
+            .map(|run| run.len() as u32)
+            .collect();
+    }
+
+    #[test]
+    fn test_nonogram_from_picture() {
+        let picture = vec!["#.#.#", ".###.", "#####", ".#.#.", "##.##"];
+        let states: Vec<SpringState> = picture
+            .iter()
+            .flat_map(|row| row.chars().map(state_from_char))
+            .collect();
+        let grid = Grid::new(5, 5, states);
+        let rows: Vec<Vec<u32>> = (0..5)
+            .map(|i| clues_of(&get_line(&grid, i, true)))
+            .collect();
+        let cols: Vec<Vec<u32>> = (0..5)
+            .map(|i| clues_of(&get_line(&grid, i, false)))
+            .collect();
+        let solved = match solve_nonogram(&rows, &cols) {
+            NonogramSolution::Unique(solved) => solved,
+            other => panic!("Expected unique, got {:?}", other),
+        };
+        assert_eq!(grid_to_strings(&solved), picture);
+    }
 }
@@ -1,8 +1,10 @@
 use day12::load_no_blanks;
 use day12::puzzle_a;
 use day12::puzzle_b;
+use day12::set_jobs_from_args;
 
 fn main() {
+    set_jobs_from_args();
     let filename = "input";
     let lines = load_no_blanks(filename);
 
@@ -37,93 +37,147 @@ fn parse_input(list_of_string_list: &Vec<Vec<String>>) -> Vec<Grid<LavaTerrain>>
     return result;
 }
 
-fn solve_for_horz_or_vert_sym(grid: &Grid<LavaTerrain>) -> Option<u32> {
-    //println!();
-    //println!("{:?}", grid);
-    let v = solve_for_horz_sym(grid, false);
-    if let Some(g) = v {
-        //println!("Found horz {}", g);
-        return Some(g * 100);
-    }
-    let vert = solve_for_vert_sym(grid, false);
-    //println!("Found vert {}", vert);
-    return vert;
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MirrorAxis {
+    /// Mirror line runs between two rows.
+    Horizontal,
+    /// Mirror line runs between two columns.
+    Vertical,
+}
+
+/// How many mismatched cells a reflection may have.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MismatchLimit {
+    Exactly(usize),
+    AtMost(usize),
 }
 
-fn solve_for_vert_sym(grid: &Grid<LavaTerrain>, with_smudge: bool) -> Option<u32> {
-    let width = grid.get_width();
-    let mut cols = vec![];
-    for _ in 0..width {
-        cols.push(vec![]);
+impl MismatchLimit {
+    fn max(&self) -> usize {
+        return match self {
+            MismatchLimit::Exactly(k) => *k,
+            MismatchLimit::AtMost(k) => *k,
+        };
     }
-    for coord in grid.coord_iter() {
-        cols[coord.x].push(grid.get_value(coord).unwrap());
+
+    fn allows(&self, count: usize) -> bool {
+        return match self {
+            MismatchLimit::Exactly(k) => count == *k,
+            MismatchLimit::AtMost(k) => count <= *k,
+        };
     }
+}
+
+/// A line of reflection in a grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reflection {
+    pub axis: MirrorAxis,
+    /// Number of rows above, or columns left of, the line.
+    pub position: usize,
+    /// Pairs of cells that should reflect each other but differ, the cell before the line first.
+    pub mismatches: Vec<(GridCoordinate, GridCoordinate)>,
+}
 
-    if with_smudge {
-        return solve_for_sym_smudge(&cols);
+impl Reflection {
+    /// Columns to the left, or 100 times the rows above.
+    pub fn summary(&self) -> u32 {
+        let position: u32 = self.position.try_into().unwrap();
+        return match self.axis {
+            MirrorAxis::Horizontal => position * 100,
+            MirrorAxis::Vertical => position,
+        };
     }
-    return solve_for_sym(&cols);
 }
 
-fn solve_for_sym(list: &Vec<Vec<LavaTerrain>>) -> Option<u32> {
-    // Find a perfect reflection on grid
-    // Find two adjacent items on the list that are the same
-    // Then check forward and back they are the same
-    // continue until run out of spaces.
+// Check the line before `position` on one axis, giving up once there are too many mismatches.
+fn check_reflection<T: Copy + PartialEq>(
+    grid: &Grid<T>,
+    axis: MirrorAxis,
+    position: usize,
+    limit: MismatchLimit,
+) -> Option<Reflection> {
+    // Lines are rows for a horizontal mirror, and columns for a vertical one.
+    let (num_lines, line_len) = match axis {
+        MirrorAxis::Horizontal => (grid.get_height(), grid.get_width()),
+        MirrorAxis::Vertical => (grid.get_width(), grid.get_height()),
+    };
+    let to_coord = |line: usize, along: usize| match axis {
+        MirrorAxis::Horizontal => GridCoordinate::new(along, line),
+        MirrorAxis::Vertical => GridCoordinate::new(line, along),
+    };
 
-    for (index, cur_list) in list.iter().enumerate() {
-        if index == 0 {
-            continue;
-        }
-        let last_index = index - 1;
-        if *cur_list != list[last_index] {
-            continue;
-        }
-        // println!("Possible reflection {} -\n{:?},\n{:?}", index, cur_list, list[index]);
-        // Possible reflection
-        let mut index_offset = 1;
-        let mut did_match = true;
-        while index_offset <= last_index && index + index_offset < list.len() {
-            let early_index = last_index - index_offset;
-            let late_index = index + index_offset;
-            let compare_one = list[early_index].clone();
-            let compare_two = list[late_index].clone();
-            assert_eq!(compare_one.len(), compare_two.len());
-            // println!("Checking {} vs {} \n{:?},\n{:?}",  early_index, late_index, compare_one, compare_two);
-            if compare_one != compare_two {
-                did_match = false;
-                break;
+    let mut mismatches = vec![];
+    let pairs = position.min(num_lines - position);
+    for offset in 0..pairs {
+        let before = position - 1 - offset;
+        let after = position + offset;
+        for along in 0..line_len {
+            let before_coord = to_coord(before, along);
+            let after_coord = to_coord(after, along);
+            if grid.get_value(before_coord) != grid.get_value(after_coord) {
+                mismatches.push((before_coord, after_coord));
+                if mismatches.len() > limit.max() {
+                    return None;
+                }
             }
-            index_offset += 1;
-        }
-        if did_match {
-            let result: u32 = index.try_into().unwrap();
-            return Some(result);
         }
     }
-    return None;
+
+    if !limit.allows(mismatches.len()) {
+        return None;
+    }
+    return Some(Reflection {
+        axis: axis,
+        position: position,
+        mismatches: mismatches,
+    });
 }
 
-fn solve_for_horz_sym(grid: &Grid<LavaTerrain>, with_smudge: bool) -> Option<u32> {
-    let mut rows = vec![];
-    let mut cur_row = vec![];
-    let mut cur_y = 0;
-    for coord in grid.coord_iter() {
-        if coord.y == cur_y {
-            cur_row.push(grid.get_value(coord).unwrap());
-        } else {
-            rows.push(cur_row);
-            cur_row = vec![grid.get_value(coord).unwrap()];
-            cur_y += 1;
+/// Find every mirror line in a grid, horizontal ones first, each in order of position.
+/// ```
+/// use day13::{find_reflections, MirrorAxis, MismatchLimit};
+/// use gridlib::{Grid, GridCoordinate};
+/// // 1 2 2 1
+/// // 3 4 4 9
+/// let grid = Grid::new(4, 2, vec![1, 2, 2, 1, 3, 4, 4, 9]);
+/// let exact = find_reflections(&grid, MismatchLimit::Exactly(0));
+/// assert_eq!(exact.len(), 0);
+///
+/// let smudged = find_reflections(&grid, MismatchLimit::Exactly(1));
+/// assert_eq!(smudged.len(), 1);
+/// assert_eq!(smudged[0].axis, MirrorAxis::Vertical);
+/// assert_eq!(smudged[0].position, 2);
+/// assert_eq!(
+///     smudged[0].mismatches,
+///     vec![(GridCoordinate::new(0, 1), GridCoordinate::new(3, 1))]
+/// );
+///
+/// // Between the rows, every column differs.
+/// let loose = find_reflections(&grid, MismatchLimit::AtMost(4));
+/// assert_eq!(loose.len(), 4);
+/// assert_eq!(loose[0].axis, MirrorAxis::Horizontal);
+/// assert_eq!(loose[0].mismatches.len(), 4);
+/// ```
+pub fn find_reflections<T: Copy + PartialEq>(
+    grid: &Grid<T>,
+    limit: MismatchLimit,
+) -> Vec<Reflection> {
+    let mut result = vec![];
+    for (axis, num_lines) in [
+        (MirrorAxis::Horizontal, grid.get_height()),
+        (MirrorAxis::Vertical, grid.get_width()),
+    ] {
+        for position in 1..num_lines {
+            if let Some(reflection) = check_reflection(grid, axis, position, limit) {
+                result.push(reflection);
+            }
         }
     }
-    rows.push(cur_row);
+    return result;
+}
 
-    if with_smudge {
-        return solve_for_sym_smudge(&rows);
-    }
-    return solve_for_sym(&rows);
+fn summarize_first(grid: &Grid<LavaTerrain>, limit: MismatchLimit) -> Option<u32> {
+    return find_reflections(grid, limit).first().map(|r| r.summary());
 }
 
 /// Find a vertical or horizontal line of reflection in each input
@@ -152,127 +206,12 @@ pub fn puzzle_a(string_list: &Vec<Vec<String>>) -> u32 {
     let input = parse_input(string_list);
     return input
         .into_iter()
-        .map(|grid| solve_for_horz_or_vert_sym(&grid).unwrap())
+        .map(|grid| summarize_first(&grid, MismatchLimit::Exactly(0)).unwrap())
         .sum();
 }
 
-fn terrain_to_bitmask(list: &Vec<LavaTerrain>) -> u32 {
-    let mut cur = 0;
-    for i in list {
-        cur *= 2;
-        cur += match i {
-            LavaTerrain::Rock => 1,
-            LavaTerrain::Ash => 0,
-        }
-    }
-    return cur;
-}
-
-fn count_ones(bitmask: u32) -> u32 {
-    let mut v = 0;
-    let mut my_bitmask = bitmask;
-    while my_bitmask > 0 {
-        v += 1;
-        my_bitmask = my_bitmask & (my_bitmask - 1);
-    }
-    return v;
-}
-
-fn solve_for_sym_smudge(list: &Vec<Vec<LavaTerrain>>) -> Option<u32> {
-    // Find an off by 1 reflection on grid
-    // Find two adjacent items on the list that are the same
-    // Then check forward and back they are the same
-    // continue until run out of spaces.
-    // Easy way here, we are going to map each row to a bitmask
-    // xor them, then count the remaining bits that are set to 1.
-    // if the difference == 1, thats the answer.
-
-    for (index, cur_list) in list.iter().enumerate() {
-        if index == 0 {
-            continue;
-        }
-        let mut difference_count = 0;
-        let last_index = index - 1;
-        let compare_one_bits = terrain_to_bitmask(cur_list);
-        let compare_two_bits = terrain_to_bitmask(&list[last_index]);
-        let xored = compare_one_bits ^ compare_two_bits;
-        difference_count += count_ones(xored);
-        if difference_count > 1 {
-            continue;
-        }
-        // println!("Possible reflection {} -\n{:?},\n{:?}", index, cur_list, list[index]);
-        // Possible reflection
-        let mut index_offset = 1;
-        while index_offset <= last_index && index + index_offset < list.len() {
-            let early_index = last_index - index_offset;
-            let late_index = index + index_offset;
-            let compare_one = list[early_index].clone();
-            let compare_two = list[late_index].clone();
-            let compare_one_bits = terrain_to_bitmask(&compare_one);
-            let compare_two_bits = terrain_to_bitmask(&compare_two);
-            let xored = compare_one_bits ^ compare_two_bits;
-            difference_count += count_ones(xored);
-            index_offset += 1;
-        }
-        if difference_count == 1 {
-            let result: u32 = index.try_into().unwrap();
-            return Some(result);
-        }
-    }
-    return None;
-}
-
-fn solve_for_horz_or_vert_sym_smudge(grid: &Grid<LavaTerrain>) -> u32 {
-    //println!();
-    //println!("{:?}", grid);
-    let v = solve_for_horz_sym(grid, true);
-    if let Some(g) = v {
-        //println!("Found horz {}", g);
-        return g * 100;
-    }
-    let vert = solve_for_vert_sym(grid, true);
-    //println!("Found vert {}", vert);
-    return vert.unwrap();
-
-    /* TODO: Why is this wrong? I feel there is an additional hidden constraint that isn't explained here.
-    let norm_value = solve_for_horz_or_vert_sym(grid).unwrap();
-    for coord in grid.coord_iter() {
-        let mut g: Grid<LavaTerrain> = Grid::new(grid.get_width(), grid.get_height(), grid.data_copy());
-        let cur = grid.get_value(coord).unwrap();
-        let new = match cur {
-            LavaTerrain::Ash => LavaTerrain::Rock,
-            LavaTerrain::Rock => LavaTerrain::Ash
-        };
-        g.set_value(coord, new);
-        let candidate_x = solve_for_horz_sym(&g);
-        let candidate_y = solve_for_vert_sym(&g);
-        if let Some(i) = candidate_x {
-            if let Some(j) = candidate_y {
-                if i * 100 != norm_value && j != norm_value {
-                    // Multiple lines discard
-                    continue;
-                }
-            }
-            if i * 100 != norm_value {
-                // We only have one result, and its different then the previous
-                // So thats a solution.
-                return i * 100;
-            }
-        }
-        if let Some(j) = candidate_y {
-            if j != norm_value {
-                // We only have one result, and its different than the previous
-                // so that's a solution
-                return j;
-            }
-        }
-    }
-    return 0;
-    */
-}
-
 /// As part 1, but we need to figure out where the smudge is.
-/// We can figure this out by... changing the value and seeing if we find an answer.
+/// The smudge is the one cell that doesn't match its reflection.
 /// ```
 /// let vec1: Vec<Vec<String>> = vec![vec![
 ///     "#.##..##.",
@@ -297,7 +236,7 @@ pub fn puzzle_b(string_list: &Vec<Vec<String>>) -> u32 {
     let input = parse_input(string_list);
     return input
         .into_iter()
-        .map(|grid| solve_for_horz_or_vert_sym_smudge(&grid))
+        .map(|grid| summarize_first(&grid, MismatchLimit::Exactly(1)).unwrap())
         .sum();
 }
 
@@ -335,22 +274,28 @@ mod tests {
         return parse_input(&vec1);
     }
 
+    fn positions(grid: &Grid<LavaTerrain>, axis: MirrorAxis, limit: MismatchLimit) -> Vec<usize> {
+        return find_reflections(grid, limit)
+            .iter()
+            .filter(|r| r.axis == axis)
+            .map(|r| r.position)
+            .collect();
+    }
+
     #[test]
     fn test_vertical() {
         let input = test_input();
-        let a = solve_for_vert_sym(&input[0], false);
-        assert_eq!(a, Some(5));
-        let b = solve_for_vert_sym(&input[1], false);
-        assert_eq!(b, None);
+        let exact = MismatchLimit::Exactly(0);
+        assert_eq!(positions(&input[0], MirrorAxis::Vertical, exact), vec![5]);
+        assert_eq!(positions(&input[1], MirrorAxis::Vertical, exact), vec![]);
     }
 
     #[test]
     fn test_horiz() {
         let input = test_input();
-        let a = solve_for_horz_sym(&input[0], false);
-        assert_eq!(a, None);
-        let b = solve_for_horz_sym(&input[1], false);
-        assert_eq!(b, Some(4));
+        let exact = MismatchLimit::Exactly(0);
+        assert_eq!(positions(&input[0], MirrorAxis::Horizontal, exact), vec![]);
+        assert_eq!(positions(&input[1], MirrorAxis::Horizontal, exact), vec![4]);
     }
 
     #[test]
@@ -367,46 +312,35 @@ mod tests {
                 r, r, r, r, r, r, a,
             ],
         );
-        let z = solve_for_horz_sym(&input, false);
-        assert_eq!(z, Some(2));
+        let z = positions(&input, MirrorAxis::Horizontal, MismatchLimit::Exactly(0));
+        assert_eq!(z, vec![2]);
     }
 
     #[test]
     fn test_smudge_input() {
         let input = test_input();
-        println!("Case 1");
-        let z = solve_for_horz_or_vert_sym_smudge(&input[0]);
-        assert_eq!(z, 300);
-
-        println!("Case 2");
-        let y = solve_for_horz_or_vert_sym_smudge(&input[1]);
-        assert_eq!(y, 100);
-    }
+        let smudged = find_reflections(&input[0], MismatchLimit::Exactly(1));
+        assert_eq!(smudged[0].summary(), 300);
+        assert_eq!(
+            smudged[0].mismatches,
+            vec![(GridCoordinate::new(0, 0), GridCoordinate::new(0, 5))]
+        );
 
-    #[test]
-    fn test_to_bitmask() {
-        let a = LavaTerrain::Ash;
-        let r = LavaTerrain::Rock;
-        let mut v = terrain_to_bitmask(&vec![]);
-        assert_eq!(v, 0);
-        v = terrain_to_bitmask(&vec![a]);
-        assert_eq!(v, 0);
-        v = terrain_to_bitmask(&vec![r]);
-        assert_eq!(v, 1);
-        v = terrain_to_bitmask(&vec![a, r]);
-        assert_eq!(v, 1);
-        v = terrain_to_bitmask(&vec![r, a]);
-        assert_eq!(v, 2);
+        let smudged = find_reflections(&input[1], MismatchLimit::Exactly(1));
+        assert_eq!(smudged[0].summary(), 100);
+        assert_eq!(
+            smudged[0].mismatches,
+            vec![(GridCoordinate::new(4, 0), GridCoordinate::new(4, 1))]
+        );
     }
 
     #[test]
-    fn test_count_ones() {
-        let mut v = count_ones(0);
-        assert_eq!(v, 0);
-        v = count_ones(1);
-        assert_eq!(v, 1);
-        // 1 + 2 + 4
-        v = count_ones(7);
-        assert_eq!(v, 3);
+    fn test_at_most_includes_exact() {
+        let input = test_input();
+        let at_most: Vec<u32> = find_reflections(&input[0], MismatchLimit::AtMost(1))
+            .iter()
+            .map(|r| r.summary())
+            .collect();
+        assert_eq!(at_most, vec![300, 5]);
     }
 }
@@ -0,0 +1,42 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// Puzzle sized platform, the same every run. Roughly a fifth round rocks and a sixth cubes,
+// which is about what real inputs have.
+fn generated_platform(size: usize) -> Vec<String> {
+    let mut state: u64 = 2023;
+    let mut lines = vec![];
+    for _ in 0..size {
+        let mut line = String::new();
+        for _ in 0..size {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            line.push(match (state >> 33) % 30 {
+                0..=5 => 'O',
+                6..=10 => '#',
+                _ => '.',
+            });
+        }
+        lines.push(line);
+    }
+    return lines;
+}
+
+fn bench_spin(c: &mut Criterion) {
+    let lines = generated_platform(100);
+    let mut group = c.benchmark_group("spin");
+    for cycles in [10, 1000000000] {
+        group.bench_with_input(BenchmarkId::new("naive", cycles), &cycles, |b, cycles| {
+            b.iter(|| day14::spin_load_naive(&lines, *cycles))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("bitboard", cycles),
+            &cycles,
+            |b, cycles| b.iter(|| day14::spin_load(&lines, *cycles)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_spin);
+criterion_main!(benches);
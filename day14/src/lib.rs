@@ -1,6 +1,8 @@
 extern crate filelib;
 
 pub use filelib::load_no_blanks;
+use gridlib::BitGrid;
+use gridlib::BitLine;
 use gridlib::Direction;
 use gridlib::Grid;
 use gridlib::GridCoordinate;
@@ -125,6 +127,174 @@ fn calc_load(grid: Grid<Terrain>) -> u32 {
     return result;
 }
 
+// Runs start..end of cells between the cube rocks in a line.
+fn free_runs(cubes: &BitLine) -> Vec<(usize, usize)> {
+    let mut runs = vec![];
+    let mut start = 0;
+    for cube in cubes.iter_ones() {
+        if cube > start {
+            runs.push((start, cube));
+        }
+        start = cube + 1;
+    }
+    if start < cubes.get_len() {
+        runs.push((start, cubes.get_len()));
+    }
+    return runs;
+}
+
+/// Platform of rocks stored as bitsets, by row and by column.
+///
+/// Cube rocks never move, so the runs of cells between them are found once. A tilt counts
+/// the round rocks in each run and packs them against one end of it.
+#[derive(Debug, Clone)]
+pub struct Platform {
+    height: usize,
+    // Round rocks, bit x of row y.
+    round_rows: BitGrid,
+    // Round rocks, bit y of row x. Always the transpose of round_rows.
+    round_cols: BitGrid,
+    row_runs: Vec<Vec<(usize, usize)>>,
+    col_runs: Vec<Vec<(usize, usize)>>,
+}
+
+impl Platform {
+    pub fn parse(string_list: &Vec<String>) -> Platform {
+        let grid = parse_input(string_list);
+        let round = BitGrid::from_grid(&grid, |t| t == Terrain::RoundRock);
+        let cubes = BitGrid::from_grid(&grid, |t| t == Terrain::CubeRock);
+        let cube_cols = cubes.transpose();
+        return Platform {
+            height: grid.get_height(),
+            round_cols: round.transpose(),
+            round_rows: round,
+            row_runs: (0..grid.get_height())
+                .map(|y| free_runs(cubes.get_row(y)))
+                .collect(),
+            col_runs: (0..grid.get_width())
+                .map(|x| free_runs(cube_cols.get_row(x)))
+                .collect(),
+        };
+    }
+
+    /// Roll every round rock as far as it goes in a cardinal direction.
+    pub fn tilt(&mut self, dir: Direction) {
+        // North and south roll along columns, east and west along rows.
+        let (by_column, to_start) = match dir {
+            Direction::NORTH => (true, true),
+            Direction::SOUTH => (true, false),
+            Direction::WEST => (false, true),
+            Direction::EAST => (false, false),
+            _ => panic!("Can only tilt {} in a cardinal direction", dir),
+        };
+        let (along, across, runs) = if by_column {
+            (&mut self.round_cols, &mut self.round_rows, &self.col_runs)
+        } else {
+            (&mut self.round_rows, &mut self.round_cols, &self.row_runs)
+        };
+        // Lines along the roll are packed a run at a time, the crossing lines a bit at a time.
+        across.clear();
+        for (line, line_runs) in runs.iter().enumerate() {
+            let bits = along.get_row_mut(line);
+            for (start, end) in line_runs.iter() {
+                let count = bits.count_ones_in(*start, *end);
+                let (from, to) = if to_start {
+                    (*start, start + count)
+                } else {
+                    (end - count, *end)
+                };
+                bits.set_range(*start, *end, false);
+                bits.set_range(from, to, true);
+                for position in from..to {
+                    across.get_row_mut(position).set(line, true);
+                }
+            }
+        }
+    }
+
+    /// Tilt north, west, south, then east.
+    pub fn spin_cycle(&mut self) {
+        self.tilt(Direction::NORTH);
+        self.tilt(Direction::WEST);
+        self.tilt(Direction::SOUTH);
+        self.tilt(Direction::EAST);
+    }
+
+    /// Run spin cycles, skipping ahead once the rocks repeat a position.
+    pub fn spin(&mut self, cycles: usize) {
+        let mut seen: HashMap<BitGrid, usize> = HashMap::new();
+        let mut history: Vec<BitGrid> = vec![];
+        for i in 0..cycles {
+            if let Some(first) = seen.get(&self.round_rows) {
+                let period = i - first;
+                let index = first + (cycles - first) % period;
+                self.round_rows = history[index].clone();
+                self.round_cols = self.round_rows.transpose();
+                return;
+            }
+            seen.insert(self.round_rows.clone(), i);
+            history.push(self.round_rows.clone());
+            self.spin_cycle();
+        }
+    }
+
+    /// Load on the north support beams.
+    pub fn load(&self) -> u32 {
+        let mut result: u32 = 0;
+        for y in 0..self.height {
+            let rocks: u32 = self.round_rows.get_row(y).count_ones().try_into().unwrap();
+            let distance: u32 = (self.height - y).try_into().unwrap();
+            result += rocks * distance;
+        }
+        return result;
+    }
+}
+
+/// Load after spinning, using the original cell by cell tilting. Kept to benchmark against.
+/// ```
+/// let vec1: Vec<String> = vec![
+///     "O....#....",
+///     "O.OO#....#",
+///     ".....##...",
+///     "OO.#O....O",
+///     ".O.....O#.",
+///     "O.#..O.#.#",
+///     "..O..#O..O",
+///     ".......O..",
+///     "#....###..",
+///     "#OO..#....",
+/// ].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(day14::spin_load_naive(&vec1, 1000000000), 64);
+/// ```
+pub fn spin_load_naive(string_list: &Vec<String>, cycles: usize) -> u32 {
+    let g = parse_input(string_list);
+    let h = spin_grid(g, cycles);
+    return calc_load(h);
+}
+
+/// Load after spinning.
+/// ```
+/// let vec1: Vec<String> = vec![
+///     "O....#....",
+///     "O.OO#....#",
+///     ".....##...",
+///     "OO.#O....O",
+///     ".O.....O#.",
+///     "O.#..O.#.#",
+///     "..O..#O..O",
+///     ".......O..",
+///     "#....###..",
+///     "#OO..#....",
+/// ].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(day14::spin_load(&vec1, 1), 87);
+/// assert_eq!(day14::spin_load(&vec1, 1000000000), 64);
+/// ```
+pub fn spin_load(string_list: &Vec<String>, cycles: usize) -> u32 {
+    let mut platform = Platform::parse(string_list);
+    platform.spin(cycles);
+    return platform.load();
+}
+
 /// Sum of the loads when you make all round rocks roll north.
 /// ```
 /// let vec1: Vec<String> = vec![
@@ -142,9 +312,9 @@ fn calc_load(grid: Grid<Terrain>) -> u32 {
 /// assert_eq!(day14::puzzle_a(&vec1), 136);
 /// ```
 pub fn puzzle_a(string_list: &Vec<String>) -> u32 {
-    let g = parse_input(string_list);
-    let h = tilt_grid(g, Direction::NORTH);
-    return calc_load(h);
+    let mut platform = Platform::parse(string_list);
+    platform.tilt(Direction::NORTH);
+    return platform.load();
 }
 
 type Cache = HashMap<Vec<Terrain>, (usize, Vec<Terrain>)>;
@@ -167,7 +337,7 @@ fn spin_grid(grid: Grid<Terrain>, iterations: usize) -> Grid<Terrain> {
         if cache.contains_key(&data_copy) {
             let (last_index, next) = cache[&data_copy].clone();
             let cycle_size = i - last_index;
-            number_left = (iterations - i) % cycle_size - 1;
+            number_left = (iterations - i - 1) % cycle_size;
             new_grid = Grid::new(new_grid.get_width(), new_grid.get_height(), next);
             break;
         }
@@ -204,9 +374,7 @@ fn spin_grid(grid: Grid<Terrain>, iterations: usize) -> Grid<Terrain> {
 /// assert_eq!(day14::puzzle_b(&vec1), 64);
 /// ```
 pub fn puzzle_b(string_list: &Vec<String>) -> u32 {
-    let g = parse_input(string_list);
-    let h = spin_grid(g, 1000000000);
-    return calc_load(h);
+    return spin_load(string_list, 1000000000);
 }
 
 #[cfg(test)]
@@ -370,4 +538,65 @@ mod tests {
         assert_eq!(rotated.get_height(), 2);
         assert_eq!(rotated.data_copy(), grid.data_copy());
     }
+
+    fn round_rocks(grid: &Grid<Terrain>) -> BitGrid {
+        return BitGrid::from_grid(grid, |t| t == Terrain::RoundRock);
+    }
+
+    #[test]
+    fn test_platform_tilt_matches_grid() {
+        let strs: Vec<String> = vec![
+            "O....#....",
+            "O.OO#....#",
+            ".....##...",
+            "OO.#O....O",
+            ".O.....O#.",
+            "O.#..O.#.#",
+            "..O..#O..O",
+            ".......O..",
+            "#....###..",
+            "#OO..#....",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        for dir in [
+            Direction::NORTH,
+            Direction::EAST,
+            Direction::SOUTH,
+            Direction::WEST,
+        ] {
+            let mut platform = Platform::parse(&strs);
+            platform.tilt(dir);
+            let expected = tilt_grid(test_input(), dir);
+            assert_eq!(platform.round_rows, round_rocks(&expected));
+            assert_eq!(platform.round_cols, round_rocks(&expected).transpose());
+            assert_eq!(platform.load(), calc_load(expected));
+        }
+    }
+
+    #[test]
+    fn test_platform_spin_matches_grid() {
+        let strs: Vec<String> = vec!["O.#O", "#O.O", "..O.", "O..#", ".#O."]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        for cycles in [1, 2, 3, 10, 1000] {
+            let mut platform = Platform::parse(&strs);
+            platform.spin(cycles);
+            let expected = spin_grid(parse_input(&strs), cycles);
+            assert_eq!(platform.round_rows, round_rocks(&expected));
+            assert_eq!(platform.load(), calc_load(expected));
+        }
+    }
+
+    #[test]
+    fn test_free_runs() {
+        let mut cubes = BitLine::new(8);
+        cubes.set(0, true);
+        cubes.set(3, true);
+        cubes.set(4, true);
+        assert_eq!(free_runs(&cubes), vec![(1, 3), (5, 8)]);
+        assert_eq!(free_runs(&BitLine::new(3)), vec![(0, 3)]);
+    }
 }
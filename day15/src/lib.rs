@@ -1,6 +1,8 @@
 extern crate filelib;
 
 pub use filelib::load_no_blanks;
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
 
 type HashType = u32;
 
@@ -14,18 +16,49 @@ fn to_hash_list(s_list: &Vec<String>) -> Vec<String> {
     return result;
 }
 
-fn hash(s: &str) -> HashType {
-    let mut result = 0;
+/// The Holiday ASCII String Helper algorithm, as a Hasher.
+///
+/// Every byte is added to the value, which is then multiplied by 17 and kept below 256.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct HolidayHasher {
+    value: u8,
+}
 
-    for c in s.chars() {
-        let ascii = c as u32;
-        result += ascii;
-        result *= 17;
-        result %= 256;
+impl Hasher for HolidayHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.value = self.value.wrapping_add(*byte).wrapping_mul(17);
+        }
     }
 
-    assert!(result <= 255);
-    return result;
+    fn finish(&self) -> u64 {
+        return self.value.into();
+    }
+}
+
+pub type BuildHolidayHasher = BuildHasherDefault<HolidayHasher>;
+
+/// A lens label. str ends its hash with an extra 0xff byte, a Label hashes only its text
+/// so its bucket is HASH of the label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label(pub String);
+
+impl Hash for Label {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(self.0.as_bytes());
+    }
+}
+
+impl From<&str> for Label {
+    fn from(s: &str) -> Self {
+        return Label(s.to_string());
+    }
+}
+
+fn hash(s: &str) -> HashType {
+    let mut hasher = HolidayHasher::default();
+    hasher.write(s.as_bytes());
+    return hasher.finish().try_into().unwrap();
 }
 
 /// We HASHing BOYS!
@@ -40,65 +73,120 @@ pub fn puzzle_a(string_list: &Vec<String>) -> HashType {
     return hash_list.into_iter().map(|x| hash(&x)).sum();
 }
 
-#[derive(Debug, Clone, Ord, PartialOrd, PartialEq, Eq, Hash)]
-struct LabeledLens {
-    focal_length: HashType,
-    label: String,
+const BUCKETS: usize = 256;
+
+/// Map with 256 buckets picked by a hash, where each bucket keeps its entries in the
+/// order they were first inserted.
+#[derive(Debug, Clone)]
+pub struct LensMap<K, V, S = BuildHolidayHasher> {
+    buckets: Vec<Vec<(K, V)>>,
+    build_hasher: S,
+}
+
+impl<K: Hash + Eq, V> LensMap<K, V> {
+    pub fn new() -> LensMap<K, V> {
+        return LensMap::with_hasher(BuildHolidayHasher::default());
+    }
+}
+
+impl<K: Hash + Eq, V> Default for LensMap<K, V> {
+    fn default() -> Self {
+        return LensMap::new();
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> LensMap<K, V, S> {
+    pub fn with_hasher(build_hasher: S) -> LensMap<K, V, S> {
+        return LensMap {
+            buckets: (0..BUCKETS).map(|_| vec![]).collect(),
+            build_hasher: build_hasher,
+        };
+    }
+
+    /// Which bucket a key belongs in.
+    pub fn bucket_index<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        let hashed: usize = self.build_hasher.hash_one(key).try_into().unwrap();
+        return hashed % BUCKETS;
+    }
+
+    /// Entries of one bucket, in insertion order.
+    pub fn bucket(&self, index: usize) -> &[(K, V)] {
+        return &self.buckets[index];
+    }
+
+    /// Replace the value in place if the key is already there, returning the old value.
+    /// Otherwise add it to the back of its bucket.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let index = self.bucket_index(&key);
+        let bucket = &mut self.buckets[index];
+        if let Some(entry) = bucket.iter_mut().find(|(k, _)| *k == key) {
+            return Some(std::mem::replace(&mut entry.1, value));
+        }
+        bucket.push((key, value));
+        return None;
+    }
+
+    /// Take the key out of its bucket, the entries behind it move forward.
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let index = self.bucket_index(key);
+        let bucket = &mut self.buckets[index];
+        let position = bucket.iter().position(|(k, _)| k.borrow() == key)?;
+        return Some(bucket.remove(position).1);
+    }
+
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let index = self.bucket_index(key);
+        return self.buckets[index]
+            .iter()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(_, v)| v);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.buckets.iter().map(|b| b.len()).sum();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.buckets.iter().all(|b| b.is_empty());
+    }
+
+    /// Every entry as (bucket, slot, key, value), bucket by bucket.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &K, &V)> {
+        return self
+            .buckets
+            .iter()
+            .enumerate()
+            .flat_map(|(bucket, entries)| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .map(move |(slot, (k, v))| (bucket, slot, k, v))
+            });
+    }
 }
 
-fn hash_map(maps: &Vec<Vec<LabeledLens>>, s: &str) -> Vec<Vec<LabeledLens>> {
-    let mut new_maps = maps.clone();
+fn apply_step(maps: &mut LensMap<Label, HashType>, s: &str) {
     if s.chars().last().unwrap().is_numeric() {
         // = case
         let (label, value) = s.split_once("=").unwrap();
         let value_parsed: HashType = value.parse().unwrap();
-        let lens = LabeledLens {
-            label: label.to_string(),
-            focal_length: value_parsed,
-        };
-        let box_num: usize = hash(label).try_into().unwrap();
-        let mut match_found = false;
-        for (index, x) in maps[box_num].clone().into_iter().enumerate() {
-            if x.label == lens.label {
-                new_maps[box_num][index] = lens.clone();
-                match_found = true;
-                break;
-            }
-        }
-        if !match_found {
-            new_maps[box_num].push(lens);
-        }
+        maps.insert(Label::from(label), value_parsed);
     } else {
         let (label, _) = s.split_once("-").unwrap();
-        let box_num: usize = hash(label).try_into().unwrap();
-        let mut offset = 0;
-        let base = maps[box_num].clone();
-        let base_len = base.len();
-        for (index, x) in base.into_iter().enumerate() {
-            if index + offset > base_len {
-                break;
-            }
-            if offset == 0 {
-                if x.label == label {
-                    offset += 1;
-                }
-            } else {
-                // we found one, move things over
-                new_maps[box_num][index - offset] = x;
-            }
-        }
-        while offset > 0 {
-            new_maps[box_num].pop();
-            offset -= 1;
-        }
+        maps.remove(&Label::from(label));
     }
-    return new_maps;
 }
 
-fn focus_power(l: &LabeledLens, box_index: usize, slot_index: usize) -> HashType {
+fn focus_power(focal_length: HashType, box_index: usize, slot_index: usize) -> HashType {
     let box_num: HashType = (box_index + 1).try_into().unwrap();
     let slot_num: HashType = (slot_index + 1).try_into().unwrap();
-    return l.focal_length * box_num * slot_num;
+    return focal_length * box_num * slot_num;
 }
 
 /// We HASH MAPPING BOYS
@@ -110,20 +198,14 @@ fn focus_power(l: &LabeledLens, box_index: usize, slot_index: usize) -> HashType
 /// ```
 pub fn puzzle_b(string_list: &Vec<String>) -> HashType {
     let hash_list = to_hash_list(string_list);
-    let mut maps: Vec<Vec<LabeledLens>> = vec![];
-    for _ in 0..256 {
-        maps.push(vec![]);
-    }
+    let mut maps: LensMap<Label, HashType> = LensMap::new();
     for x in hash_list {
-        maps = hash_map(&maps, &x);
+        apply_step(&mut maps, &x);
     }
-    let mut result = 0;
-    for (index, cur_box) in maps.iter().enumerate() {
-        for (len_index, cur_lens) in cur_box.iter().enumerate() {
-            result += focus_power(&cur_lens, index, len_index);
-        }
-    }
-    return result;
+    return maps
+        .iter()
+        .map(|(index, slot, _, focal_length)| focus_power(*focal_length, index, slot))
+        .sum();
 }
 
 #[cfg(test)]
@@ -199,41 +281,23 @@ mod tests {
     #[test]
     fn test_hashmap_rn1() {
         let hash_list: Vec<String> = vec!["rn=1"].iter().map(|s| s.to_string()).collect();
-        let mut maps: Vec<Vec<LabeledLens>> = vec![];
-        for _ in 0..256 {
-            maps.push(vec![]);
-        }
+        let mut maps: LensMap<Label, HashType> = LensMap::new();
         for x in hash_list {
-            maps = hash_map(&maps, &x);
+            apply_step(&mut maps, &x);
         }
-        assert_eq!(maps[0].len(), 1);
-        assert_eq!(
-            maps[0][0],
-            LabeledLens {
-                label: "rn".to_string(),
-                focal_length: 1
-            }
-        );
+        assert_eq!(maps.bucket(0).len(), 1);
+        assert_eq!(maps.bucket(0)[0], (Label::from("rn"), 1));
     }
 
     #[test]
     fn test_hashmap_cm() {
         let hash_list: Vec<String> = vec!["rn=1", "cm-"].iter().map(|s| s.to_string()).collect();
-        let mut maps: Vec<Vec<LabeledLens>> = vec![];
-        for _ in 0..256 {
-            maps.push(vec![]);
-        }
+        let mut maps: LensMap<Label, HashType> = LensMap::new();
         for x in hash_list {
-            maps = hash_map(&maps, &x);
+            apply_step(&mut maps, &x);
         }
-        assert_eq!(maps[0].len(), 1);
-        assert_eq!(
-            maps[0][0],
-            LabeledLens {
-                label: "rn".to_string(),
-                focal_length: 1
-            }
-        );
+        assert_eq!(maps.bucket(0).len(), 1);
+        assert_eq!(maps.bucket(0)[0], (Label::from("rn"), 1));
     }
 
     #[test]
@@ -242,29 +306,14 @@ mod tests {
             .iter()
             .map(|s| s.to_string())
             .collect();
-        let mut maps: Vec<Vec<LabeledLens>> = vec![];
-        for _ in 0..256 {
-            maps.push(vec![]);
-        }
+        let mut maps: LensMap<Label, HashType> = LensMap::new();
         for x in hash_list {
-            maps = hash_map(&maps, &x);
+            apply_step(&mut maps, &x);
         }
-        assert_eq!(maps[0].len(), 1);
-        assert_eq!(
-            maps[0][0],
-            LabeledLens {
-                label: "rn".to_string(),
-                focal_length: 1
-            }
-        );
-        assert_eq!(maps[1].len(), 1);
-        assert_eq!(
-            maps[1][0],
-            LabeledLens {
-                label: "qp".to_string(),
-                focal_length: 3
-            }
-        );
+        assert_eq!(maps.bucket(0).len(), 1);
+        assert_eq!(maps.bucket(0)[0], (Label::from("rn"), 1));
+        assert_eq!(maps.bucket(1).len(), 1);
+        assert_eq!(maps.bucket(1)[0], (Label::from("qp"), 3));
     }
 
     #[test]
@@ -273,36 +322,15 @@ mod tests {
             .iter()
             .map(|s| s.to_string())
             .collect();
-        let mut maps: Vec<Vec<LabeledLens>> = vec![];
-        for _ in 0..256 {
-            maps.push(vec![]);
-        }
+        let mut maps: LensMap<Label, HashType> = LensMap::new();
         for x in hash_list {
-            maps = hash_map(&maps, &x);
+            apply_step(&mut maps, &x);
         }
-        assert_eq!(maps[0].len(), 2);
-        assert_eq!(
-            maps[0][0],
-            LabeledLens {
-                label: "rn".to_string(),
-                focal_length: 1
-            }
-        );
-        assert_eq!(
-            maps[0][1],
-            LabeledLens {
-                label: "cm".to_string(),
-                focal_length: 2
-            }
-        );
-        assert_eq!(maps[1].len(), 1);
-        assert_eq!(
-            maps[1][0],
-            LabeledLens {
-                label: "qp".to_string(),
-                focal_length: 3
-            }
-        );
+        assert_eq!(maps.bucket(0).len(), 2);
+        assert_eq!(maps.bucket(0)[0], (Label::from("rn"), 1));
+        assert_eq!(maps.bucket(0)[1], (Label::from("cm"), 2));
+        assert_eq!(maps.bucket(1).len(), 1);
+        assert_eq!(maps.bucket(1)[0], (Label::from("qp"), 3));
     }
 
     #[test]
@@ -311,28 +339,66 @@ mod tests {
             .iter()
             .map(|s| s.to_string())
             .collect();
-        let mut maps: Vec<Vec<LabeledLens>> = vec![];
-        for _ in 0..256 {
-            maps.push(vec![]);
-        }
+        let mut maps: LensMap<Label, HashType> = LensMap::new();
         for x in hash_list {
-            maps = hash_map(&maps, &x);
+            apply_step(&mut maps, &x);
         }
-        assert_eq!(maps[0].len(), 2);
-        assert_eq!(
-            maps[0][0],
-            LabeledLens {
-                label: "rn".to_string(),
-                focal_length: 1
-            }
-        );
+        assert_eq!(maps.bucket(0).len(), 2);
+        assert_eq!(maps.bucket(0)[0], (Label::from("rn"), 1));
+        assert_eq!(maps.bucket(0)[1], (Label::from("cm"), 2));
+        assert_eq!(maps.bucket(1).len(), 0);
+    }
+
+    #[test]
+    fn test_hasher_matches_hash() {
+        let build = BuildHolidayHasher::default();
+        assert_eq!(build.hash_one(Label::from("rn")), 0);
+        assert_eq!(build.hash_one(Label::from("qp")), 1);
+        assert_eq!(build.hash_one(Label::from("HASH")), 52);
+    }
+
+    #[test]
+    fn test_lensmap_replace_in_place() {
+        let mut maps: LensMap<Label, HashType> = LensMap::new();
+        assert_eq!(maps.insert(Label::from("rn"), 1), None);
+        assert_eq!(maps.insert(Label::from("cm"), 2), None);
+        assert_eq!(maps.insert(Label::from("rn"), 7), Some(1));
         assert_eq!(
-            maps[0][1],
-            LabeledLens {
-                label: "cm".to_string(),
-                focal_length: 2
-            }
+            maps.bucket(0),
+            &[(Label::from("rn"), 7), (Label::from("cm"), 2)]
         );
-        assert_eq!(maps[1].len(), 0);
+        assert_eq!(maps.get(&Label::from("rn")), Some(&7));
+        assert_eq!(maps.len(), 2);
+    }
+
+    #[test]
+    fn test_lensmap_remove() {
+        let mut maps: LensMap<Label, HashType> = LensMap::new();
+        maps.insert(Label::from("rn"), 1);
+        maps.insert(Label::from("cm"), 2);
+        assert_eq!(maps.remove(&Label::from("ab")), None);
+        assert_eq!(maps.remove(&Label::from("rn")), Some(1));
+        assert_eq!(maps.bucket(0), &[(Label::from("cm"), 2)]);
+        assert_eq!(maps.remove(&Label::from("cm")), Some(2));
+        assert!(maps.is_empty());
+    }
+
+    #[test]
+    fn test_lensmap_other_types() {
+        let mut maps: LensMap<u8, &str> = LensMap::new();
+        maps.insert(3, "three");
+        // (3 * 17) % 256
+        assert_eq!(maps.bucket_index(&3u8), 51);
+        assert_eq!(maps.get(&3), Some(&"three"));
+        let entries: Vec<(usize, usize, &u8, &&str)> = maps.iter().collect();
+        assert_eq!(entries, vec![(51, 0, &3, &"three")]);
+    }
+
+    #[test]
+    fn test_lensmap_u8_max() {
+        let mut maps: LensMap<u8, &str> = LensMap::new();
+        maps.insert(255, "max");
+        assert_eq!(maps.bucket_index(&255u8), (255 * 17) % 256);
+        assert_eq!(maps.bucket((255 * 17) % 256), &[(255, "max")]);
     }
 }
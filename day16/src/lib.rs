@@ -1,13 +1,37 @@
 extern crate filelib;
 
 pub use filelib::load_no_blanks;
+use gridlib::BitGrid;
+use gridlib::BitLine;
 use gridlib::Direction;
 use gridlib::Grid;
 use gridlib::GridCoordinate;
 use gridlib::GridTraversable;
+use parallellib::parallel_map;
+pub use parallellib::set_jobs_from_args;
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::Graph;
+use petgraph::graph::NodeIndex;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
-use std::thread;
+use std::sync::Arc;
+
+/// Anything a beam can meet on a tile of the contraption.
+pub trait OpticalElement: Send + Sync {
+    /// Directions beams leave the tile in, when a beam enters travelling in direction.
+    /// An empty list absorbs the beam.
+    fn exit_directions(&self, direction: Direction) -> Vec<Direction>;
+}
+
+struct EmptySpace;
+
+impl OpticalElement for EmptySpace {
+    fn exit_directions(&self, direction: Direction) -> Vec<Direction> {
+        // Keep going in current direction
+        return vec![direction];
+    }
+}
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, PartialEq, Eq, Hash)]
 enum MirrorDir {
@@ -15,51 +39,215 @@ enum MirrorDir {
     NorthwestToSoutheast,
 }
 
+impl OpticalElement for MirrorDir {
+    fn exit_directions(&self, direction: Direction) -> Vec<Direction> {
+        return match self {
+            MirrorDir::SouthwestToNortheast => {
+                // This is a /
+                // If dir = East, become North, if dir = south, become West
+                // If dir = west, become south, if dir = north, become east
+                match direction {
+                    Direction::EAST => vec![Direction::NORTH],
+                    Direction::NORTH => vec![Direction::EAST],
+                    Direction::SOUTH => vec![Direction::WEST],
+                    Direction::WEST => vec![Direction::SOUTH],
+                    _ => panic!("Not supported"),
+                }
+            }
+            MirrorDir::NorthwestToSoutheast => {
+                // This is a \
+                // If dir = East, become South, if dir = south, become east
+                // If dir = west, become North, if dir = north, become West
+                match direction {
+                    Direction::EAST => vec![Direction::SOUTH],
+                    Direction::SOUTH => vec![Direction::EAST],
+                    Direction::NORTH => vec![Direction::WEST],
+                    Direction::WEST => vec![Direction::NORTH],
+                    _ => panic!("Not supported"),
+                }
+            }
+        };
+    }
+}
+
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, PartialEq, Eq, Hash)]
 enum SplitterDir {
     HorizontalToVertical,
     VerticalToHorizontal,
 }
 
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, PartialEq, Eq, Hash)]
-enum Terrain {
-    Empty,
-    Mirror(MirrorDir),
-    Splitter(SplitterDir),
+impl OpticalElement for SplitterDir {
+    fn exit_directions(&self, direction: Direction) -> Vec<Direction> {
+        return match self {
+            SplitterDir::HorizontalToVertical => {
+                // This is a |
+                // If north south, treat as empty
+                // If east west, split into two beams, one north, one south
+                match direction {
+                    Direction::NORTH | Direction::SOUTH => vec![direction],
+                    Direction::EAST | Direction::WEST => vec![Direction::NORTH, Direction::SOUTH],
+                    _ => panic!("Not supported"),
+                }
+            }
+            SplitterDir::VerticalToHorizontal => {
+                // This is a -
+                // If north south, split into two beams, one east, one west
+                // If east west, treat as empty
+                match direction {
+                    Direction::NORTH | Direction::SOUTH => vec![Direction::EAST, Direction::WEST],
+                    Direction::EAST | Direction::WEST => vec![direction],
+                    _ => panic!("Not supported"),
+                }
+            }
+        };
+    }
 }
 
-fn parse_grid(lines: &Vec<String>) -> Grid<Terrain> {
-    let width = lines[0].len();
-    let height = lines.len();
-    let mut grid_values = vec![];
-
-    for line in lines {
-        for c in line.chars() {
-            let tile = match c {
-                '.' => Terrain::Empty,
-                '/' => Terrain::Mirror(MirrorDir::SouthwestToNortheast),
-                '\\' => Terrain::Mirror(MirrorDir::NorthwestToSoutheast),
-                '|' => Terrain::Splitter(SplitterDir::HorizontalToVertical),
-                '-' => Terrain::Splitter(SplitterDir::VerticalToHorizontal),
-                _ => panic!("SAVE MEEEEEE"),
-            };
-            grid_values.push(tile);
+/// Which optical element each character in the input stands for.
+#[derive(Clone)]
+pub struct ElementRegistry {
+    symbols: HashMap<char, usize>,
+    elements: Vec<Arc<dyn OpticalElement>>,
+}
+
+impl ElementRegistry {
+    /// No elements at all, not even empty space.
+    pub fn new() -> ElementRegistry {
+        return ElementRegistry {
+            symbols: HashMap::new(),
+            elements: vec![],
+        };
+    }
+
+    /// Empty space, the two mirrors and the two splitters from the puzzle.
+    pub fn standard() -> ElementRegistry {
+        let mut registry = ElementRegistry::new();
+        registry.register('.', EmptySpace);
+        registry.register('/', MirrorDir::SouthwestToNortheast);
+        registry.register('\\', MirrorDir::NorthwestToSoutheast);
+        registry.register('|', SplitterDir::HorizontalToVertical);
+        registry.register('-', SplitterDir::VerticalToHorizontal);
+        return registry;
+    }
+
+    /// Use element wherever symbol appears, replacing anything already registered for it.
+    pub fn register<E: OpticalElement + 'static>(&mut self, symbol: char, element: E) {
+        match self.symbols.get(&symbol) {
+            Some(index) => self.elements[*index] = Arc::new(element),
+            None => {
+                self.symbols.insert(symbol, self.elements.len());
+                self.elements.push(Arc::new(element));
+            }
         }
     }
+}
 
-    return Grid::new(width, height, grid_values);
+impl Default for ElementRegistry {
+    fn default() -> Self {
+        return ElementRegistry::new();
+    }
 }
 
-fn ray_trace(grid: &Grid<Terrain>) -> Vec<GridCoordinate> {
+/// Grid of optical elements.
+pub struct Contraption {
+    // Index into elements for each tile.
+    grid: Grid<usize>,
+    elements: Vec<Arc<dyn OpticalElement>>,
+    // Elements every beam goes straight through.
+    transparent: Vec<bool>,
+}
+
+impl Contraption {
+    /// Panics on a character that isn't registered.
+    /// ```
+    /// use day16::{Contraption, ElementRegistry, OpticalElement};
+    /// use gridlib::{Direction, GridCoordinate};
+    ///
+    /// struct Absorber;
+    /// impl OpticalElement for Absorber {
+    ///     fn exit_directions(&self, _direction: Direction) -> Vec<Direction> {
+    ///         return vec![];
+    ///     }
+    /// }
+    ///
+    /// let mut registry = ElementRegistry::standard();
+    /// registry.register('#', Absorber);
+    /// let lines: Vec<String> = vec![r".\.", r"..#", r".#."].iter().map(|s| s.to_string()).collect();
+    /// let contraption = Contraption::parse(&lines, &registry);
+    /// assert_eq!(contraption.get_width(), 3);
+    ///
+    /// // Down off the mirror and into the absorber.
+    /// let graph = day16::BeamGraph::new(contraption);
+    /// let lit = graph.energized(GridCoordinate::new(0, 0), Direction::EAST);
+    /// assert_eq!(lit.count_ones(), 4);
+    /// ```
+    pub fn parse(lines: &Vec<String>, registry: &ElementRegistry) -> Contraption {
+        let width = lines[0].len();
+        let height = lines.len();
+        let mut grid_values = vec![];
+
+        for line in lines {
+            for c in line.chars() {
+                match registry.symbols.get(&c) {
+                    Some(index) => grid_values.push(*index),
+                    None => panic!("No optical element registered for '{}'", c),
+                }
+            }
+        }
+
+        let transparent = registry
+            .elements
+            .iter()
+            .map(|element| {
+                [
+                    Direction::NORTH,
+                    Direction::EAST,
+                    Direction::SOUTH,
+                    Direction::WEST,
+                ]
+                .into_iter()
+                .all(|direction| element.exit_directions(direction) == vec![direction])
+            })
+            .collect();
+        return Contraption {
+            grid: Grid::new(width, height, grid_values),
+            elements: registry.elements.clone(),
+            transparent: transparent,
+        };
+    }
+
+    pub fn get_width(&self) -> usize {
+        return self.grid.get_width();
+    }
+
+    pub fn get_height(&self) -> usize {
+        return self.grid.get_height();
+    }
+
+    fn exit_directions(&self, coord: GridCoordinate, direction: Direction) -> Vec<Direction> {
+        let element = self.grid.get_value(coord).unwrap();
+        return self.elements[element].exit_directions(direction);
+    }
+
+    fn is_transparent(&self, coord: GridCoordinate) -> bool {
+        return self.transparent[self.grid.get_value(coord).unwrap()];
+    }
+}
+
+fn parse_grid(lines: &Vec<String>) -> Contraption {
+    return Contraption::parse(lines, &ElementRegistry::standard());
+}
+
+fn ray_trace(contraption: &Contraption) -> Vec<GridCoordinate> {
     // It starts at 0,0 with direction East
     let start = GridCoordinate::new(0, 0);
     let start_direction = Direction::EAST;
-    return ray_trace_inner(grid, start, start_direction);
+    return ray_trace_inner(contraption, start, start_direction);
 }
 
 // Factor out for part b
 fn ray_trace_inner(
-    grid: &Grid<Terrain>,
+    contraption: &Contraption,
     start: GridCoordinate,
     start_direction: Direction,
 ) -> Vec<GridCoordinate> {
@@ -76,109 +264,12 @@ fn ray_trace_inner(
         }
         visited_coords.insert(coord);
         cache.insert((cur_direction, coord));
-        let terrain = grid.get_value(coord).unwrap();
-        match terrain {
-            Terrain::Empty => {
-                // Keep going in current direction
-                if let Some(next_coord) = grid.get_coordinate_by_direction(coord, cur_direction) {
-                    to_visit.push_back((cur_direction, next_coord));
-                }
-            }
-            Terrain::Mirror(dir) => {
-                match dir {
-                    MirrorDir::SouthwestToNortheast => {
-                        // This is a /
-                        // If dir = East, become North, if dir = south, become West
-                        // If dir = west, become south, if dir = north, become east
-                        let next_direction = match cur_direction {
-                            Direction::EAST => Direction::NORTH,
-                            Direction::NORTH => Direction::EAST,
-                            Direction::SOUTH => Direction::WEST,
-                            Direction::WEST => Direction::SOUTH,
-                            _ => panic!("Not supported"),
-                        };
-                        if let Some(next_coord) =
-                            grid.get_coordinate_by_direction(coord, next_direction)
-                        {
-                            to_visit.push_back((next_direction, next_coord));
-                        }
-                    }
-                    MirrorDir::NorthwestToSoutheast => {
-                        // This is a \
-                        // If dir = East, become South, if dir = south, become east
-                        // If dir = west, become North, if dir = north, become West
-                        let next_direction = match cur_direction {
-                            Direction::EAST => Direction::SOUTH,
-                            Direction::SOUTH => Direction::EAST,
-                            Direction::NORTH => Direction::WEST,
-                            Direction::WEST => Direction::NORTH,
-                            _ => panic!("Not supported"),
-                        };
-                        if let Some(next_coord) =
-                            grid.get_coordinate_by_direction(coord, next_direction)
-                        {
-                            to_visit.push_back((next_direction, next_coord));
-                        }
-                    }
-                }
-            }
-            Terrain::Splitter(dir) => {
-                match dir {
-                    SplitterDir::HorizontalToVertical => {
-                        // This is a |
-                        // If north south, treat as empty
-                        // If east west, split into two beams, one north, one south
-                        match cur_direction {
-                            Direction::NORTH | Direction::SOUTH => {
-                                if let Some(next_coord) =
-                                    grid.get_coordinate_by_direction(coord, cur_direction)
-                                {
-                                    to_visit.push_back((cur_direction, next_coord));
-                                }
-                            }
-                            Direction::EAST | Direction::WEST => {
-                                if let Some(north_coord) =
-                                    grid.get_coordinate_by_direction(coord, Direction::NORTH)
-                                {
-                                    to_visit.push_back((Direction::NORTH, north_coord));
-                                }
-                                if let Some(south_coord) =
-                                    grid.get_coordinate_by_direction(coord, Direction::SOUTH)
-                                {
-                                    to_visit.push_back((Direction::SOUTH, south_coord));
-                                }
-                            }
-                            _ => panic!("Not supported"),
-                        }
-                    }
-                    SplitterDir::VerticalToHorizontal => {
-                        // This is a -
-                        // If north south, split into two beams, one east, one west
-                        // If east west, treat as empty
-                        match cur_direction {
-                            Direction::NORTH | Direction::SOUTH => {
-                                if let Some(east_coord) =
-                                    grid.get_coordinate_by_direction(coord, Direction::EAST)
-                                {
-                                    to_visit.push_back((Direction::EAST, east_coord));
-                                }
-                                if let Some(west_coord) =
-                                    grid.get_coordinate_by_direction(coord, Direction::WEST)
-                                {
-                                    to_visit.push_back((Direction::WEST, west_coord));
-                                }
-                            }
-                            Direction::EAST | Direction::WEST => {
-                                if let Some(next_coord) =
-                                    grid.get_coordinate_by_direction(coord, cur_direction)
-                                {
-                                    to_visit.push_back((cur_direction, next_coord));
-                                }
-                            }
-                            _ => panic!("Not supported"),
-                        }
-                    }
-                }
+        for next_direction in contraption.exit_directions(coord, cur_direction) {
+            if let Some(next_coord) = contraption
+                .grid
+                .get_coordinate_by_direction(coord, next_direction)
+            {
+                to_visit.push_back((next_direction, next_coord));
             }
         }
     }
@@ -208,7 +299,8 @@ pub fn puzzle_a(string_list: &Vec<String>) -> usize {
     return energized_tiles.len();
 }
 
-fn generate_entrances(grid: &Grid<Terrain>) -> Vec<(Direction, GridCoordinate)> {
+fn generate_entrances(contraption: &Contraption) -> Vec<(Direction, GridCoordinate)> {
+    let grid = &contraption.grid;
     let mut results = vec![];
     let max_x = grid.get_width() - 1;
     let max_y = grid.get_height() - 1;
@@ -229,6 +321,145 @@ fn generate_entrances(grid: &Grid<Terrain>) -> Vec<(Direction, GridCoordinate)>
     return results;
 }
 
+// A beam arriving at an optical element, travelling in a direction.
+type BeamArrival = (GridCoordinate, Direction);
+
+// Follow a beam from start, through transparent tiles, to the first other element it reaches.
+// Returns the tiles passed, and the arrival at that element if it doesn't leave the grid.
+fn follow_beam(
+    contraption: &Contraption,
+    start: GridCoordinate,
+    direction: Direction,
+) -> (Vec<GridCoordinate>, Option<BeamArrival>) {
+    let mut tiles = vec![];
+    let mut coord = start;
+    loop {
+        if !contraption.is_transparent(coord) {
+            return (tiles, Some((coord, direction)));
+        }
+        tiles.push(coord);
+        match contraption
+            .grid
+            .get_coordinate_by_direction(coord, direction)
+        {
+            Some(next) => coord = next,
+            None => return (tiles, None),
+        }
+    }
+}
+
+/// The contraption compiled into a graph of beam segments between optical elements.
+///
+/// Each node is a beam arriving at an element that isn't transparent, and an edge is the
+/// straight run of transparent tiles to the next one. Beams can loop, so the graph is
+/// condensed into strongly connected components, and the energized tiles of every
+/// component are worked out once, successors first. Any entrance is then one walk to its
+/// first element plus a lookup.
+pub struct BeamGraph {
+    contraption: Contraption,
+    component_of: HashMap<BeamArrival, usize>,
+    // Per component, bit x + y * width is set for each tile lit.
+    energized: Vec<BitLine>,
+}
+
+impl BeamGraph {
+    pub fn parse(string_list: &Vec<String>) -> BeamGraph {
+        return BeamGraph::new(parse_grid(string_list));
+    }
+
+    pub fn new(contraption: Contraption) -> BeamGraph {
+        let grid = &contraption.grid;
+        let mut graph: Graph<BeamArrival, ()> = Graph::new();
+        let mut nodes: HashMap<BeamArrival, NodeIndex> = HashMap::new();
+        for coord in grid.coord_iter() {
+            if contraption.is_transparent(coord) {
+                continue;
+            }
+            for direction in [
+                Direction::NORTH,
+                Direction::EAST,
+                Direction::SOUTH,
+                Direction::WEST,
+            ] {
+                nodes.insert((coord, direction), graph.add_node((coord, direction)));
+            }
+        }
+
+        // Tiles lit by a node itself: its element, and the runs leaving it.
+        let mut own_tiles: Vec<Vec<GridCoordinate>> = vec![vec![]; graph.node_count()];
+        for (&(coord, direction), &node) in nodes.iter() {
+            let tiles = &mut own_tiles[node.index()];
+            tiles.push(coord);
+            for exit in contraption.exit_directions(coord, direction) {
+                let Some(next) = grid.get_coordinate_by_direction(coord, exit) else {
+                    continue;
+                };
+                let (passed, arrival) = follow_beam(&contraption, next, exit);
+                tiles.extend(passed);
+                if let Some(arrival) = arrival {
+                    graph.add_edge(node, nodes[&arrival], ());
+                }
+            }
+        }
+
+        // Tarjan gives the components in reverse topological order, so everything a
+        // component leads to has already been filled in.
+        let width = grid.get_width();
+        let components = tarjan_scc(&graph);
+        let mut component_index = vec![0; graph.node_count()];
+        let mut energized: Vec<BitLine> = Vec::with_capacity(components.len());
+        for (index, members) in components.iter().enumerate() {
+            for node in members.iter() {
+                component_index[node.index()] = index;
+            }
+            let mut lit = BitLine::new(width * grid.get_height());
+            for node in members.iter() {
+                for tile in own_tiles[node.index()].iter() {
+                    lit.set(tile.x + tile.y * width, true);
+                }
+                for next in graph.neighbors(*node) {
+                    let next_index = component_index[next.index()];
+                    if next_index != index {
+                        lit |= &energized[next_index];
+                    }
+                }
+            }
+            energized.push(lit);
+        }
+
+        let component_of = nodes
+            .iter()
+            .map(|(arrival, node)| (*arrival, component_index[node.index()]))
+            .collect();
+        return BeamGraph {
+            contraption: contraption,
+            component_of: component_of,
+            energized: energized,
+        };
+    }
+
+    /// Tiles energized by a beam entering at start, travelling in direction.
+    pub fn energized(&self, start: GridCoordinate, direction: Direction) -> BitGrid {
+        let width = self.contraption.get_width();
+        let (passed, arrival) = follow_beam(&self.contraption, start, direction);
+        let mut lit = BitGrid::new(width, self.contraption.get_height());
+        if let Some(arrival) = arrival {
+            for index in self.energized[self.component_of[&arrival]].iter_ones() {
+                lit.set_value(GridCoordinate::new(index % width, index / width), true);
+            }
+        }
+        for tile in passed {
+            lit.set_value(tile, true);
+        }
+        return lit;
+    }
+
+    /// Every edge tile with the direction that points into the grid, corners twice.
+    pub fn entrances(&self) -> Vec<(Direction, GridCoordinate)> {
+        return generate_entrances(&self.contraption);
+    }
+}
+
 /// Try from every possible start point
 /// ```
 /// let vec1: Vec<String> = vec![
@@ -246,20 +477,13 @@ fn generate_entrances(grid: &Grid<Terrain>) -> Vec<(Direction, GridCoordinate)>
 /// assert_eq!(day16::puzzle_b(&vec1), 51);
 /// ```
 pub fn puzzle_b(string_list: &Vec<String>) -> usize {
-    let grid = parse_grid(string_list);
-    let entrances = generate_entrances(&grid);
-    return entrances
-        .into_iter()
-        .map(|(direction, origin)| {
-            let thread_grid = grid.clone();
-            let handle = thread::spawn(move || {
-                return ray_trace_inner(&thread_grid, origin, direction).len();
-            });
-            return handle;
-        })
-        .map(|handle| handle.join().unwrap())
-        .max()
-        .unwrap();
+    let graph = BeamGraph::parse(string_list);
+    return parallel_map(&graph.entrances(), |(direction, origin)| {
+        graph.energized(*origin, *direction).count_ones()
+    })
+    .into_iter()
+    .max()
+    .unwrap();
 }
 
 #[cfg(test)]
@@ -272,4 +496,157 @@ mod tests {
         let grid = parse_grid(&vec1);
         assert_eq!(ray_trace(&grid).len(), 4);
     }
+
+    fn assert_matches_ray_trace(lines: &Vec<String>, registry: &ElementRegistry) {
+        let contraption = Contraption::parse(lines, registry);
+        let graph = BeamGraph::new(Contraption::parse(lines, registry));
+        for (direction, origin) in generate_entrances(&contraption) {
+            let mut expected = ray_trace_inner(&contraption, origin, direction);
+            expected.sort();
+            let actual: Vec<GridCoordinate> =
+                graph.energized(origin, direction).iter_ones().collect();
+            assert_eq!(actual, expected, "from {} going {}", origin, direction);
+        }
+    }
+
+    #[test]
+    fn test_beam_graph_matches_ray_trace() {
+        let vec1: Vec<String> = vec![
+            r".|...\....",
+            r"|.-.\.....",
+            r".....|-...",
+            r"........|.",
+            r"..........",
+            r".........\",
+            r"..../.\\..",
+            r".-.-/..|..",
+            r".|....-|.\",
+            r"..//.|....",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_matches_ray_trace(&vec1, &ElementRegistry::standard());
+    }
+
+    #[test]
+    fn test_beam_graph_loops() {
+        // The splitters send beams round the mirror loop, and back into each other.
+        let vec1: Vec<String> = vec![r"/.-.\", r"|...|", r"\.-./", r"..|.."]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_matches_ray_trace(&vec1, &ElementRegistry::standard());
+    }
+
+    #[test]
+    fn test_beam_graph_no_elements() {
+        let vec1: Vec<String> = vec!["...", "..."].iter().map(|s| s.to_string()).collect();
+        let graph = BeamGraph::parse(&vec1);
+        let lit = graph.energized(GridCoordinate::new(0, 1), Direction::EAST);
+        assert_eq!(lit.count_ones(), 3);
+    }
+
+    struct Absorber;
+
+    impl OpticalElement for Absorber {
+        fn exit_directions(&self, _direction: Direction) -> Vec<Direction> {
+            return vec![];
+        }
+    }
+
+    // Carries on, and also splits off to both sides.
+    struct Prism;
+
+    impl OpticalElement for Prism {
+        fn exit_directions(&self, direction: Direction) -> Vec<Direction> {
+            return match direction {
+                Direction::NORTH | Direction::SOUTH => {
+                    vec![direction, Direction::EAST, Direction::WEST]
+                }
+                _ => vec![direction, Direction::NORTH, Direction::SOUTH],
+            };
+        }
+    }
+
+    // A / mirror to beams heading west or south, see through to beams heading east or north.
+    struct OneWayMirror;
+
+    impl OpticalElement for OneWayMirror {
+        fn exit_directions(&self, direction: Direction) -> Vec<Direction> {
+            return match direction {
+                Direction::WEST => vec![Direction::SOUTH],
+                Direction::SOUTH => vec![Direction::WEST],
+                _ => vec![direction],
+            };
+        }
+    }
+
+    fn custom_registry() -> ElementRegistry {
+        let mut registry = ElementRegistry::standard();
+        registry.register('#', Absorber);
+        registry.register('*', Prism);
+        registry.register('>', OneWayMirror);
+        return registry;
+    }
+
+    #[test]
+    fn test_register_replaces() {
+        let mut registry = ElementRegistry::standard();
+        registry.register('-', Absorber);
+        assert_eq!(registry.elements.len(), 5);
+        let vec1: Vec<String> = vec!["..-.."].iter().map(|s| s.to_string()).collect();
+        let contraption = Contraption::parse(&vec1, &registry);
+        assert_eq!(ray_trace(&contraption).len(), 3);
+        assert_eq!(ElementRegistry::default().elements.len(), 0);
+    }
+
+    #[test]
+    fn test_absorber() {
+        let vec1: Vec<String> = vec!["..#.."].iter().map(|s| s.to_string()).collect();
+        let contraption = Contraption::parse(&vec1, &custom_registry());
+        assert_eq!(ray_trace(&contraption).len(), 3);
+    }
+
+    #[test]
+    fn test_prism() {
+        let vec1: Vec<String> = vec!["...", "*..", "..."]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let contraption = Contraption::parse(&vec1, &custom_registry());
+        let lit = ray_trace_inner(&contraption, GridCoordinate::new(0, 1), Direction::EAST);
+        assert_eq!(lit.len(), 5);
+    }
+
+    #[test]
+    fn test_one_way_mirror() {
+        let vec1: Vec<String> = vec!["..>..", ".....", "..>.."]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let contraption = Contraption::parse(&vec1, &custom_registry());
+        // Heading east it goes straight through.
+        let east = ray_trace_inner(&contraption, GridCoordinate::new(0, 0), Direction::EAST);
+        assert_eq!(east.len(), 5);
+        // Heading west it turns south, then the lower mirror turns it west.
+        let west = ray_trace_inner(&contraption, GridCoordinate::new(4, 0), Direction::WEST);
+        assert_eq!(west.len(), 7);
+    }
+
+    #[test]
+    fn test_beam_graph_custom_elements() {
+        let vec1: Vec<String> = vec![r".*..\.", r"..#.>.", r"\.-*./", r".>..|.", r"..*#.."]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_matches_ray_trace(&vec1, &custom_registry());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unregistered_element() {
+        let vec1: Vec<String> = vec!["..#.."].iter().map(|s| s.to_string()).collect();
+        parse_grid(&vec1);
+    }
 }
@@ -1,8 +1,10 @@
 use day16::load_no_blanks;
 use day16::puzzle_a;
 use day16::puzzle_b;
+use day16::set_jobs_from_args;
 
 fn main() {
+    set_jobs_from_args();
     let filename = "input";
     let lines = load_no_blanks(filename);
 
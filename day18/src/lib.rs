@@ -1,59 +1,20 @@
 extern crate filelib;
 
 pub use filelib::load_no_blanks;
-use gridlib::Direction;
+use gridlib::parse_hex_step;
+use gridlib::parse_step;
 use gridlib::GridCoordinateInf64;
+use gridlib::TurtlePath;
 
-// Directions are Up = North, Down = South, Right = east, Left = west
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-struct PlanStep {
-    dir: Direction,
-    distance: u64,
+fn parse_plan(input: &Vec<String>) -> TurtlePath {
+    // Directions are Up = North, Down = South, Right = east, Left = west
+    let steps = input.iter().map(|line| parse_step(line).unwrap()).collect();
+    return TurtlePath::from_steps(GridCoordinateInf64::new(0, 0), &steps);
 }
 
-fn parse_plan(input: &Vec<String>) -> Vec<PlanStep> {
-    let mut result = vec![];
-
-    for line in input {
-        let (dir_s, rest) = line.split_once(" ").unwrap();
-        let (dist_s, _) = rest.split_once(" ").unwrap();
-
-        let direction = match dir_s {
-            "R" => Direction::EAST,
-            "D" => Direction::SOUTH,
-            "L" => Direction::WEST,
-            "U" => Direction::NORTH,
-            _ => panic!("Unsupported dir"),
-        };
-        let distance: u64 = dist_s.parse().unwrap();
-        let step = PlanStep {
-            dir: direction,
-            distance: distance,
-        };
-        result.push(step);
-    }
-
-    return result;
-}
-
-fn get_points(plan: &Vec<PlanStep>) -> Vec<GridCoordinateInf64> {
-    // Assume start at 0,0
-    // follow each plan step to get a list of grid coordinates.
-    // Return those
-    let start = GridCoordinateInf64::new(0, 0);
-    let mut result = vec![start];
-    let mut cur = start;
-    for step in plan {
-        let dist_i64: i64 = step.distance.try_into().unwrap();
-        cur = cur.move_dir_dist(step.dir, dist_i64);
-        result.push(cur);
-    }
-    return result;
-}
-
-fn compute_area(plan: &Vec<PlanStep>) -> u64 {
-    // First, convert the planSteps to a list of
-    let points = get_points(plan);
+fn compute_area(plan: &TurtlePath) -> u64 {
+    // The corners of the trench, starting and ending at 0,0
+    let points = plan.get_vertices();
     //println!("{:?}", points);
     let max_y = points.iter().map(|p| p.y).max().unwrap();
 
@@ -163,34 +124,15 @@ pub fn puzzle_a(string_list: &Vec<String>) -> u64 {
     return compute_area(&plan);
 }
 
-fn parse_plan_hex(input: &Vec<String>) -> Vec<PlanStep> {
-    let mut result = vec![];
-
-    for line in input {
-        let (_, rgb_str_with_endbracket) = line.split_once("(#").unwrap();
-        // 012345
-        // 70c710
-        //      ^ Direction
-        // ^---^  Distance
-        let dir_s = rgb_str_with_endbracket.chars().nth(5).unwrap();
-        let dist_s = &rgb_str_with_endbracket[0..=4];
-
-        let direction = match dir_s {
-            '0' => Direction::EAST,
-            '1' => Direction::SOUTH,
-            '2' => Direction::WEST,
-            '3' => Direction::NORTH,
-            _ => panic!("Unsupported dir"),
-        };
-        let distance: u64 = u64::from_str_radix(dist_s, 16).unwrap();
-        let step = PlanStep {
-            dir: direction,
-            distance: distance,
-        };
-        result.push(step);
-    }
-
-    return result;
+fn parse_plan_hex(input: &Vec<String>) -> TurtlePath {
+    // (#70c710)
+    //   ^---^  Distance
+    //        ^ Direction
+    let steps = input
+        .iter()
+        .map(|line| parse_hex_step(line).unwrap())
+        .collect();
+    return TurtlePath::from_steps(GridCoordinateInf64::new(0, 0), &steps);
 }
 
 /// Parse from the hex instead
@@ -2,9 +2,17 @@ extern crate filelib;
 
 pub use filelib::load;
 pub use filelib::split_lines_by_blanks;
+use mathlib::Interval;
+use mathlib::IntervalBox;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 
 type WorkflowValue = u64;
 
+// Every rating in a part is between these, inclusive.
+const RATING_MIN: WorkflowValue = 1;
+const RATING_MAX: WorkflowValue = 4000;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum Category {
     ExtremelyCoolLooking,
@@ -21,6 +29,17 @@ struct Part {
     s: WorkflowValue,
 }
 
+impl Category {
+    fn dimension(&self) -> usize {
+        return match self {
+            Category::ExtremelyCoolLooking => 0,
+            Category::Musical => 1,
+            Category::Aerodynamic => 2,
+            Category::Shiny => 3,
+        };
+    }
+}
+
 impl Part {
     fn get_value_by_category(&self, category: Category) -> WorkflowValue {
         return match category {
@@ -58,19 +77,19 @@ struct Operation {
 }
 
 impl Operation {
-    fn should_do_flow(&self, part: &Part) -> bool {
+    fn condition(&self) -> Option<Condition> {
         if let Some(cat) = self.category {
             if let Some(comp) = self.compare {
                 if let Some(value) = self.value {
-                    let v = part.get_value_by_category(cat);
-                    return match comp {
-                        Comparison::LessThan => v < value,
-                        Comparison::GreaterThan => v > value,
-                    };
+                    return Some(Condition {
+                        category: cat,
+                        compare: comp,
+                        value: value,
+                    });
                 }
             }
         }
-        return true;
+        return None;
     }
 }
 
@@ -80,24 +99,8 @@ struct Workflow {
     rules: Vec<Operation>,
 }
 
-impl Workflow {
-    fn check_part(&self, part: &Part) -> Flow {
-        for r in self.rules.iter() {
-            if r.should_do_flow(part) {
-                return r.flow.clone();
-            }
-        }
-        panic!("Workflow with no default flow");
-    }
-}
-
-fn parse_input(input: &Vec<Vec<String>>) -> (Vec<Part>, Vec<Workflow>) {
-    let workflows_list = &input[0];
-    let part_list = &input[1];
-
+fn parse_parts(part_list: &Vec<String>) -> Vec<Part> {
     let mut parts = vec![];
-    let mut workflows = vec![];
-
     for line in part_list {
         let (x_equal, rest) = line
             .split_once("{")
@@ -127,7 +130,11 @@ fn parse_input(input: &Vec<Vec<String>>) -> (Vec<Part>, Vec<Workflow>) {
         };
         parts.push(part);
     }
+    return parts;
+}
 
+fn parse_workflows(workflows_list: &Vec<String>) -> Vec<Workflow> {
+    let mut workflows = vec![];
     for line in workflows_list {
         let (name, rest) = line.split_once("{").unwrap();
         let (no_end, _) = rest.split_once("}").unwrap();
@@ -199,21 +206,414 @@ fn parse_input(input: &Vec<Vec<String>>) -> (Vec<Part>, Vec<Workflow>) {
         };
         workflows.push(workflow);
     }
+    return workflows;
+}
 
-    return (parts, workflows);
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Condition {
+    category: Category,
+    compare: Comparison,
+    value: WorkflowValue,
 }
 
-fn eval_part(part: &Part, initial_name: &str, workflows: &Vec<Workflow>) -> bool {
-    let first: Vec<&Workflow> = workflows
-        .iter()
-        .filter(|f| f.name == initial_name)
-        .collect();
-    let flow = first[0].check_part(part);
-    return match flow {
-        Flow::Accept => true,
-        Flow::Reject => false,
-        Flow::SendTo(new_name) => eval_part(part, &new_name, workflows),
-    };
+impl Condition {
+    fn matches(&self, part: &Part) -> bool {
+        let v = part.get_value_by_category(self.category);
+        return match self.compare {
+            Comparison::LessThan => v < self.value,
+            Comparison::GreaterThan => v > self.value,
+        };
+    }
+
+    /// Split a range into the parts matching the condition, and those that don't.
+    fn split(&self, range: &PartRange) -> (Option<PartRange>, Option<PartRange>) {
+        let dim = self.category.dimension();
+        return match self.compare {
+            Comparison::LessThan => range.split_at(dim, self.value),
+            Comparison::GreaterThan => {
+                let (below, above) = range.split_at(dim, self.value + 1);
+                (above, below)
+            }
+        };
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Target {
+    Accept,
+    Reject,
+    Workflow(usize),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Rule {
+    condition: Option<Condition>,
+    target: Target,
+}
+
+impl Rule {
+    fn matches(&self, part: &Part) -> bool {
+        return match self.condition {
+            Some(condition) => condition.matches(part),
+            None => true,
+        };
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct CompiledWorkflow {
+    name: String,
+    rules: Vec<Rule>,
+}
+
+/// Problems found while compiling workflows.
+///
+/// Rule indexes count from 0 within their workflow.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum WorkflowError {
+    /// Two workflows share a name.
+    DuplicateWorkflow(String),
+    /// The starting workflow does not exist.
+    MissingStart(String),
+    /// A rule sends parts to a workflow that does not exist.
+    MissingTarget { workflow: String, target: String },
+    /// The workflow has no unconditional rule, so parts can fall off the end.
+    NoDefaultRule(String),
+    /// Following rules from the first workflow listed leads back to it.
+    Cycle(Vec<String>),
+    /// No part can ever reach this rule.
+    UnreachableRule { workflow: String, rule: usize },
+    /// An earlier rule already catches every part this rule would.
+    ShadowedRule {
+        workflow: String,
+        rule: usize,
+        shadowed_by: usize,
+    },
+}
+
+impl WorkflowError {
+    /// Dead rules are suspicious, but don't change the result, so they are only warnings.
+    pub fn is_fatal(&self) -> bool {
+        return !matches!(
+            self,
+            WorkflowError::UnreachableRule { .. } | WorkflowError::ShadowedRule { .. }
+        );
+    }
+}
+
+impl Display for WorkflowError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            WorkflowError::DuplicateWorkflow(name) => {
+                write!(f, "workflow {} is defined more than once", name)
+            }
+            WorkflowError::MissingStart(name) => write!(f, "start workflow {} is missing", name),
+            WorkflowError::MissingTarget { workflow, target } => {
+                write!(
+                    f,
+                    "workflow {} sends to missing workflow {}",
+                    workflow, target
+                )
+            }
+            WorkflowError::NoDefaultRule(name) => {
+                write!(f, "workflow {} has no default rule", name)
+            }
+            WorkflowError::Cycle(names) => {
+                write!(f, "workflows loop: {} -> {}", names.join(" -> "), names[0])
+            }
+            WorkflowError::UnreachableRule { workflow, rule } => {
+                write!(f, "rule {} of workflow {} is unreachable", rule, workflow)
+            }
+            WorkflowError::ShadowedRule {
+                workflow,
+                rule,
+                shadowed_by,
+            } => write!(
+                f,
+                "rule {} of workflow {} is shadowed by rule {}",
+                rule, workflow, shadowed_by
+            ),
+        };
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum VisitState {
+    Unvisited,
+    OnPath,
+    Done,
+}
+
+/// Workflows resolved to indexes and checked so evaluation always terminates.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RuleEngine {
+    workflows: Vec<CompiledWorkflow>,
+    start: usize,
+    warnings: Vec<WorkflowError>,
+}
+
+impl RuleEngine {
+    /// Parse and validate workflow lines, starting evaluation at `start`.
+    ///
+    /// Fatal problems are returned as an error, dead rules are kept as warnings.
+    /// ```
+    /// use day19::{RuleEngine, WorkflowError};
+    /// let good: Vec<String> = vec!["in{x<10:A,x<5:R,foo}", "foo{R}"]
+    ///     .iter().map(|s| s.to_string()).collect();
+    /// let engine = RuleEngine::compile(&good, "in").unwrap();
+    /// assert_eq!(engine.count_accepted(), 9 * 4000 * 4000 * 4000);
+    /// assert_eq!(
+    ///     engine.warnings(),
+    ///     &vec![WorkflowError::ShadowedRule {
+    ///         workflow: "in".to_string(),
+    ///         rule: 1,
+    ///         shadowed_by: 0
+    ///     }]
+    /// );
+    ///
+    /// let missing: Vec<String> = vec!["in{x<10:A,bar}"].iter().map(|s| s.to_string()).collect();
+    /// assert_eq!(
+    ///     RuleEngine::compile(&missing, "in"),
+    ///     Err(WorkflowError::MissingTarget {
+    ///         workflow: "in".to_string(),
+    ///         target: "bar".to_string()
+    ///     })
+    /// );
+    /// ```
+    pub fn compile(workflow_lines: &Vec<String>, start: &str) -> Result<RuleEngine, WorkflowError> {
+        return RuleEngine::from_workflows(&parse_workflows(workflow_lines), start);
+    }
+
+    fn from_workflows(workflows: &Vec<Workflow>, start: &str) -> Result<RuleEngine, WorkflowError> {
+        let mut indexes: HashMap<&str, usize> = HashMap::new();
+        for (index, workflow) in workflows.iter().enumerate() {
+            if indexes.insert(&workflow.name, index).is_some() {
+                return Err(WorkflowError::DuplicateWorkflow(workflow.name.clone()));
+            }
+        }
+        let start_index = match indexes.get(start) {
+            Some(index) => *index,
+            None => return Err(WorkflowError::MissingStart(start.to_string())),
+        };
+
+        let mut warnings = vec![];
+        let mut compiled = vec![];
+        for workflow in workflows {
+            let mut rules = vec![];
+            for operation in workflow.rules.iter() {
+                let target = match &operation.flow {
+                    Flow::Accept => Target::Accept,
+                    Flow::Reject => Target::Reject,
+                    Flow::SendTo(name) => match indexes.get(name.as_str()) {
+                        Some(index) => Target::Workflow(*index),
+                        None => {
+                            return Err(WorkflowError::MissingTarget {
+                                workflow: workflow.name.clone(),
+                                target: name.clone(),
+                            })
+                        }
+                    },
+                };
+                rules.push(Rule {
+                    condition: operation.condition(),
+                    target: target,
+                });
+            }
+
+            let default_index = match rules.iter().position(|r| r.condition.is_none()) {
+                Some(index) => index,
+                None => return Err(WorkflowError::NoDefaultRule(workflow.name.clone())),
+            };
+            // Anything after the default rule is never looked at, so drop it.
+            let after_default = rules.split_off(default_index + 1);
+            warnings.extend(find_dead_rules(&workflow.name, &rules));
+            for offset in 0..after_default.len() {
+                warnings.push(WorkflowError::UnreachableRule {
+                    workflow: workflow.name.clone(),
+                    rule: default_index + 1 + offset,
+                });
+            }
+
+            compiled.push(CompiledWorkflow {
+                name: workflow.name.clone(),
+                rules: rules,
+            });
+        }
+
+        if let Some(cycle) = find_cycle(&compiled) {
+            return Err(WorkflowError::Cycle(cycle));
+        }
+
+        return Ok(RuleEngine {
+            workflows: compiled,
+            start: start_index,
+            warnings: warnings,
+        });
+    }
+
+    /// Non-fatal problems found during compile.
+    pub fn warnings(&self) -> &Vec<WorkflowError> {
+        return &self.warnings;
+    }
+
+    fn accepts(&self, part: &Part) -> bool {
+        let mut index = self.start;
+        loop {
+            // Compile guarantees every workflow ends in an unconditional rule.
+            let rule = self.workflows[index]
+                .rules
+                .iter()
+                .find(|r| r.matches(part))
+                .unwrap();
+            match rule.target {
+                Target::Accept => return true,
+                Target::Reject => return false,
+                Target::Workflow(next) => index = next,
+            }
+        }
+    }
+
+    fn accepted_ranges(&self, index: usize, in_range: PartRange) -> Vec<PartRange> {
+        // Split the range by each rule in turn.
+        // Reject: Eliminate, don't add
+        // Accept: Take out, and add on to the result.
+        // Workflow: Recurse down that workflow with the matching piece.
+        let mut accept_ranges: Vec<PartRange> = vec![];
+        let mut range = Some(in_range);
+
+        for rule in self.workflows[index].rules.iter() {
+            // Check if range is impossible now, if it is, no point in continuing
+            let current = match range {
+                Some(current) => current,
+                None => break,
+            };
+            let matching = match rule.condition {
+                Some(condition) => {
+                    let (matching, rest) = condition.split(&current);
+                    range = rest;
+                    matching
+                }
+                None => {
+                    range = None;
+                    Some(current)
+                }
+            };
+            if let Some(matching) = matching {
+                match rule.target {
+                    Target::Accept => accept_ranges.push(matching),
+                    Target::Reject => {}
+                    Target::Workflow(next) => {
+                        accept_ranges.extend(self.accepted_ranges(next, matching))
+                    }
+                }
+            }
+        }
+
+        return accept_ranges;
+    }
+
+    /// Count how many combinations of ratings would be accepted.
+    pub fn count_accepted(&self) -> WorkflowValue {
+        return self
+            .accepted_ranges(self.start, full_part_range())
+            .iter()
+            .map(|r| r.volume())
+            .sum();
+    }
+}
+
+fn find_dead_rules(name: &str, rules: &Vec<Rule>) -> Vec<WorkflowError> {
+    let mut result = vec![];
+    let mut remaining = Some(full_part_range());
+    for (index, rule) in rules.iter().enumerate() {
+        let condition = match rule.condition {
+            Some(condition) => condition,
+            None => {
+                if remaining.is_none() {
+                    result.push(WorkflowError::UnreachableRule {
+                        workflow: name.to_string(),
+                        rule: index,
+                    });
+                }
+                break;
+            }
+        };
+        let (matching, rest) = match remaining {
+            Some(current) => condition.split(&current),
+            None => (None, None),
+        };
+        if matching.is_none() {
+            // Blame a single earlier rule if it catches everything this one would.
+            let (own, _) = condition.split(&full_part_range());
+            let shadowed_by = own.and_then(|own| {
+                return rules[..index].iter().position(|earlier| {
+                    if let Some(earlier_condition) = earlier.condition {
+                        let (_, missed) = earlier_condition.split(&own);
+                        return missed.is_none();
+                    }
+                    return false;
+                });
+            });
+            match shadowed_by {
+                Some(earlier) => result.push(WorkflowError::ShadowedRule {
+                    workflow: name.to_string(),
+                    rule: index,
+                    shadowed_by: earlier,
+                }),
+                None => result.push(WorkflowError::UnreachableRule {
+                    workflow: name.to_string(),
+                    rule: index,
+                }),
+            }
+        }
+        remaining = rest;
+    }
+    return result;
+}
+
+fn find_cycle(workflows: &Vec<CompiledWorkflow>) -> Option<Vec<String>> {
+    let mut states = vec![VisitState::Unvisited; workflows.len()];
+    let mut path = vec![];
+    for index in 0..workflows.len() {
+        if let Some(cycle) = find_cycle_from(workflows, index, &mut states, &mut path) {
+            return Some(cycle);
+        }
+    }
+    return None;
+}
+
+fn find_cycle_from(
+    workflows: &Vec<CompiledWorkflow>,
+    index: usize,
+    states: &mut Vec<VisitState>,
+    path: &mut Vec<usize>,
+) -> Option<Vec<String>> {
+    match states[index] {
+        VisitState::Done => return None,
+        VisitState::OnPath => {
+            let first = path.iter().position(|p| *p == index).unwrap();
+            return Some(
+                path[first..]
+                    .iter()
+                    .map(|p| workflows[*p].name.clone())
+                    .collect(),
+            );
+        }
+        VisitState::Unvisited => {}
+    }
+
+    states[index] = VisitState::OnPath;
+    path.push(index);
+    for rule in workflows[index].rules.iter() {
+        if let Target::Workflow(next) = rule.target {
+            if let Some(cycle) = find_cycle_from(workflows, next, states, path) {
+                return Some(cycle);
+            }
+        }
+    }
+    path.pop();
+    states[index] = VisitState::Done;
+    return None;
 }
 
 /// Add ratings of all accepted parts.
@@ -240,256 +640,21 @@ fn eval_part(part: &Part, initial_name: &str, workflows: &Vec<Workflow>) -> bool
 /// ```
 pub fn puzzle_a(input: &Vec<Vec<String>>) -> WorkflowValue {
     let first_endpoint_name = "in";
-    let (parts, workflows) = parse_input(input);
+    let engine = RuleEngine::compile(&input[0], first_endpoint_name).unwrap();
+    let parts = parse_parts(&input[1]);
     return parts
         .iter()
-        .filter(|f| eval_part(f, first_endpoint_name, &workflows))
+        .filter(|f| engine.accepts(f))
         .map(|p| p.get_value_sum())
         .sum();
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-struct PartRange {
-    x_mins: WorkflowValue,
-    x_maxs: WorkflowValue,
-    m_mins: WorkflowValue,
-    m_maxs: WorkflowValue,
-    a_mins: WorkflowValue,
-    a_maxs: WorkflowValue,
-    s_mins: WorkflowValue,
-    s_maxs: WorkflowValue,
-}
-
-fn recurse_derive_num_acceptable_parts(
-    flows: &Vec<Workflow>,
-    first_endpoint_name: &str,
-    in_range: PartRange,
-) -> Vec<PartRange> {
-    // Split mins / maxs by Flow
-    // Reject: Eliminate, don't add
-    // Accept: Take out, and add on to the recurse value at the end.
-    // SendTo: Recurse down the flow for each distinct send to.
-    let first: Vec<&Workflow> = flows
-        .iter()
-        .filter(|f| f.name == first_endpoint_name)
-        .collect();
-    let workflow = first[0];
-
-    let mut accept_ranges: Vec<PartRange> = vec![];
-    let mut range = in_range.clone();
-
-    for rule in workflow.rules.clone() {
-        // Check if range is impossible now, if it is, no point in continuing
-        if range.x_mins > range.x_maxs
-            || range.m_mins > range.m_maxs
-            || range.a_mins > range.a_maxs
-            || range.s_mins > range.s_maxs
-        {
-            break;
-        }
-
-        if let Some(cat) = rule.category {
-            if let Some(comp) = rule.compare {
-                if let Some(value) = rule.value {
-                    match rule.flow {
-                        Flow::Accept => {
-                            let mut to_accept = range.clone();
-                            match comp {
-                                Comparison::LessThan => {
-                                    // Add an accept that looks like range.
-                                    // Figure out value to alter by category
-                                    // we want to decrease max to be value - 1.
-                                    // and we want to change range to min value + 1
-                                    match cat {
-                                        Category::ExtremelyCoolLooking => {
-                                            to_accept.x_maxs = value - 1;
-                                            range.x_mins = value;
-                                        }
-                                        Category::Musical => {
-                                            to_accept.m_maxs = value - 1;
-                                            range.m_mins = value;
-                                        }
-                                        Category::Aerodynamic => {
-                                            to_accept.a_maxs = value - 1;
-                                            range.a_mins = value;
-                                        }
-                                        Category::Shiny => {
-                                            to_accept.s_maxs = value - 1;
-                                            range.s_mins = value;
-                                        }
-                                    }
-                                }
-                                Comparison::GreaterThan => {
-                                    // Add an accept that looks like range.
-                                    // Figure out value to alter by category
-                                    // we want to increase min to be value + 1.
-                                    // and we want to change range to max value - 1
-                                    match cat {
-                                        Category::ExtremelyCoolLooking => {
-                                            range.x_maxs = value;
-                                            to_accept.x_mins = value + 1;
-                                        }
-                                        Category::Musical => {
-                                            range.m_maxs = value;
-                                            to_accept.m_mins = value + 1;
-                                        }
-                                        Category::Aerodynamic => {
-                                            range.a_maxs = value;
-                                            to_accept.a_mins = value + 1;
-                                        }
-                                        Category::Shiny => {
-                                            range.s_maxs = value;
-                                            to_accept.s_mins = value + 1;
-                                        }
-                                    }
-                                }
-                            }
-                            accept_ranges.push(to_accept);
-                        }
-                        Flow::Reject => {
-                            match comp {
-                                Comparison::LessThan => {
-                                    // Alter self, make sure we aren't less than the value
-                                    // Set min to Value
-                                    match cat {
-                                        Category::ExtremelyCoolLooking => {
-                                            range.x_mins = value;
-                                        }
-                                        Category::Musical => {
-                                            range.m_mins = value;
-                                        }
-                                        Category::Aerodynamic => {
-                                            range.a_mins = value;
-                                        }
-                                        Category::Shiny => {
-                                            range.s_mins = value;
-                                        }
-                                    }
-                                }
-                                Comparison::GreaterThan => {
-                                    // Alter self, make sure we aren't more than the value
-                                    // Set max to Value
-                                    match cat {
-                                        Category::ExtremelyCoolLooking => {
-                                            range.x_maxs = value;
-                                        }
-                                        Category::Musical => {
-                                            range.m_maxs = value;
-                                        }
-                                        Category::Aerodynamic => {
-                                            range.a_maxs = value;
-                                        }
-                                        Category::Shiny => {
-                                            range.s_maxs = value;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        Flow::SendTo(c) => {
-                            let mut copy = range.clone();
-                            match comp {
-                                Comparison::LessThan => {
-                                    // This copy should get max = value - 1
-                                    // While range should get min = value
-                                    match cat {
-                                        Category::ExtremelyCoolLooking => {
-                                            copy.x_maxs = value - 1;
-                                            range.x_mins = value;
-                                        }
-                                        Category::Musical => {
-                                            copy.m_maxs = value - 1;
-                                            range.m_mins = value;
-                                        }
-                                        Category::Aerodynamic => {
-                                            copy.a_maxs = value - 1;
-                                            range.a_mins = value;
-                                        }
-                                        Category::Shiny => {
-                                            copy.s_maxs = value - 1;
-                                            range.s_mins = value;
-                                        }
-                                    }
-                                }
-                                Comparison::GreaterThan => {
-                                    // This copy should get min = value + 1
-                                    // While range should get max = value
-                                    match cat {
-                                        Category::ExtremelyCoolLooking => {
-                                            range.x_maxs = value;
-                                            copy.x_mins = value + 1;
-                                        }
-                                        Category::Musical => {
-                                            range.m_maxs = value;
-                                            copy.m_mins = value + 1;
-                                        }
-                                        Category::Aerodynamic => {
-                                            range.a_maxs = value;
-                                            copy.a_mins = value + 1;
-                                        }
-                                        Category::Shiny => {
-                                            range.s_maxs = value;
-                                            copy.s_mins = value + 1;
-                                        }
-                                    }
-                                }
-                            }
-                            let recurse = recurse_derive_num_acceptable_parts(flows, &c, copy);
-                            accept_ranges.extend(recurse);
-                        }
-                    }
-                }
-            }
-        } else {
-            match rule.flow {
-                Flow::Accept => {
-                    accept_ranges.push(range);
-                }
-                Flow::Reject => {
-                    // Throw away this entire range
-                }
-                Flow::SendTo(c) => {
-                    let recurse = recurse_derive_num_acceptable_parts(flows, &c, range);
-                    accept_ranges.extend(recurse);
-                }
-            }
-            break;
-        }
-    }
-
-    return accept_ranges;
-}
+// One dimension per category, see Category::dimension.
+type PartRange = IntervalBox<WorkflowValue, 4>;
 
-fn derive_num_acceptable_parts(flows: &Vec<Workflow>, first_endpoint_name: &str) -> WorkflowValue {
-    let x_mins = 1;
-    let x_maxs = 4000;
-    let m_mins = 1;
-    let m_maxs = 4000;
-    let a_mins = 1;
-    let a_maxs = 4000;
-    let s_mins = 1;
-    let s_maxs = 4000;
-
-    let part_ranges = PartRange {
-        x_mins: x_mins,
-        x_maxs: x_maxs,
-        m_mins: m_mins,
-        m_maxs: m_maxs,
-        a_mins: a_mins,
-        a_maxs: a_maxs,
-        s_mins: s_mins,
-        s_maxs: s_maxs,
-    };
-    let ranges = recurse_derive_num_acceptable_parts(flows, first_endpoint_name, part_ranges);
-    return ranges
-        .iter()
-        .map(|s| {
-            (s.x_maxs - s.x_mins + 1)
-                * (s.m_maxs - s.m_mins + 1)
-                * (s.a_maxs - s.a_mins + 1)
-                * (s.s_maxs - s.s_mins + 1)
-        })
-        .sum();
+fn full_part_range() -> PartRange {
+    let rating = Interval::new(RATING_MIN, RATING_MAX);
+    return IntervalBox::new([rating, rating, rating, rating]);
 }
 
 /// Ignore parts, figure out how many combinations would be accepted.
@@ -516,6 +681,93 @@ fn derive_num_acceptable_parts(flows: &Vec<Workflow>, first_endpoint_name: &str)
 /// ```
 pub fn puzzle_b(input: &Vec<Vec<String>>) -> WorkflowValue {
     let first_endpoint_name = "in";
-    let (_, workflows) = parse_input(input);
-    return derive_num_acceptable_parts(&workflows, first_endpoint_name);
+    let engine = RuleEngine::compile(&input[0], first_endpoint_name).unwrap();
+    return engine.count_accepted();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(lines: Vec<&str>) -> Vec<String> {
+        return lines.iter().map(|s| s.to_string()).collect();
+    }
+
+    #[test]
+    fn test_compile_missing_start() {
+        let lines = to_lines(vec!["px{A}"]);
+        assert_eq!(
+            RuleEngine::compile(&lines, "in"),
+            Err(WorkflowError::MissingStart("in".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_compile_duplicate() {
+        let lines = to_lines(vec!["in{A}", "in{R}"]);
+        assert_eq!(
+            RuleEngine::compile(&lines, "in"),
+            Err(WorkflowError::DuplicateWorkflow("in".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_compile_no_default() {
+        let lines = to_lines(vec!["in{x<5:A}"]);
+        assert_eq!(
+            RuleEngine::compile(&lines, "in"),
+            Err(WorkflowError::NoDefaultRule("in".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_compile_cycle() {
+        let lines = to_lines(vec!["in{x<5:A,a}", "a{m>5:b,R}", "b{in}"]);
+        let error = RuleEngine::compile(&lines, "in").unwrap_err();
+        assert_eq!(error, WorkflowError::Cycle(to_lines(vec!["in", "a", "b"])));
+        assert!(error.is_fatal());
+        assert_eq!(format!("{}", error), "workflows loop: in -> a -> b -> in");
+    }
+
+    #[test]
+    fn test_compile_unreachable() {
+        // Rule 2 is unreachable because x is fully split by the first two rules,
+        // rule 4 comes after the default.
+        let lines = to_lines(vec!["in{x<100:A,x>99:R,m<5:A,R,A}"]);
+        let engine = RuleEngine::compile(&lines, "in").unwrap();
+        assert_eq!(
+            engine.warnings(),
+            &vec![
+                WorkflowError::UnreachableRule {
+                    workflow: "in".to_string(),
+                    rule: 2
+                },
+                WorkflowError::UnreachableRule {
+                    workflow: "in".to_string(),
+                    rule: 3
+                },
+                WorkflowError::UnreachableRule {
+                    workflow: "in".to_string(),
+                    rule: 4
+                },
+            ]
+        );
+        assert!(!engine.warnings()[0].is_fatal());
+        assert_eq!(engine.count_accepted(), 99 * 4000 * 4000 * 4000);
+    }
+
+    #[test]
+    fn test_accepts() {
+        let lines = to_lines(vec!["in{x>10:a,R}", "a{s<20:A,R}"]);
+        let engine = RuleEngine::compile(&lines, "in").unwrap();
+        let part = Part {
+            x: 11,
+            m: 1,
+            a: 1,
+            s: 19,
+        };
+        assert!(engine.accepts(&part));
+        assert!(!engine.accepts(&Part { s: 20, ..part }));
+        assert!(!engine.accepts(&Part { x: 10, ..part }));
+    }
 }
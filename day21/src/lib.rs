@@ -1,11 +1,11 @@
 extern crate filelib;
 
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 
 pub use filelib::load_no_blanks;
 
-use gridlib::Direction;
+use gridlib::BitGrid;
 use gridlib::Grid;
 use gridlib::GridCoordinate;
 use gridlib::GridTraversable;
@@ -38,105 +38,101 @@ fn parse_grid(string_list: &Vec<String>) -> (Grid<Terrain>, GridCoordinate) {
     return (Grid::new(width, height, values), start_coord);
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-struct QueueState {
-    distance: u32,
-    location: GridCoordinate,
-    previous_steps: Vec<GridCoordinate>,
+/// Every plot the elf could be standing on after some number of steps, one bit per cell.
+///
+/// Walking back and forth means anywhere reachable in n steps is reachable again in n + 2,
+/// so the frontier never needs to remember earlier steps. Each step moves every cell at
+/// once: shift each row both ways, OR in the rows above and below, then mask out the rocks.
+pub struct GardenStepper {
+    plots: BitGrid,
+    frontier: BitGrid,
+    steps: usize,
 }
 
-impl Ord for QueueState {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // In case of a tie we compare positions - this step is necessary
-        // to make implementations of `PartialEq` and `Ord` consistent.
-        other
-            .distance
-            .cmp(&self.distance)
-            .then_with(|| other.previous_steps.len().cmp(&self.previous_steps.len()))
-            .then_with(|| other.location.cmp(&self.location))
+impl GardenStepper {
+    fn new(grid: &Grid<Terrain>, origin: &GridCoordinate) -> GardenStepper {
+        let plots = BitGrid::from_grid(grid, |t| t == Terrain::GardenPlot);
+        let mut frontier = BitGrid::new(grid.get_width(), grid.get_height());
+        frontier.set_value(*origin, true);
+        return GardenStepper {
+            plots: plots,
+            frontier: frontier,
+            steps: 0,
+        };
     }
-}
 
-impl PartialOrd for QueueState {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        return Some(self.cmp(other));
+    /// Start from the S in the puzzle input.
+    pub fn parse(string_list: &Vec<String>) -> GardenStepper {
+        let (grid, origin) = parse_grid(string_list);
+        return GardenStepper::new(&grid, &origin);
     }
-}
 
-// Dijkstra to find distances.
-fn find_places_x_steps_from_you(
-    grid: &Grid<Terrain>,
-    origin: &GridCoordinate,
-    num_steps: u32,
-) -> usize {
-    let width = grid.get_width();
-    let start_path = vec![origin.clone()];
-
-    // Set all distances to max
-    // coordinate, direction, streak
-    let mut dist: Vec<u32> = vec![u32::MAX; grid.get_height() * grid.get_width()];
-
-    let mut queue = BinaryHeap::new();
-    queue.push(QueueState {
-        distance: 0,
-        location: origin.clone(),
-        previous_steps: start_path,
-    });
-
-    while let Some(state) = queue.pop() {
-        // Because this is a priority queue, we do all the closest paths first
-        // So if we ever hit too high, break.
-        if state.distance > num_steps {
-            break;
-        }
-
-        let index = state.location.y * width + state.location.x;
-        if state.distance >= dist[index] {
-            continue;
-        }
-        dist[index] = state.distance;
-
-        for possible_dir in [
-            Direction::NORTH,
-            Direction::EAST,
-            Direction::SOUTH,
-            Direction::WEST,
-        ] {
-            let next_pos = grid.get_coordinate_by_direction(state.location, possible_dir);
-            if next_pos == None {
-                // Off the edge, skip it.
-                continue;
+    /// Take one more step from every cell on the frontier.
+    pub fn step(&mut self) {
+        let height = self.frontier.get_height();
+        let mut next = BitGrid::new(self.frontier.get_width(), height);
+        for y in 0..height {
+            let current = self.frontier.get_row(y);
+            let mut row = current.shift_left(1);
+            row |= &current.shift_right(1);
+            if y > 0 {
+                row |= self.frontier.get_row(y - 1);
             }
-            let next_loc = next_pos.unwrap();
-            let next_distance = state.distance + 1;
-            let mut steps = state.previous_steps.clone();
-            steps.push(next_loc);
-
-            // Get the terrain type
-            let terrain_type = grid.get_value(next_loc).unwrap();
-            match terrain_type {
-                Terrain::GardenPlot => {
-                    let next_state = QueueState {
-                        distance: next_distance,
-                        location: next_loc,
-                        previous_steps: steps,
-                    };
-                    queue.push(next_state);
-                }
-                Terrain::Rock => {
-                    // Skip it
-                    continue;
-                }
+            if y + 1 < height {
+                row |= self.frontier.get_row(y + 1);
             }
+            row &= self.plots.get_row(y);
+            next.set_row(y, row);
         }
+        self.frontier = next;
+        self.steps += 1;
+    }
+
+    pub fn get_steps(&self) -> usize {
+        return self.steps;
     }
 
-    return dist
-        .into_iter()
-        .filter(|x| *x <= num_steps)
-        .filter(|x| x % 2 == num_steps % 2)
-        .collect::<Vec<_>>()
-        .len();
+    pub fn get_frontier(&self) -> &BitGrid {
+        return &self.frontier;
+    }
+
+    /// How many plots could be reached in exactly get_steps() steps.
+    pub fn count(&self) -> usize {
+        return self.frontier.count_ones();
+    }
+}
+
+// Reachable counts for 0 to max_steps steps, index i is exactly i steps.
+fn reachable_series(grid: &Grid<Terrain>, origin: &GridCoordinate, max_steps: usize) -> Vec<usize> {
+    let mut stepper = GardenStepper::new(grid, origin);
+    let mut series = vec![stepper.count()];
+    while stepper.get_steps() < max_steps {
+        stepper.step();
+        series.push(stepper.count());
+    }
+    return series;
+}
+
+/// Plots reachable in exactly 0, 1, 2 ... max_steps steps, on the garden as given.
+/// ```
+/// let vec1: Vec<String> = vec![
+///     "...........",
+///     ".....###.#.",
+///     ".###.##..#.",
+///     "..#.#...#..",
+///     "....#.#....",
+///     ".##..S####.",
+///     ".##..#...#.",
+///     ".......##..",
+///     ".##.#.####.",
+///     ".##..##.##.",
+///     "..........."
+/// ].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(day21::reachable_counts(&vec1, 6), vec![1, 2, 4, 6, 9, 13, 16]);
+/// ```
+pub fn reachable_counts(string_list: &Vec<String>, max_steps: usize) -> Vec<usize> {
+    let (grid, origin) = parse_grid(string_list);
+    return reachable_series(&grid, &origin, max_steps);
 }
 
 /// Where can you get with 64 plots
@@ -157,8 +153,7 @@ fn find_places_x_steps_from_you(
 /// assert_eq!(day21::puzzle_a(&vec1, 6), 16);
 /// ```
 pub fn puzzle_a(string_list: &Vec<String>, num_steps: u32) -> usize {
-    let (grid, origin) = parse_grid(string_list);
-    return find_places_x_steps_from_you(&grid, &origin, num_steps);
+    return reachable_counts(string_list, num_steps as usize)[num_steps as usize];
 }
 
 fn solve_quadratic(n: i64, v0: i64, v1: i64, v2: i64) -> i64 {
@@ -181,49 +176,461 @@ fn expand_grid(grid: &Grid<Terrain>, factor: usize) -> Grid<Terrain> {
     return Grid::new(new_width, new_height, values);
 }
 
-/// Hahaha infinite grid now, slightly more complicated.
-/// I failed to get this working
-/// And solved manually. Not sure what I'm doing wrong to find the variables
-/// for this quadratic.
-pub fn puzzle_b(string_list: &Vec<String>, num_steps: u32) -> i64 {
-    let (grid, origin) = parse_grid(string_list);
-    let y_u32: u32 = origin.y.try_into().unwrap();
-    let height_u32: u32 = grid.get_height().try_into().unwrap();
-    let expanded_grid = expand_grid(&grid, 7);
-
-    /*
-     factor of 1:
-     A -> 0 move
-     Factor of 2:
-     AA
-     AA
-     0 move
-     factor of 3:
-     AAA
-     AAA -> 1 down, 1 right
-     AAA
-     factor of 4:
-     AAAA
-     AAAA  -> 1 down, 1 right
-     AAAA
-     AAAA
-     factor of 5:
-     AAAAA
-     AAAAA
-     AAAAA -> 2 down, 2 right
-     AAAAA
-     AAAAA
-    */
+// Every tile reached from the start along the one tile repeated forever,
+// breadth first, for checking the faster methods on small step counts.
+fn brute_force_infinite(grid: &Grid<Terrain>, origin: &GridCoordinate, num_steps: u64) -> u64 {
+    let width = grid.get_width() as i64;
+    let height = grid.get_height() as i64;
+    let mut frontier = HashSet::new();
+    frontier.insert((origin.x as i64, origin.y as i64));
+    for _ in 0..num_steps {
+        let mut next = HashSet::new();
+        for (x, y) in frontier {
+            for (nx, ny) in [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)] {
+                let wrapped = GridCoordinate::new(
+                    nx.rem_euclid(width) as usize,
+                    ny.rem_euclid(height) as usize,
+                );
+                if grid.get_value(wrapped).unwrap() == Terrain::GardenPlot {
+                    next.insert((nx, ny));
+                }
+            }
+        }
+        frontier = next;
+    }
+    return frontier.len() as u64;
+}
+
+// The quadratic trick only holds when the walk out of every tile is unobstructed:
+// a square tile, the start in the middle, and its row, column and the borders all clear.
+fn has_clear_cross(grid: &Grid<Terrain>, origin: &GridCoordinate) -> bool {
+    let size = grid.get_width();
+    if grid.get_height() != size || origin.x != size / 2 || origin.y != size / 2 {
+        return false;
+    }
+    let clear = |x: usize, y: usize| -> bool {
+        return grid.get_value(GridCoordinate::new(x, y)).unwrap() == Terrain::GardenPlot;
+    };
+    for i in 0..size {
+        if !clear(i, origin.y) || !clear(origin.x, i) {
+            return false;
+        }
+        if !clear(i, 0) || !clear(i, size - 1) || !clear(0, i) || !clear(size - 1, i) {
+            return false;
+        }
+    }
+    return true;
+}
+
+// Once the start is clear of its own tile the counts for steps r, r + size, r + 2 * size...
+// grow quadratically, so three samples fit the rest.
+fn quadratic_infinite(grid: &Grid<Terrain>, origin: &GridCoordinate, num_steps: u64) -> u64 {
+    let size = grid.get_width() as u64;
+    let remainder = num_steps % size;
+    if num_steps < remainder + size * 2 {
+        return brute_force_infinite(grid, origin, num_steps);
+    }
+    let expanded_grid = expand_grid(grid, 7);
     let new_start = GridCoordinate::new(
-        origin.x + grid.get_height() * 3,
+        origin.x + grid.get_width() * 3,
         origin.y + grid.get_height() * 3,
     );
+    let series = reachable_series(&expanded_grid, &new_start, (remainder + size * 2) as usize);
+    let samples: Vec<i64> = (0..3)
+        .map(|i| series[(remainder + size * i) as usize] as i64)
+        .collect();
+    let num_traverse = (num_steps / size) as i64;
+    return solve_quadratic(num_traverse, samples[0], samples[1], samples[2]) as u64;
+}
+
+// Breadth first distances from the start over a window of (2 * radius + 1) tiles square,
+// with the start in the middle tile. u32::MAX is unreachable.
+fn window_distances(grid: &Grid<Terrain>, origin: &GridCoordinate, radius: usize) -> Grid<u32> {
+    let window = expand_grid(grid, radius * 2 + 1);
+    let start = GridCoordinate::new(
+        origin.x + grid.get_width() * radius,
+        origin.y + grid.get_height() * radius,
+    );
+    let mut dist = Grid::new(
+        window.get_width(),
+        window.get_height(),
+        vec![u32::MAX; window.get_width() * window.get_height()],
+    );
+    dist.set_value(start, 0);
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    while let Some(location) = queue.pop_front() {
+        let next_distance = dist.get_value(location).unwrap() + 1;
+        for next_loc in window.get_adjacent_coordinates(location) {
+            if window.get_value(next_loc).unwrap() == Terrain::Rock {
+                continue;
+            }
+            if dist.get_value(next_loc).unwrap() == u32::MAX {
+                dist.set_value(next_loc, next_distance);
+                queue.push_back(next_loc);
+            }
+        }
+    }
+    return dist;
+}
+
+// How many of d, d + step, d + 2 * step... are at most n with the same parity as n.
+// With weighted each d + m * step counts m + 1 times, the number of corner tiles
+// m tiles further out.
+fn count_repeats(d: u64, n: u64, step: u64, weighted: bool) -> u64 {
+    if d > n {
+        return 0;
+    }
+    let max_m = (n - d) / step;
+    let parity_gap = (n - d) % 2;
+    // An odd step flips parity every tile so only every other one counts, an even step
+    // keeps it so either all of them count or none do.
+    let (first, stride) = if step % 2 == 1 {
+        (parity_gap, 2)
+    } else if parity_gap == 1 {
+        return 0;
+    } else {
+        (0, 1)
+    };
+    if first > max_m {
+        return 0;
+    }
+    let terms = (max_m - first) / stride + 1;
+    if !weighted {
+        return terms;
+    }
+    return terms * (first + 1) + stride * terms * (terms - 1) / 2;
+}
+
+// Cells in the largest window of distances worth building, about 20MB with the garden.
+const WINDOW_CELL_BUDGET: usize = 1 << 22;
+
+// A window of distances whose outermost ring of tiles repeats the ring inside it.
+struct SettledWindow {
+    dist: Grid<u32>,
+    ring: usize,
+    // Extra steps to reach a tile one further out across, and one further out down.
+    step_x: u64,
+    step_y: u64,
+}
+
+// The smallest window that settles, growing the ring until the window would go over
+// WINDOW_CELL_BUDGET.
+fn settle_window(grid: &Grid<Terrain>, origin: &GridCoordinate) -> Option<SettledWindow> {
+    let width = grid.get_width();
+    let height = grid.get_height();
+    let mut ring = 2;
+    loop {
+        // One spare tile past the ring so its distances aren't cut short by the window edge.
+        let tiles = 2 * (ring + 1) + 1;
+        if tiles * tiles * width * height > WINDOW_CELL_BUDGET {
+            return None;
+        }
+        let dist = window_distances(grid, origin, ring + 1);
+        if let Some((step_x, step_y)) = ring_steps(&dist, width, height, ring) {
+            return Some(SettledWindow {
+                dist: dist,
+                ring: ring,
+                step_x: step_x,
+                step_y: step_y,
+            });
+        }
+        ring *= 2;
+    }
+}
+
+// The most tiles out from the start a window can reach inside WINDOW_CELL_BUDGET.
+fn largest_window_radius(grid: &Grid<Terrain>) -> usize {
+    let tile_cells = grid.get_width() * grid.get_height();
+    let mut radius = 0;
+    while (2 * (radius + 1) + 1) * (2 * (radius + 1) + 1) * tile_cells <= WINDOW_CELL_BUDGET {
+        radius += 1;
+    }
+    return radius;
+}
+
+// Plots reachable in exactly 0, 1, 2... steps for as long as the window can tell, which
+// is up to the nearest reachable plot on its border. Past that a walk could have left it.
+fn window_series(dist: &Grid<u32>) -> Vec<u64> {
+    let width = dist.get_width();
+    let height = dist.get_height();
+    let distance = |x: usize, y: usize| dist.get_value(GridCoordinate::new(x, y)).unwrap();
+    let mut limit = u32::MAX;
+    for i in 0..width {
+        limit = limit.min(distance(i, 0)).min(distance(i, height - 1));
+    }
+    for i in 0..height {
+        limit = limit.min(distance(0, i)).min(distance(width - 1, i));
+    }
+    let values = dist.data_copy();
+    if limit == u32::MAX {
+        limit = values.iter().filter(|d| **d != u32::MAX).max().unwrap() + 1;
+    }
+    let mut exactly = vec![0; limit as usize];
+    for d in values {
+        if d < limit {
+            exactly[d as usize] += 1;
+        }
+    }
+    let mut series: Vec<u64> = vec![];
+    for (steps, count) in exactly.into_iter().enumerate() {
+        let two_back = if steps >= 2 { series[steps - 2] } else { 0 };
+        series.push(two_back + count);
+    }
+    return series;
+}
+
+// Like the quadratic trick, counts for steps r, r + period, r + 2 * period... grow
+// quadratically far enough out, but the period needn't be the tile size. Take the
+// shortest period that holds over the back two thirds of series and fit num_steps from
+// its last three samples. None if no period holds.
+fn fit_series(series: &[u64], num_steps: u64) -> Option<u64> {
+    let len = series.len();
+    if num_steps < len as u64 {
+        return Some(series[num_steps as usize]);
+    }
+    let count = |steps: usize| series[steps] as i64;
+    let from = len / 3;
+    for period in 1..=(len - from) / 5 {
+        let holds = (from..len - 3 * period).all(|n| {
+            count(n + 3 * period) - 3 * count(n + 2 * period) + 3 * count(n + period) - count(n)
+                == 0
+        });
+        if !holds {
+            continue;
+        }
+        let last = len - 1 - 2 * period;
+        let remainder = (num_steps % period as u64) as usize;
+        let base = last - (last % period + period - remainder) % period;
+        let num_traverse = ((num_steps - base as u64) / period as u64) as i64;
+        return Some(solve_quadratic(
+            num_traverse,
+            count(base),
+            count(base + period),
+            count(base + 2 * period),
+        ) as u64);
+    }
+    return None;
+}
+
+// Tile classes: far enough out, a tile on an axis is the tile before it with every
+// distance the same number of steps further, and a diagonal tile likewise from either
+// neighbour. Count a window directly, then carry its outermost ring of tiles on forever.
+// Gardens that never settle within the budget fall back to the quadratic when it holds,
+// then to a quadratic over some other period, and stepping every plot when neither fits.
+fn tile_class_infinite(grid: &Grid<Terrain>, origin: &GridCoordinate, num_steps: u64) -> u64 {
+    let width = grid.get_width();
+    let height = grid.get_height();
+    let SettledWindow {
+        dist,
+        ring,
+        step_x,
+        step_y,
+    } = match settle_window(grid, origin) {
+        Some(settled) => settled,
+        None if has_clear_cross(grid, origin) => {
+            return quadratic_infinite(grid, origin, num_steps);
+        }
+        None => {
+            let dist = window_distances(grid, origin, largest_window_radius(grid));
+            return match fit_series(&window_series(&dist), num_steps) {
+                Some(count) => count,
+                None => brute_force_infinite(grid, origin, num_steps),
+            };
+        }
+    };
+
+    let offset = ring as i64 + 1;
+    let tile_distance = |tx: i64, ty: i64, x: usize, y: usize| -> u32 {
+        let wx = (tx + offset) as usize * width + x;
+        let wy = (ty + offset) as usize * height + y;
+        return dist.get_value(GridCoordinate::new(wx, wy)).unwrap();
+    };
+
+    let k = ring as i64;
+    let mut total = 0;
+    for ty in -k..=k {
+        for tx in -k..=k {
+            let edge_x = tx.abs() == k;
+            let edge_y = ty.abs() == k;
+            for y in 0..height {
+                for x in 0..width {
+                    let d = tile_distance(tx, ty, x, y);
+                    if d == u32::MAX {
+                        continue;
+                    }
+                    let d = d as u64;
+                    total += match (edge_x, edge_y) {
+                        (false, false) => (d <= num_steps && d % 2 == num_steps % 2) as u64,
+                        (true, false) => count_repeats(d, num_steps, step_x, false),
+                        (false, true) => count_repeats(d, num_steps, step_y, false),
+                        (true, true) if step_x == step_y => {
+                            count_repeats(d, num_steps, step_x, true)
+                        }
+                        (true, true) => (0..)
+                            .map(|a| d + a * step_x)
+                            .take_while(|start| *start <= num_steps)
+                            .map(|start| count_repeats(start, num_steps, step_y, false))
+                            .sum(),
+                    };
+                }
+            }
+        }
+    }
+    return total;
+}
+
+// Does every tile on the ring cost the same number of extra steps over the tile inside it,
+// one number across and one down, for every plot? Those two numbers if so. A plot
+// unreachable in one must be unreachable in the other.
+fn ring_steps(dist: &Grid<u32>, width: usize, height: usize, ring: usize) -> Option<(u64, u64)> {
+    let offset = ring as i64 + 1;
+    let k = ring as i64;
+    // Nothing reachable on the ring leaves a step unknown, any will do then.
+    let mut step_x = None;
+    let mut step_y = None;
+    let matches = |tx: i64, ty: i64, ix: i64, iy: i64, step: &mut Option<u32>| -> bool {
+        for y in 0..height {
+            for x in 0..width {
+                let outer = dist
+                    .get_value(GridCoordinate::new(
+                        (tx + offset) as usize * width + x,
+                        (ty + offset) as usize * height + y,
+                    ))
+                    .unwrap();
+                let inner = dist
+                    .get_value(GridCoordinate::new(
+                        (ix + offset) as usize * width + x,
+                        (iy + offset) as usize * height + y,
+                    ))
+                    .unwrap();
+                if outer == u32::MAX || inner == u32::MAX {
+                    if outer != inner {
+                        return false;
+                    }
+                    continue;
+                }
+                if outer <= inner {
+                    return false;
+                }
+                match step {
+                    Some(s) if *s != outer - inner => return false,
+                    Some(_) => {}
+                    None => *step = Some(outer - inner),
+                }
+            }
+        }
+        return true;
+    };
+    for sign in [-1, 1] {
+        for i in -k + 1..k {
+            if !matches(sign * k, i, sign * (k - 1), i, &mut step_x)
+                || !matches(i, sign * k, i, sign * (k - 1), &mut step_y)
+            {
+                return None;
+            }
+        }
+        for other in [-1, 1] {
+            if !matches(sign * k, other * k, sign * (k - 1), other * k, &mut step_x)
+                || !matches(sign * k, other * k, sign * k, other * (k - 1), &mut step_y)
+            {
+                return None;
+            }
+        }
+    }
+    return Some((
+        step_x.unwrap_or(width as u32) as u64,
+        step_y.unwrap_or(height as u32) as u64,
+    ));
+}
+
+/// Which way reachable plots on the endless garden are counted.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InfiniteMethod {
+    /// Fit a quadratic through three samples, only valid with a clear start row and column.
+    Quadratic,
+    /// Count a window of tiles and extend its outer ring, for gardens with or without clear
+    /// rows and columns. Ones that never settle into repeating tiles fit a quadratic over
+    /// whatever period their counts repeat with, or are stepped plot by plot when none
+    /// shows, which is slow for many steps.
+    TileClasses,
+}
+
+/// Pick the quadratic method when the garden is shaped for it, tile classes otherwise.
+/// ```
+/// let vec1: Vec<String> = vec![
+///     "...",
+///     ".S.",
+///     "...",
+/// ].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(day21::choose_infinite_method(&vec1), day21::InfiniteMethod::Quadratic);
+/// let vec2: Vec<String> = vec![
+///     "...",
+///     ".S#",
+///     "...",
+/// ].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(day21::choose_infinite_method(&vec2), day21::InfiniteMethod::TileClasses);
+/// ```
+pub fn choose_infinite_method(string_list: &Vec<String>) -> InfiniteMethod {
+    let (grid, origin) = parse_grid(string_list);
+    if has_clear_cross(&grid, &origin) {
+        return InfiniteMethod::Quadratic;
+    }
+    return InfiniteMethod::TileClasses;
+}
 
-    let v0 = find_places_x_steps_from_you(&expanded_grid, &new_start, y_u32);
-    let v1 = find_places_x_steps_from_you(&expanded_grid, &new_start, y_u32 + height_u32);
-    let v2 = find_places_x_steps_from_you(&expanded_grid, &new_start, y_u32 + height_u32 * 2);
-    let num_traverse = (num_steps - y_u32) / height_u32;
-    return solve_quadratic(num_traverse as i64, v0 as i64, v1 as i64, v2 as i64);
+/// Plots reachable in exactly num_steps steps on the endlessly repeated garden, using method.
+/// ```
+/// let vec1: Vec<String> = vec![
+///     "...........",
+///     ".....###.#.",
+///     ".###.##..#.",
+///     "..#.#...#..",
+///     "....#.#....",
+///     ".##..S####.",
+///     ".##..#...#.",
+///     ".......##..",
+///     ".##.#.####.",
+///     ".##..##.##.",
+///     "..........."
+/// ].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(day21::reachable_infinite(&vec1, 1000, day21::InfiniteMethod::TileClasses), 668697);
+/// ```
+pub fn reachable_infinite(
+    string_list: &Vec<String>,
+    num_steps: u64,
+    method: InfiniteMethod,
+) -> u64 {
+    let (grid, origin) = parse_grid(string_list);
+    return match method {
+        InfiniteMethod::Quadratic => quadratic_infinite(&grid, &origin, num_steps),
+        InfiniteMethod::TileClasses => tile_class_infinite(&grid, &origin, num_steps),
+    };
+}
+
+/// Hahaha infinite grid now, slightly more complicated.
+/// Real inputs have a clear row and column through the start so a quadratic fits,
+/// anything else is counted by tile classes.
+/// ```
+/// let vec1: Vec<String> = vec![
+///     "...........",
+///     ".....###.#.",
+///     ".###.##..#.",
+///     "..#.#...#..",
+///     "....#.#....",
+///     ".##..S####.",
+///     ".##..#...#.",
+///     ".......##..",
+///     ".##.#.####.",
+///     ".##..##.##.",
+///     "..........."
+/// ].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(day21::puzzle_b(&vec1, 100), 6536);
+/// assert_eq!(day21::puzzle_b(&vec1, 5000), 16733044);
+/// ```
+pub fn puzzle_b(string_list: &Vec<String>, num_steps: u32) -> i64 {
+    let method = choose_infinite_method(string_list);
+    return reachable_infinite(string_list, num_steps as u64, method) as i64;
 }
 
 #[cfg(test)]
@@ -267,4 +674,205 @@ mod tests {
             ]
         )
     }
+
+    fn example() -> Vec<String> {
+        return vec![
+            "...........",
+            ".....###.#.",
+            ".###.##..#.",
+            "..#.#...#..",
+            "....#.#....",
+            ".##..S####.",
+            ".##..#...#.",
+            ".......##..",
+            ".##.#.####.",
+            ".##..##.##.",
+            "...........",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    }
+
+    // Clear start row, column and borders, as real inputs have.
+    fn clear_cross() -> Vec<String> {
+        return vec![
+            "...........",
+            ".##.....#..",
+            "..#....#.#.",
+            ".#.#.....#.",
+            "...#...##..",
+            ".....S.....",
+            ".#.#....#..",
+            "..##...#.#.",
+            ".#.......#.",
+            "...#...#...",
+            "...........",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    }
+
+    #[test]
+    fn test_tile_classes_match_brute_force() {
+        let (grid, origin) = parse_grid(&example());
+        assert!(!has_clear_cross(&grid, &origin));
+        for steps in [0, 1, 6, 10, 17, 33, 50, 64, 81] {
+            assert_eq!(
+                tile_class_infinite(&grid, &origin, steps),
+                brute_force_infinite(&grid, &origin, steps),
+                "{} steps",
+                steps
+            );
+        }
+    }
+
+    #[test]
+    fn test_quadratic_matches_brute_force() {
+        let (grid, origin) = parse_grid(&clear_cross());
+        assert!(has_clear_cross(&grid, &origin));
+        for steps in [5, 16, 27, 38, 49, 60, 71] {
+            let expected = brute_force_infinite(&grid, &origin, steps);
+            assert_eq!(quadratic_infinite(&grid, &origin, steps), expected);
+            assert_eq!(tile_class_infinite(&grid, &origin, steps), expected);
+        }
+        let far = 26501365;
+        assert_eq!(
+            quadratic_infinite(&grid, &origin, far),
+            tile_class_infinite(&grid, &origin, far)
+        );
+    }
+
+    #[test]
+    fn test_example_large_steps() {
+        let (grid, origin) = parse_grid(&example());
+        assert_eq!(tile_class_infinite(&grid, &origin, 50), 1594);
+        assert_eq!(tile_class_infinite(&grid, &origin, 500), 167004);
+        assert_eq!(tile_class_infinite(&grid, &origin, 5000), 16733044);
+    }
+
+    #[test]
+    fn test_rectangular_tile() {
+        let lines: Vec<String> = vec!["..#..", "..S..", ".#...", "....."]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let (grid, origin) = parse_grid(&lines);
+        for steps in [3, 8, 15, 24, 31] {
+            assert_eq!(
+                tile_class_infinite(&grid, &origin, steps),
+                brute_force_infinite(&grid, &origin, steps)
+            );
+        }
+    }
+
+    #[test]
+    fn test_slow_to_settle() {
+        let lines: Vec<String> = vec!["#..#", "..#.", "...#", ".#S.", "####", "...."]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let (grid, origin) = parse_grid(&lines);
+        // Rings of 2 and 4 tiles don't repeat yet.
+        assert_eq!(settle_window(&grid, &origin).unwrap().ring, 8);
+        for steps in [7, 20, 41, 64, 99] {
+            assert_eq!(
+                tile_class_infinite(&grid, &origin, steps),
+                brute_force_infinite(&grid, &origin, steps)
+            );
+        }
+    }
+
+    #[test]
+    fn test_never_settles() {
+        // No clear row or column, so crossing a tile costs more than its size.
+        let lines: Vec<String> = vec![".#.", "..#", "#S.", "#..", ".#."]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let (grid, origin) = parse_grid(&lines);
+        assert!(settle_window(&grid, &origin).is_none());
+        for steps in [5, 30, 61] {
+            assert_eq!(
+                tile_class_infinite(&grid, &origin, steps),
+                brute_force_infinite(&grid, &origin, steps)
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_clear_line() {
+        // One rock in every row and column, on a slant. Crossing a tile costs 11 or 13
+        // steps depending on the heading, so the ring never repeats and the counts are
+        // fitted over a period instead.
+        let lines: Vec<String> = (0..11)
+            .map(|y| {
+                (0..11)
+                    .map(|x| match (x, y) {
+                        (5, 5) => 'S',
+                        _ if x == (2 * y + 1) % 11 => '#',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+        let (grid, origin) = parse_grid(&lines);
+        assert!(settle_window(&grid, &origin).is_none());
+        assert_eq!(
+            tile_class_infinite(&grid, &origin, 120),
+            brute_force_infinite(&grid, &origin, 120)
+        );
+        // Past the largest window, checked against a bigger one.
+        assert_eq!(tile_class_infinite(&grid, &origin, 2000), 3432003);
+    }
+
+    #[test]
+    fn test_series_matches_distances() {
+        let (grid, origin) = parse_grid(&example());
+        // A window of one tile is just the garden.
+        let dist = window_distances(&grid, &origin, 0);
+        let series = reachable_series(&grid, &origin, 40);
+        for (steps, count) in series.into_iter().enumerate() {
+            let expected = dist
+                .data_copy()
+                .into_iter()
+                .filter(|d| *d as usize <= steps && *d as usize % 2 == steps % 2)
+                .count();
+            assert_eq!(count, expected, "{} steps", steps);
+        }
+    }
+
+    #[test]
+    fn test_stepper_wide_rows() {
+        // Wider than one word so shifts carry between words.
+        let lines: Vec<String> = vec![".".repeat(70) + "S" + &".".repeat(59)];
+        let mut stepper = GardenStepper::parse(&lines);
+        for _ in 0..65 {
+            stepper.step();
+        }
+        assert_eq!(stepper.get_steps(), 65);
+        // Odd squares from 6 to 70 away, and to 59 on the right.
+        assert_eq!(stepper.count(), 33 + 30);
+        assert!(stepper
+            .get_frontier()
+            .get_value(GridCoordinate::new(5, 0))
+            .unwrap());
+        assert!(!stepper
+            .get_frontier()
+            .get_value(GridCoordinate::new(6, 0))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_count_repeats() {
+        // 3, 7, 11 for odd n = 11 with an even size.
+        assert_eq!(count_repeats(3, 11, 4, false), 3);
+        assert_eq!(count_repeats(4, 11, 4, false), 0);
+        // 3, 8, 13, 18: only 3 and 13 share parity with 13... up to 13.
+        assert_eq!(count_repeats(3, 13, 5, false), 2);
+        // 3 once and 13 three times.
+        assert_eq!(count_repeats(3, 13, 5, true), 4);
+        assert_eq!(count_repeats(20, 13, 5, true), 0);
+    }
 }
@@ -0,0 +1,48 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// A snapshot the same every run, of straight bricks up to four cubes long on a 10x10
+// footprint like real inputs. Each starts a layer or two above the last so none overlap.
+fn generated_snapshot(count: usize) -> Vec<String> {
+    let mut state: u64 = 2023;
+    let mut next = |limit: u64| -> u32 {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        return ((state >> 33) % limit) as u32;
+    };
+    let mut lines = vec![];
+    let mut z = 1;
+    for _ in 0..count {
+        let length = next(4);
+        let (x, y) = (next(10 - length as u64), next(10 - length as u64));
+        let back = match next(3) {
+            0 => (x + length, y, z),
+            1 => (x, y + length, z),
+            _ => (x, y, z + length),
+        };
+        lines.push(format!(
+            "{},{},{}~{},{},{}",
+            x, y, z, back.0, back.1, back.2
+        ));
+        z = back.2 + 1 + next(2);
+    }
+    return lines;
+}
+
+fn bench_settle(c: &mut Criterion) {
+    let mut group = c.benchmark_group("settle");
+    group.sample_size(10);
+    for count in [50, 200] {
+        let lines = generated_snapshot(count);
+        group.bench_with_input(BenchmarkId::new("naive", count), &lines, |b, lines| {
+            b.iter(|| day22::supports_naive(lines))
+        });
+        group.bench_with_input(BenchmarkId::new("height_map", count), &lines, |b, lines| {
+            b.iter(|| day22::supports(lines))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_settle);
+criterion_main!(benches);
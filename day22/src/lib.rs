@@ -7,10 +7,11 @@ use std::hash::{Hash, Hasher};
 
 pub use filelib::load_no_blanks;
 
-// use petgraph::dot::Dot;
+use petgraph::algo::dominators::{simple_fast, Dominators};
 use petgraph::graph::Graph;
 use petgraph::graph::NodeIndex;
-use petgraph::Undirected;
+use petgraph::Directed;
+use petgraph::Direction;
 
 // Bricks must remain above GROUND_Z, aka, they must be at least 1.
 const GROUND_Z: u32 = 0;
@@ -133,6 +134,10 @@ impl Brick {
         };
     }
 
+    fn bottom_z(&self) -> Coord {
+        return self.front.z.min(self.back.z);
+    }
+
     fn fall(&self) -> Brick {
         let mut front = self.front.clone();
         let mut back = self.back.clone();
@@ -248,98 +253,305 @@ fn simulate_fall(bricks: &Vec<Brick>) -> Vec<Brick> {
     return end;
 }
 
-type BrickGraph = Graph<Brick, u32, Undirected>;
-
-fn build_brick_graph(bricks: &Vec<Brick>) -> BrickGraph {
-    let mut graph = Graph::new_undirected();
-    let mut brick_map: HashMap<Brick, NodeIndex> = HashMap::new();
-    let mut seen_edges: HashSet<(Brick, Brick)> = HashSet::new();
+// Highest settled cube over each x,y column, and which brick it belongs to.
+struct HeightMap {
+    width: usize,
+    tops: Vec<(Coord, Option<usize>)>,
+}
 
-    for brick in bricks.clone() {
-        let index = graph.add_node(brick.clone());
-        brick_map.insert(brick.clone(), index);
+impl HeightMap {
+    fn new(width: usize, depth: usize) -> HeightMap {
+        return HeightMap {
+            width: width,
+            tops: vec![(GROUND_Z, None); width * depth],
+        };
     }
-    for brick in bricks.clone() {
-        if brick.front.z == GROUND_Z + 1 || brick.back.z == GROUND_Z + 1 {
-            // Treat nodes on the ground as root nodes.
-            continue;
-        }
-        let index = brick_map.get(&brick).unwrap();
 
-        for other_brick in bricks.clone() {
-            if seen_edges.contains(&(other_brick.clone(), brick.clone())) {
-                continue;
+    fn footprint(&self, brick: &Brick) -> Vec<usize> {
+        let mut cells = vec![];
+        for y in brick.front.y.min(brick.back.y)..=brick.front.y.max(brick.back.y) {
+            for x in brick.front.x.min(brick.back.x)..=brick.front.x.max(brick.back.x) {
+                cells.push(y as usize * self.width + x as usize);
             }
-            if brick == other_brick {
-                continue;
+        }
+        return cells;
+    }
+
+    // Drop the brick onto whatever is highest under it, and return it moved along with the
+    // ids of the bricks it lands on.
+    fn drop(&mut self, brick: &Brick) -> (Brick, Vec<usize>) {
+        let cells = self.footprint(brick);
+        let rest = cells.iter().map(|c| self.tops[*c].0).max().unwrap();
+        let mut below = vec![];
+        for cell in cells.iter() {
+            if let (height, Some(id)) = self.tops[*cell] {
+                if height == rest && !below.contains(&id) {
+                    below.push(id);
+                }
             }
-            if other_brick.does_support(&brick) {
-                let other_index = brick_map.get(&other_brick.clone()).unwrap();
-                graph.add_edge(*index, *other_index, 1);
-                seen_edges.insert((brick.clone(), other_brick.clone()));
+        }
+
+        let fall = brick.bottom_z() - (rest + 1);
+        let mut front = brick.front;
+        let mut back = brick.back;
+        front.z -= fall;
+        back.z -= fall;
+        let top = front.z.max(back.z);
+        for cell in cells {
+            self.tops[cell] = (top, Some(brick.debug_id));
+        }
+        return (Brick::new(front, back, brick.debug_id), below);
+    }
+}
+
+// Settle the bricks lowest first, each one only looking at the height map under its
+// footprint. The (below, above) support pairs come out along the way.
+fn settle(bricks: &Vec<Brick>) -> (Vec<Brick>, Vec<(usize, usize)>) {
+    let mut sorted_order: Vec<&Brick> = bricks.iter().collect();
+    sorted_order.sort_by_key(|b| b.bottom_z());
+    let width = bricks
+        .iter()
+        .map(|b| b.front.x.max(b.back.x))
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let depth = bricks
+        .iter()
+        .map(|b| b.front.y.max(b.back.y))
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let mut heights = HeightMap::new(width as usize, depth as usize);
+
+    let mut settled = vec![];
+    let mut supports = vec![];
+    for brick in sorted_order {
+        let (landed, below) = heights.drop(brick);
+        for id in below {
+            supports.push((id, landed.debug_id));
+        }
+        settled.push(landed);
+    }
+    return (settled, supports);
+}
+
+/// (below, above) pairs of bricks touching once settled, found by dropping one cube at a
+/// time and checking every pair. Kept to benchmark against.
+pub fn supports_naive(snapshot: &Vec<String>) -> Vec<(usize, usize)> {
+    let fallen = simulate_fall(&parse_bricks(snapshot));
+    let mut supports = vec![];
+    for brick in fallen.iter() {
+        for other_brick in fallen.iter() {
+            if brick != other_brick && brick.does_support(other_brick) {
+                supports.push((brick.debug_id, other_brick.debug_id));
             }
         }
     }
+    supports.sort();
+    return supports;
+}
 
-    /*
-    println!("Copy this into https://viz-js.com/");
-    println!("{:?}", Dot::new(&graph));
-    */
+/// (below, above) pairs of bricks touching once settled, from the height map.
+/// ```
+/// let vec1: Vec<String> = vec![
+///     "1,0,1~1,2,1",
+///     "0,0,2~2,0,2",
+///     "0,2,3~2,2,3",
+/// ].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(day22::supports(&vec1), vec![(0, 1), (0, 2)]);
+/// ```
+pub fn supports(snapshot: &Vec<String>) -> Vec<(usize, usize)> {
+    let (_, mut supports) = settle(&parse_bricks(snapshot));
+    supports.sort();
+    return supports;
+}
 
-    return graph;
+// Nodes are brick ids, apart from the ground which is None.
+type SupportGraph = Graph<Option<usize>, (), Directed>;
+
+/// The settled stack, with an edge from each brick (or the ground) to every brick resting
+/// directly on it.
+///
+/// A brick falls once everything it rests on is gone, so the bricks that fall when X is
+/// removed are exactly those X dominates: every path up from the ground to them goes
+/// through X. The dominator tree answers that for every brick at once.
+pub struct BrickStack {
+    graph: SupportGraph,
+    ground: NodeIndex,
+    // Node for each brick, indexed by id, which is its line in the snapshot.
+    nodes: Vec<NodeIndex>,
+    // Lowest z of each settled brick, by id.
+    bottoms: Vec<Coord>,
+    dominators: Dominators<NodeIndex>,
 }
 
-fn find_safe_to_disintegrate(graph: &BrickGraph) -> u32 {
-    // Check every node in the graph to those with one neighbor.
-    // if that node doesn't have a brick on Z = 1, count it
-    let end_nodes: Vec<NodeIndex> = graph
-        .node_indices()
-        .filter(|n| {
-            if graph[*n].front.z == GROUND_Z + 1 || graph[*n].back.z == GROUND_Z + 1 {
-                //println!("ground {}", graph[*n].debug_id);
-                return false;
+impl BrickStack {
+    /// Let the snapshot settle, then work out what holds up what.
+    pub fn parse(snapshot: &Vec<String>) -> BrickStack {
+        let bricks = parse_bricks(snapshot);
+        let (fallen, supports) = settle(&bricks);
+        let mut bottoms = vec![0; fallen.len()];
+        for brick in fallen.iter() {
+            bottoms[brick.debug_id] = brick.bottom_z();
+        }
+        return BrickStack::new(bottoms, &supports);
+    }
+
+    // Bricks by id with their settled bottoms, and (below, above) pairs of touching bricks.
+    fn new(bottoms: Vec<Coord>, supports: &Vec<(usize, usize)>) -> BrickStack {
+        let mut graph = Graph::new();
+        let ground = graph.add_node(None);
+        let nodes: Vec<NodeIndex> = (0..bottoms.len())
+            .map(|id| graph.add_node(Some(id)))
+            .collect();
+        for (id, bottom) in bottoms.iter().enumerate() {
+            if *bottom == GROUND_Z + 1 {
+                graph.add_edge(ground, nodes[id], ());
             }
-            if graph.neighbors(*n).count() != 1 {
-                //println!("more than one connection {}", graph[*n].debug_id);
+        }
+        for (below, above) in supports {
+            graph.add_edge(nodes[*below], nodes[*above], ());
+        }
+        let dominators = simple_fast(&graph, ground);
+        return BrickStack {
+            graph: graph,
+            ground: ground,
+            nodes: nodes,
+            bottoms: bottoms,
+            dominators: dominators,
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.nodes.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.nodes.is_empty();
+    }
+
+    fn brick_ids(&self, nodes: impl Iterator<Item = NodeIndex>) -> Vec<usize> {
+        let mut ids: Vec<usize> = nodes.filter_map(|n| self.graph[n]).collect();
+        ids.sort();
+        ids.dedup();
+        return ids;
+    }
+
+    /// Bricks resting directly on brick id.
+    pub fn supporting(&self, id: usize) -> Vec<usize> {
+        return self.brick_ids(
+            self.graph
+                .neighbors_directed(self.nodes[id], Direction::Outgoing),
+        );
+    }
+
+    /// Bricks that brick id rests directly on, empty if it's on the ground.
+    pub fn supported_by(&self, id: usize) -> Vec<usize> {
+        return self.brick_ids(
+            self.graph
+                .neighbors_directed(self.nodes[id], Direction::Incoming),
+        );
+    }
+
+    /// The one brick every support of brick id leads down through, None if nothing
+    /// short of the ground does.
+    pub fn critical_support(&self, id: usize) -> Option<usize> {
+        let idom = self.dominators.immediate_dominator(self.nodes[id]).unwrap();
+        return self.graph[idom];
+    }
+
+    /// For every brick by id, how many other bricks fall if it is disintegrated.
+    pub fn chain_reactions(&self) -> Vec<usize> {
+        // A brick's dominator is always lower down, so going top down every dominator
+        // subtree is complete before it's added to its parent.
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        order.sort_by_key(|id| std::cmp::Reverse(self.bottoms[*id]));
+        let mut falls = vec![0; self.len()];
+        for id in order {
+            if let Some(parent) = self.critical_support(id) {
+                falls[parent] += falls[id] + 1;
             }
-            return graph.neighbors(*n).count() == 1;
-        })
-        .collect();
-    let multiple_supports: Vec<NodeIndex> = graph
-        .node_indices()
-        .filter(|n| {
-            // for each neighbor, check if it has multiple supports. If it does, I can go.
-            //println!("Checking neighbors of {}", graph[*n].debug_id);
-
-            // Get number of supports
-            for m in graph.neighbors(*n) {
-                if !graph[*n].does_support(&graph[m]) {
-                    continue;
+        }
+        return falls;
+    }
+
+    /// Bricks that would make at least one other brick fall.
+    pub fn load_bearing(&self) -> Vec<usize> {
+        return self.brick_ids(
+            self.nodes
+                .iter()
+                .filter_map(|n| self.dominators.immediate_dominator(*n)),
+        );
+    }
+
+    /// Fewest bricks to disintegrate so brick id falls, sorted by id. None if it is on
+    /// the ground, as nothing else can bring it down.
+    ///
+    /// A minimum vertex cut between the ground and the brick, found as a max flow with
+    /// each brick split into an in and an out node joined by a capacity of one.
+    pub fn removals_to_drop(&self, id: usize) -> Option<Vec<usize>> {
+        let target = self.nodes[id];
+        if self.graph.contains_edge(self.ground, target) {
+            return None;
+        }
+        if let Some(single) = self.critical_support(id) {
+            return Some(vec![single]);
+        }
+
+        let node_in = |n: NodeIndex| n.index() * 2;
+        let node_out = |n: NodeIndex| n.index() * 2 + 1;
+        let unlimited = self.graph.node_count() as i64 + 1;
+        let size = self.graph.node_count() * 2;
+        let mut adjacent: Vec<Vec<usize>> = vec![vec![]; size];
+        let mut capacity: HashMap<(usize, usize), i64> = HashMap::new();
+        let mut add_edge = |from: usize, to: usize, amount: i64| {
+            adjacent[from].push(to);
+            adjacent[to].push(from);
+            *capacity.entry((from, to)).or_insert(0) += amount;
+            capacity.entry((to, from)).or_insert(0);
+        };
+        for n in self.graph.node_indices() {
+            let amount = if n == self.ground { unlimited } else { 1 };
+            add_edge(node_in(n), node_out(n), amount);
+        }
+        for edge in self.graph.edge_indices() {
+            let (from, to) = self.graph.edge_endpoints(edge).unwrap();
+            add_edge(node_out(from), node_in(to), unlimited);
+        }
+
+        let source = node_in(self.ground);
+        let sink = node_in(target);
+        loop {
+            // Shortest augmenting path, each one carries a single unit.
+            let mut previous: Vec<Option<usize>> = vec![None; size];
+            let mut queue = VecDeque::from([source]);
+            while let Some(at) = queue.pop_front() {
+                if at == sink {
+                    break;
                 }
-                let mut support_count = 0;
-                //println!("Checking neighbor {}", graph[m].debug_id);
-                for l in graph.neighbors(m) {
-                    if graph[l].does_support(&graph[m]) {
-                        /*println!(
-                            "Detected {} supports {}",
-                            graph[l].debug_id, graph[m].debug_id
-                        );*/
-                        support_count += 1;
+                for next in adjacent[at].iter() {
+                    if *next != source && previous[*next].is_none() && capacity[&(at, *next)] > 0 {
+                        previous[*next] = Some(at);
+                        queue.push_back(*next);
                     }
                 }
-                if support_count == 1 {
-                    //println!("Multiple supports not found for neighbor of {}", graph[*n].debug_id);
-                    return false;
-                }
             }
-            return true;
-        })
-        .collect();
-
-    let mut set = HashSet::new();
-    set.extend(end_nodes);
-    set.extend(multiple_supports);
-    return set.len().try_into().unwrap();
+            if previous[sink].is_none() {
+                // The cut is every brick whose in node is still reachable but out node isn't.
+                let cut = self.graph.node_indices().filter(|n| {
+                    return (node_in(*n) == source || previous[node_in(*n)].is_some())
+                        && previous[node_out(*n)].is_none();
+                });
+                return Some(self.brick_ids(cut));
+            }
+            let mut at = sink;
+            while let Some(before) = previous[at] {
+                *capacity.get_mut(&(before, at)).unwrap() -= 1;
+                *capacity.get_mut(&(at, before)).unwrap() += 1;
+                at = before;
+            }
+        }
+    }
 }
 
 /// 3d bricks, how fun. Find which can be removed without causing a brick to fall.
@@ -356,16 +568,13 @@ fn find_safe_to_disintegrate(graph: &BrickGraph) -> u32 {
 /// assert_eq!(day22::puzzle_a(&vec1), 5);
 /// ```
 pub fn puzzle_a(string_list: &Vec<String>) -> u32 {
-    let bricks = parse_bricks(string_list);
-    //println!("Starting fall");
-    let fallen = simulate_fall(&bricks);
-    //println!("Starting graph");
-    let graph = build_brick_graph(&fallen);
-    //println!("Starting disintegrate");
-    return find_safe_to_disintegrate(&graph);
+    let stack = BrickStack::parse(string_list);
+    return (stack.len() - stack.load_bearing().len())
+        .try_into()
+        .unwrap();
 }
 
-/// Toplogical sort, and take the sum of everything but the last element.
+/// Sum of how many bricks fall for each brick disintegrated, from the dominator tree.
 /// ```
 /// let vec1: Vec<String> = vec![
 ///     "1,0,1~1,2,1",
@@ -379,61 +588,8 @@ pub fn puzzle_a(string_list: &Vec<String>) -> u32 {
 /// assert_eq!(day22::puzzle_b(&vec1), 7);
 /// ```
 pub fn puzzle_b(string_list: &Vec<String>) -> usize {
-    let bricks = parse_bricks(string_list);
-    //println!("Starting fall");
-    let fallen = simulate_fall(&bricks);
-    //println!("Starting graph");
-    let graph = build_brick_graph(&fallen);
-    let mut sum = 0;
-
-    for n in graph.node_indices() {
-        let my_supports = get_direct_supports(&graph, &n);
-        if my_supports.is_empty() {
-            continue;
-        }
-
-        let mut queue: VecDeque<NodeIndex> = VecDeque::new();
-        queue.push_back(n);
-        let mut fallen_indexes: Vec<NodeIndex> = vec![];
-        while let Some(m) = queue.pop_front() {
-            fallen_indexes.push(m);
-            let m_supports = get_direct_supports(&graph, &m);
-
-            // Check that all bricks supported by m have fallen.
-            for a in m_supports {
-                let supports = get_supported_by(&graph, &a);
-                if supports.len() > 0 && supports.iter().all(|x| fallen_indexes.contains(x)) {
-                    queue.push_back(a);
-                }
-            }
-        }
-
-        if fallen_indexes.len() > 0 {
-            sum += fallen_indexes.len() - 1;
-        }
-    }
-
-    return sum;
-}
-
-fn get_direct_supports(graph: &BrickGraph, node: &NodeIndex) -> Vec<NodeIndex> {
-    let mut supports = vec![];
-    for n in graph.neighbors(*node) {
-        if graph[*node].does_support(&graph[n]) {
-            supports.push(n.clone());
-        }
-    }
-    return supports;
-}
-
-fn get_supported_by(graph: &BrickGraph, node: &NodeIndex) -> Vec<NodeIndex> {
-    let mut supports = vec![];
-    for n in graph.neighbors(*node) {
-        if graph[n].does_support(&graph[*node]) {
-            supports.push(n.clone());
-        }
-    }
-    return supports;
+    let stack = BrickStack::parse(string_list);
+    return stack.chain_reactions().into_iter().sum();
 }
 
 #[cfg(test)]
@@ -464,4 +620,110 @@ mod tests {
         assert_eq!(one_brick.does_intersect(&far_away_brick), false);
         assert_eq!(far_away_brick.does_intersect(&one_brick), false);
     }
+
+    fn example() -> Vec<String> {
+        return vec![
+            "1,0,1~1,2,1",
+            "0,0,2~2,0,2",
+            "0,2,3~2,2,3",
+            "0,0,4~0,2,4",
+            "2,0,5~2,2,5",
+            "0,1,6~2,1,6",
+            "1,1,8~1,1,9",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    }
+
+    // Remove bricks one at a time from the bottom up, the slow way.
+    fn falls_without(stack: &BrickStack, removed: &Vec<usize>) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..stack.len()).collect();
+        order.sort_by_key(|id| stack.bottoms[*id]);
+        let mut gone: HashSet<usize> = removed.iter().cloned().collect();
+        let mut fallen = vec![];
+        for id in order {
+            let below = stack.supported_by(id);
+            if !gone.contains(&id) && !below.is_empty() && below.iter().all(|b| gone.contains(b)) {
+                gone.insert(id);
+                fallen.push(id);
+            }
+        }
+        fallen.sort();
+        return fallen;
+    }
+
+    #[test]
+    fn test_example_stack() {
+        let stack = BrickStack::parse(&example());
+        assert_eq!(stack.len(), 7);
+        assert_eq!(stack.supporting(0), vec![1, 2]);
+        assert_eq!(stack.supported_by(3), vec![1, 2]);
+        assert_eq!(stack.supported_by(0), vec![]);
+        assert_eq!(stack.chain_reactions(), vec![6, 0, 0, 0, 0, 1, 0]);
+        assert_eq!(stack.load_bearing(), vec![0, 5]);
+        assert_eq!(stack.critical_support(6), Some(5));
+        assert_eq!(stack.critical_support(0), None);
+        for id in 0..stack.len() {
+            assert_eq!(
+                stack.chain_reactions()[id],
+                falls_without(&stack, &vec![id]).len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_removals_to_drop() {
+        let stack = BrickStack::parse(&example());
+        assert_eq!(stack.removals_to_drop(0), None);
+        assert_eq!(stack.removals_to_drop(3), Some(vec![0]));
+        assert_eq!(stack.removals_to_drop(6), Some(vec![5]));
+
+        // Two towers joined at the top, then one more brick resting on the join and a
+        // third tower.
+        let stack = BrickStack::new(
+            vec![1, 1, 2, 3, 4, 1, 5],
+            &vec![(0, 2), (1, 3), (2, 4), (3, 4), (4, 6), (5, 6)],
+        );
+        assert_eq!(stack.critical_support(4), None);
+        let cut = stack.removals_to_drop(4).unwrap();
+        assert_eq!(cut.len(), 2);
+        assert!(falls_without(&stack, &cut).contains(&4));
+        let cut = stack.removals_to_drop(6).unwrap();
+        assert_eq!(cut.len(), 2);
+        assert!(cut.contains(&5));
+        assert!(falls_without(&stack, &cut).contains(&6));
+        assert_eq!(stack.chain_reactions(), vec![1, 1, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_supports_match_naive() {
+        assert_eq!(supports(&example()), supports_naive(&example()));
+
+        // Straight bricks stacked one layer above the last, so none start out overlapping.
+        let mut state: u64 = 22;
+        let mut next = |limit: u64| -> u32 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            return ((state >> 33) % limit) as u32;
+        };
+        let mut lines = vec![];
+        let mut z = 1;
+        for _ in 0..60 {
+            let (x, y) = (next(5), next(5));
+            let length = next(3);
+            let back = match next(3) {
+                0 => (x + length, y, z),
+                1 => (x, y + length, z),
+                _ => (x, y, z + length),
+            };
+            lines.push(format!(
+                "{},{},{}~{},{},{}",
+                x, y, z, back.0, back.1, back.2
+            ));
+            z = back.2 + 1 + next(2);
+        }
+        assert_eq!(supports(&lines), supports_naive(&lines));
+    }
 }
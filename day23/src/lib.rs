@@ -1,16 +1,12 @@
 extern crate filelib;
 
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
-
 pub use filelib::load_no_blanks;
+use gridlib::compress_grid_directed;
 use gridlib::Direction;
 use gridlib::Grid;
 use gridlib::GridCoordinate;
 use gridlib::GridTraversable;
 
-const DEBUG: bool = false;
-
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum Terrain {
     Path,
@@ -81,118 +77,158 @@ fn find_exit(grid: &Grid<Terrain>) -> GridCoordinate {
     return GridCoordinate::new(99, 99);
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-struct QueueState {
-    cur_location: GridCoordinate,
-    distance: u32,
-    previous_steps: Vec<GridCoordinate>,
+// Can you step from one tile in this direction? Slopes only let you go downhill.
+fn can_leave(terrain: Terrain, direction: Direction) -> bool {
+    return match terrain {
+        Terrain::Path => true,
+        Terrain::Forest => false,
+        Terrain::Slope(d) => d == direction,
+    };
 }
 
-impl Ord for QueueState {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // Make large distances happen first!!!
-        self.distance
-            .cmp(&other.distance)
-            .then_with(|| other.previous_steps.len().cmp(&self.previous_steps.len()))
-            .then_with(|| other.cur_location.cmp(&self.cur_location))
-    }
+/// The trails squashed down to the junctions, dead ends, the entrance and the exit, with
+/// an edge for each corridor you can walk between two of them and its length in steps.
+pub struct TrailGraph {
+    junctions: Vec<GridCoordinate>,
+    // Outgoing (junction, length) for each junction.
+    edges: Vec<Vec<(usize, usize)>>,
+    start: usize,
+    end: usize,
 }
 
-impl PartialOrd for QueueState {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        return Some(self.cmp(other));
+impl TrailGraph {
+    /// Slopes are one way when with_slope, plain path otherwise.
+    pub fn parse(string_list: &Vec<String>, with_slope: bool) -> TrailGraph {
+        let grid = parse_terrain(string_list, with_slope);
+        return TrailGraph::new(&grid);
     }
-}
 
-fn find_longest_path(
-    grid: &Grid<Terrain>,
-    start: &GridCoordinate,
-    end: &GridCoordinate,
-) -> Vec<GridCoordinate> {
-    let mut final_path = vec![];
-    let mut dist = vec![0; grid.get_height() * grid.get_width()];
+    fn new(grid: &Grid<Terrain>) -> TrailGraph {
+        let entrance = find_entrance(grid);
+        let exit = find_exit(grid);
+        let corridors = compress_grid_directed(
+            grid,
+            |t| t != Terrain::Forest,
+            can_leave,
+            &vec![entrance, exit],
+        );
+        let junctions = corridors.get_nodes().clone();
+        let mut edges = vec![vec![]; junctions.len()];
+        for edge in corridors.get_edges() {
+            // Going round a loop back to the same junction never helps.
+            if edge.from != edge.to {
+                edges[edge.from].push((edge.to, edge.length));
+            }
+        }
 
-    let mut queue = BinaryHeap::new();
-    queue.push(QueueState {
-        cur_location: start.clone(),
-        distance: 0,
-        previous_steps: vec![],
-    });
+        return TrailGraph {
+            junctions: junctions,
+            edges: edges,
+            start: 0,
+            end: 1,
+        };
+    }
 
-    while let Some(state) = queue.pop() {
-        let index = state.cur_location.y * grid.get_width() + state.cur_location.x;
+    pub fn get_junctions(&self) -> &Vec<GridCoordinate> {
+        return &self.junctions;
+    }
 
-        // We have looped, throw out path
-        if state.previous_steps.contains(&state.cur_location) {
-            continue;
+    /// Junctions in an order where every corridor leads later, None if there is a loop.
+    pub fn topological_order(&self) -> Option<Vec<usize>> {
+        let mut incoming = vec![0; self.junctions.len()];
+        for edges in self.edges.iter() {
+            for (to, _) in edges {
+                incoming[*to] += 1;
+            }
         }
-
-        if state.distance > dist[index] {
-            // We have a longer path to here, throw this out.
-            dist[index] = state.distance;
-            if state.cur_location == *end {
-                final_path = state.previous_steps.clone();
-                final_path.push(state.cur_location);
-                continue;
+        let mut ready: Vec<usize> = (0..self.junctions.len())
+            .filter(|j| incoming[*j] == 0)
+            .collect();
+        let mut order = vec![];
+        while let Some(junction) = ready.pop() {
+            order.push(junction);
+            for (to, _) in self.edges[junction].iter() {
+                incoming[*to] -= 1;
+                if incoming[*to] == 0 {
+                    ready.push(*to);
+                }
             }
         }
+        if order.len() != self.junctions.len() {
+            return None;
+        }
+        return Some(order);
+    }
 
-        let cur_terrain = grid.get_value(state.cur_location).unwrap();
-        match cur_terrain {
-            Terrain::Path => {
-                // check all four directions.
-                for possible_dir in [
-                    Direction::NORTH,
-                    Direction::EAST,
-                    Direction::SOUTH,
-                    Direction::WEST,
-                ] {
-                    let next_pos =
-                        grid.get_coordinate_by_direction(state.cur_location, possible_dir);
-
-                    if next_pos == None {
-                        continue;
-                    }
-
-                    let next_loc = next_pos.unwrap();
-                    if state.previous_steps.contains(&next_loc) {
-                        continue;
+    /// Longest walk from entrance to exit when the corridors can't loop back,
+    /// in one pass over the topological order. None if there's a loop or no way out.
+    pub fn longest_path_dag(&self) -> Option<usize> {
+        let order = self.topological_order()?;
+        let mut best: Vec<Option<usize>> = vec![None; self.junctions.len()];
+        best[self.start] = Some(0);
+        for junction in order {
+            if let Some(so_far) = best[junction] {
+                for (to, length) in self.edges[junction].iter() {
+                    let through = so_far + length;
+                    if best[*to] < Some(through) {
+                        best[*to] = Some(through);
                     }
-                    let mut steps = state.previous_steps.clone();
-                    steps.push(state.cur_location);
-                    let next_state = QueueState {
-                        cur_location: next_loc,
-                        distance: state.distance + 1,
-                        previous_steps: steps,
-                    };
-                    queue.push(next_state);
                 }
             }
-            Terrain::Forest => {
-                // Got onto a forest, abandon path.
+        }
+        return best[self.end];
+    }
+
+    /// Longest walk from entrance to exit never visiting a junction twice, by trying them
+    /// all with the visited junctions in a u64. None if there's no way out.
+    pub fn longest_path_dfs(&self) -> Option<usize> {
+        assert!(
+            self.junctions.len() <= 64,
+            "Too many junctions for a u64 mask"
+        );
+        // The exit usually hangs off a single junction. Once there, going anywhere else
+        // can never come back, so treat reaching it as reaching the exit.
+        let mut target = self.end;
+        let mut bonus = 0;
+        let into_end: Vec<(usize, usize)> = (0..self.junctions.len())
+            .flat_map(|from| {
+                return self.edges[from]
+                    .iter()
+                    .filter(|(to, _)| *to == self.end)
+                    .map(move |(_, length)| (from, *length));
+            })
+            .collect();
+        if into_end.len() == 1 {
+            (target, bonus) = into_end[0];
+        }
+        return self
+            .dfs(self.start, target, 1 << self.start)
+            .map(|length| length + bonus);
+    }
+
+    fn dfs(&self, at: usize, target: usize, visited: u64) -> Option<usize> {
+        if at == target {
+            return Some(0);
+        }
+        let mut best = None;
+        for (to, length) in self.edges[at].iter() {
+            if visited & (1 << to) != 0 {
                 continue;
             }
-            Terrain::Slope(d) => {
-                // Follow the slope direction
-                let next_pos = grid.get_coordinate_by_direction(state.cur_location, d);
-                // Fell off the edge from the slope
-                if next_pos == None {
-                    continue;
-                }
-
-                let next_loc = next_pos.unwrap();
-                let mut steps = state.previous_steps.clone();
-                steps.push(state.cur_location);
-                let next_state = QueueState {
-                    cur_location: next_loc,
-                    distance: state.distance + 1,
-                    previous_steps: steps,
-                };
-                queue.push(next_state);
+            if let Some(rest) = self.dfs(*to, target, visited | (1 << to)) {
+                best = best.max(Some(rest + length));
             }
         }
+        return best;
+    }
+
+    /// Longest walk from entrance to exit, taking the fast path when slopes rule out loops.
+    pub fn longest_path(&self) -> Option<usize> {
+        if self.topological_order().is_some() {
+            return self.longest_path_dag();
+        }
+        return self.longest_path_dfs();
     }
-    return final_path;
 }
 
 /// Find LONGEST path without steping on a tile twice.
@@ -225,41 +261,7 @@ fn find_longest_path(
 /// assert_eq!(day23::puzzle_a(&vec1), 94);
 /// ```
 pub fn puzzle_a(string_list: &Vec<String>) -> usize {
-    let grid = parse_terrain(string_list, true);
-    let entrance = find_entrance(&grid);
-    let exit = find_exit(&grid);
-    // We don't include The start square for some reason.
-    return find_longest_path(&grid, &entrance, &exit).len() - 1;
-}
-
-fn print_path(grid: &Grid<Terrain>, path: &Vec<GridCoordinate>) {
-    if !DEBUG {
-        return;
-    }
-
-    let mut cur_y = 0;
-    for c in grid.coord_iter() {
-        if c.y != cur_y {
-            cur_y = c.y;
-            println!("");
-        }
-        let v = grid.get_value(c).unwrap();
-        if path.contains(&c) {
-            print!("O");
-        } else {
-            match v {
-                Terrain::Path => print!("."),
-                Terrain::Forest => print!("#"),
-                Terrain::Slope(d) => match d {
-                    Direction::NORTH => print!("^"),
-                    Direction::EAST => print!(">"),
-                    Direction::SOUTH => print!("v"),
-                    Direction::WEST => print!("<"),
-                    _ => panic!("???"),
-                },
-            }
-        }
-    }
+    return TrailGraph::parse(string_list, true).longest_path().unwrap();
 }
 
 /// Ignore slopes.
@@ -292,13 +294,81 @@ fn print_path(grid: &Grid<Terrain>, path: &Vec<GridCoordinate>) {
 /// assert_eq!(day23::puzzle_b(&vec1), 154);
 /// ```
 pub fn puzzle_b(string_list: &Vec<String>) -> usize {
-    let grid = parse_terrain(string_list, false);
-    let entrance = find_entrance(&grid);
-    let exit = find_exit(&grid);
-    let path = find_longest_path(&grid, &entrance, &exit);
+    return TrailGraph::parse(string_list, false)
+        .longest_path()
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<String> {
+        return vec![
+            "#.#####################",
+            "#.......#########...###",
+            "#######.#########.#.###",
+            "###.....#.>.>.###.#.###",
+            "###v#####.#v#.###.#.###",
+            "###.>...#.#.#.....#...#",
+            "###v###.#.#.#########.#",
+            "###...#.#.#.......#...#",
+            "#####.#.#.#######.#.###",
+            "#.....#.#.#.......#...#",
+            "#.#####.#.#.#########v#",
+            "#.#...#...#...###...>.#",
+            "#.#.#v#######v###.###v#",
+            "#...#.>.#...>.>.#.###.#",
+            "#####v#.#.###v#.#.###.#",
+            "#.....#...#...#.#.#...#",
+            "#.#########.###.#.#.###",
+            "#...###...#...#...#.###",
+            "###.###.#.###v#####v###",
+            "#...#...#.#.>.>.#.>.###",
+            "#.###.###.#.###.#.#v###",
+            "#.....###...###...#...#",
+            "#####################.#",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    }
 
-    print_path(&grid, &path);
+    #[test]
+    fn test_junctions() {
+        let graph = TrailGraph::parse(&example(), true);
+        assert_eq!(graph.get_junctions().len(), 9);
+        assert_eq!(graph.get_junctions()[0], GridCoordinate::new(1, 0));
+        assert_eq!(graph.get_junctions()[1], GridCoordinate::new(21, 22));
+        // The entrance corridor is 15 steps to the first junction.
+        assert_eq!(graph.edges[0].len(), 1);
+        assert_eq!(graph.edges[0][0].1, 15);
+    }
+
+    #[test]
+    fn test_slopes_are_acyclic() {
+        let graph = TrailGraph::parse(&example(), true);
+        assert!(graph.topological_order().is_some());
+        assert_eq!(graph.longest_path_dag(), Some(94));
+        assert_eq!(graph.longest_path_dfs(), Some(94));
+    }
 
-    // We don't include The start square for some reason.
-    return path.len() - 1;
+    #[test]
+    fn test_without_slopes_loops() {
+        let graph = TrailGraph::parse(&example(), false);
+        assert_eq!(graph.topological_order(), None);
+        assert_eq!(graph.longest_path_dag(), None);
+        assert_eq!(graph.longest_path_dfs(), Some(154));
+    }
+
+    #[test]
+    fn test_single_corridor() {
+        let lines: Vec<String> = vec!["#.###", "#...#", "###.#"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let graph = TrailGraph::parse(&lines, true);
+        assert_eq!(graph.get_junctions().len(), 2);
+        assert_eq!(graph.longest_path(), Some(4));
+    }
 }
@@ -0,0 +1,675 @@
+use crate::direction::Direction;
+use crate::grid::coordinate_by_direction;
+use crate::grid::{Grid, GridRotation, GridTraversable};
+use crate::gridcoord::GridCoordinate;
+
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign};
+
+const WORD_BITS: usize = 64;
+
+// Mask of the lowest `count` bits, count may be a whole word.
+fn low_bits(count: usize) -> u64 {
+    if count >= WORD_BITS {
+        return u64::MAX;
+    }
+    return (1 << count) - 1;
+}
+
+/// Fixed length row of bits, packed into u64 words.
+///
+/// Index 0 is the lowest bit of the first word. Bits past the length are always kept
+/// zero, so equal lines hash the same.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitLine {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl BitLine {
+    /// All bits unset.
+    pub fn new(len: usize) -> BitLine {
+        return BitLine {
+            len: len,
+            words: vec![0; len.div_ceil(WORD_BITS)],
+        };
+    }
+
+    pub fn get_len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        assert!(index < self.len, "Bit {} out of range {}", index, self.len);
+        return self.words[index / WORD_BITS] >> (index % WORD_BITS) & 1 == 1;
+    }
+
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.len, "Bit {} out of range {}", index, self.len);
+        let mask = 1 << (index % WORD_BITS);
+        if value {
+            self.words[index / WORD_BITS] |= mask;
+        } else {
+            self.words[index / WORD_BITS] &= !mask;
+        }
+    }
+
+    /// Unset every bit.
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub fn count_ones(&self) -> usize {
+        return self.words.iter().map(|w| w.count_ones() as usize).sum();
+    }
+
+    /// Number of set bits in start..end.
+    pub fn count_ones_in(&self, start: usize, end: usize) -> usize {
+        assert!(
+            start <= end && end <= self.len,
+            "Bad range {}..{}",
+            start,
+            end
+        );
+        let mut total = 0;
+        let mut index = start;
+        while index < end {
+            let word = index / WORD_BITS;
+            let offset = index % WORD_BITS;
+            let take = (WORD_BITS - offset).min(end - index);
+            total += (self.words[word] >> offset & low_bits(take)).count_ones() as usize;
+            index += take;
+        }
+        return total;
+    }
+
+    /// Set every bit in start..end to value.
+    pub fn set_range(&mut self, start: usize, end: usize, value: bool) {
+        assert!(
+            start <= end && end <= self.len,
+            "Bad range {}..{}",
+            start,
+            end
+        );
+        let mut index = start;
+        while index < end {
+            let word = index / WORD_BITS;
+            let offset = index % WORD_BITS;
+            let take = (WORD_BITS - offset).min(end - index);
+            let mask = low_bits(take) << offset;
+            if value {
+                self.words[word] |= mask;
+            } else {
+                self.words[word] &= !mask;
+            }
+            index += take;
+        }
+    }
+
+    /// Indexes of the set bits, in increasing order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        return self
+            .words
+            .iter()
+            .enumerate()
+            .flat_map(|(word_index, word)| {
+                let mut remaining = *word;
+                return std::iter::from_fn(move || {
+                    if remaining == 0 {
+                        return None;
+                    }
+                    let bit = remaining.trailing_zeros() as usize;
+                    remaining &= remaining - 1;
+                    return Some(word_index * WORD_BITS + bit);
+                });
+            });
+    }
+
+    /// Every bit moves `amount` towards index 0, bits shifted past the start are lost.
+    pub fn shift_left(&self, amount: usize) -> BitLine {
+        let mut result = BitLine::new(self.len);
+        let word_shift = amount / WORD_BITS;
+        let bit_shift = amount % WORD_BITS;
+        for i in 0..result.words.len() {
+            let source = i + word_shift;
+            if source >= self.words.len() {
+                break;
+            }
+            let mut word = self.words[source] >> bit_shift;
+            if bit_shift > 0 && source + 1 < self.words.len() {
+                word |= self.words[source + 1] << (WORD_BITS - bit_shift);
+            }
+            result.words[i] = word;
+        }
+        return result;
+    }
+
+    /// Every bit moves `amount` away from index 0, bits shifted past the end are lost.
+    pub fn shift_right(&self, amount: usize) -> BitLine {
+        let mut result = BitLine::new(self.len);
+        let word_shift = amount / WORD_BITS;
+        let bit_shift = amount % WORD_BITS;
+        for i in word_shift..result.words.len() {
+            let source = i - word_shift;
+            let mut word = self.words[source] << bit_shift;
+            if bit_shift > 0 && source > 0 {
+                word |= self.words[source - 1] >> (WORD_BITS - bit_shift);
+            }
+            result.words[i] = word;
+        }
+        result.clear_tail();
+        return result;
+    }
+
+    /// Every bit flipped.
+    pub fn invert(&self) -> BitLine {
+        let mut result = BitLine {
+            len: self.len,
+            words: self.words.iter().map(|w| !w).collect(),
+        };
+        result.clear_tail();
+        return result;
+    }
+
+    /// Same bits in the opposite order.
+    pub fn reverse(&self) -> BitLine {
+        let mut result = BitLine::new(self.len);
+        for index in self.iter_ones() {
+            result.set(self.len - 1 - index, true);
+        }
+        return result;
+    }
+
+    fn clear_tail(&mut self) {
+        let used = self.len % WORD_BITS;
+        if used > 0 {
+            let last = self.words.len() - 1;
+            self.words[last] &= low_bits(used);
+        }
+    }
+
+    fn combine_assign(&mut self, other: &BitLine, op: fn(u64, u64) -> u64) {
+        assert_eq!(self.len, other.len, "BitLines must be the same length");
+        for (a, b) in self.words.iter_mut().zip(other.words.iter()) {
+            *a = op(*a, *b);
+        }
+    }
+
+    fn combine(&self, other: &BitLine, op: fn(u64, u64) -> u64) -> BitLine {
+        assert_eq!(self.len, other.len, "BitLines must be the same length");
+        return BitLine {
+            len: self.len,
+            words: self
+                .words
+                .iter()
+                .zip(other.words.iter())
+                .map(|(a, b)| op(*a, *b))
+                .collect(),
+        };
+    }
+}
+
+impl BitAnd for &BitLine {
+    type Output = BitLine;
+
+    fn bitand(self, other: &BitLine) -> BitLine {
+        return self.combine(other, |a, b| a & b);
+    }
+}
+
+impl BitOr for &BitLine {
+    type Output = BitLine;
+
+    fn bitor(self, other: &BitLine) -> BitLine {
+        return self.combine(other, |a, b| a | b);
+    }
+}
+
+impl BitXor for &BitLine {
+    type Output = BitLine;
+
+    fn bitxor(self, other: &BitLine) -> BitLine {
+        return self.combine(other, |a, b| a ^ b);
+    }
+}
+
+impl BitAndAssign<&BitLine> for BitLine {
+    fn bitand_assign(&mut self, other: &BitLine) {
+        self.combine_assign(other, |a, b| a & b);
+    }
+}
+
+impl BitOrAssign<&BitLine> for BitLine {
+    fn bitor_assign(&mut self, other: &BitLine) {
+        self.combine_assign(other, |a, b| a | b);
+    }
+}
+
+impl BitXorAssign<&BitLine> for BitLine {
+    fn bitxor_assign(&mut self, other: &BitLine) {
+        self.combine_assign(other, |a, b| a ^ b);
+    }
+}
+
+/// Grid of booleans, stored as one BitLine per row.
+///
+/// Same coordinates as Grid, top left corner is 0,0. Cheap to compare and hash, so it works
+/// well as a cache key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    rows: Vec<BitLine>,
+}
+
+impl BitGrid {
+    /// All cells unset.
+    pub fn new(width: usize, height: usize) -> BitGrid {
+        return BitGrid {
+            width: width,
+            height: height,
+            rows: vec![BitLine::new(width); height],
+        };
+    }
+
+    /// Set every cell whose value matches the predicate.
+    pub fn from_grid<T: Copy, F: Fn(T) -> bool>(grid: &Grid<T>, predicate: F) -> BitGrid {
+        let mut result = BitGrid::new(grid.get_width(), grid.get_height());
+        for coord in grid.coord_iter() {
+            if predicate(grid.get_value(coord).unwrap()) {
+                result.rows[coord.y].set(coord.x, true);
+            }
+        }
+        return result;
+    }
+
+    pub fn to_grid(&self) -> Grid<bool> {
+        let mut values = Vec::with_capacity(self.width * self.height);
+        for row in self.rows.iter() {
+            for x in 0..self.width {
+                values.push(row.get(x));
+            }
+        }
+        return Grid::new(self.width, self.height, values);
+    }
+
+    pub fn get_width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn get_height(&self) -> usize {
+        return self.height;
+    }
+
+    /// Row y, bit x is the cell at (x, y).
+    pub fn get_row(&self, y: usize) -> &BitLine {
+        return &self.rows[y];
+    }
+
+    pub fn get_row_mut(&mut self, y: usize) -> &mut BitLine {
+        return &mut self.rows[y];
+    }
+
+    pub fn set_row(&mut self, y: usize, row: BitLine) {
+        assert_eq!(row.get_len(), self.width, "Row must be the grid width");
+        self.rows[y] = row;
+    }
+
+    /// Column x, bit y is the cell at (x, y).
+    pub fn get_column(&self, x: usize) -> BitLine {
+        let mut result = BitLine::new(self.height);
+        for (y, row) in self.rows.iter().enumerate() {
+            if row.get(x) {
+                result.set(y, true);
+            }
+        }
+        return result;
+    }
+
+    /// Flip over the main diagonal, so rows become columns.
+    pub fn transpose(&self) -> BitGrid {
+        return BitGrid {
+            width: self.height,
+            height: self.width,
+            rows: (0..self.width).map(|x| self.get_column(x)).collect(),
+        };
+    }
+
+    /// Unset every cell.
+    pub fn clear(&mut self) {
+        for row in self.rows.iter_mut() {
+            row.clear();
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        return self.rows.iter().map(|row| row.count_ones()).sum();
+    }
+
+    /// Coordinates of the set cells, in the same order as coord_iter.
+    pub fn iter_ones(&self) -> impl Iterator<Item = GridCoordinate> + '_ {
+        return self
+            .rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter_ones().map(move |x| GridCoordinate::new(x, y)));
+    }
+
+    /// Every set cell moved one step in direction, cells moved off the edge are lost.
+    pub fn shift(&self, direction: Direction) -> BitGrid {
+        if self.height == 0 {
+            return self.clone();
+        }
+        let empty = BitLine::new(self.width);
+        let rows = match direction {
+            Direction::NORTH => self.rows[1..]
+                .iter()
+                .cloned()
+                .chain(std::iter::once(empty))
+                .collect(),
+            Direction::SOUTH => std::iter::once(empty)
+                .chain(self.rows[..self.height - 1].iter().cloned())
+                .collect(),
+            Direction::EAST => self.rows.iter().map(|row| row.shift_right(1)).collect(),
+            Direction::WEST => self.rows.iter().map(|row| row.shift_left(1)).collect(),
+            Direction::NORTHEAST => {
+                return self.shift(Direction::NORTH).shift(Direction::EAST);
+            }
+            Direction::SOUTHEAST => {
+                return self.shift(Direction::SOUTH).shift(Direction::EAST);
+            }
+            Direction::SOUTHWEST => {
+                return self.shift(Direction::SOUTH).shift(Direction::WEST);
+            }
+            Direction::NORTHWEST => {
+                return self.shift(Direction::NORTH).shift(Direction::WEST);
+            }
+        };
+        return BitGrid {
+            width: self.width,
+            height: self.height,
+            rows: rows,
+        };
+    }
+
+    /// Every cell flipped.
+    pub fn invert(&self) -> BitGrid {
+        return BitGrid {
+            width: self.width,
+            height: self.height,
+            rows: self.rows.iter().map(|row| row.invert()).collect(),
+        };
+    }
+
+    fn combine(&self, other: &BitGrid, op: fn(&BitLine, &BitLine) -> BitLine) -> BitGrid {
+        assert_eq!(self.width, other.width, "BitGrids must be the same width");
+        assert_eq!(
+            self.height, other.height,
+            "BitGrids must be the same height"
+        );
+        return BitGrid {
+            width: self.width,
+            height: self.height,
+            rows: self
+                .rows
+                .iter()
+                .zip(other.rows.iter())
+                .map(|(a, b)| op(a, b))
+                .collect(),
+        };
+    }
+}
+
+impl BitAnd for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitand(self, other: &BitGrid) -> BitGrid {
+        return self.combine(other, |a, b| a & b);
+    }
+}
+
+impl BitOr for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitor(self, other: &BitGrid) -> BitGrid {
+        return self.combine(other, |a, b| a | b);
+    }
+}
+
+impl BitXor for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitxor(self, other: &BitGrid) -> BitGrid {
+        return self.combine(other, |a, b| a ^ b);
+    }
+}
+
+impl GridTraversable for BitGrid {
+    type Item = bool;
+
+    fn get_value(&self, pos: GridCoordinate) -> Option<Self::Item> {
+        if pos.y >= self.height || pos.x >= self.width {
+            return None;
+        }
+        return Some(self.rows[pos.y].get(pos.x));
+    }
+
+    fn set_value(&mut self, pos: GridCoordinate, value: Self::Item) {
+        if pos.y >= self.height || pos.x >= self.width {
+            return;
+        }
+        self.rows[pos.y].set(pos.x, value);
+    }
+
+    fn get_coordinate_by_direction(
+        &self,
+        pos: GridCoordinate,
+        direction: Direction,
+    ) -> Option<GridCoordinate> {
+        return coordinate_by_direction(self.width, self.height, pos, direction);
+    }
+}
+
+impl GridRotation for BitGrid {
+    type Item = bool;
+
+    fn rotate_clockwise(&mut self) {
+        // Old column x, read bottom to top, becomes new row x.
+        let rows = (0..self.width)
+            .map(|x| self.get_column(x).reverse())
+            .collect();
+        *self = BitGrid {
+            width: self.height,
+            height: self.width,
+            rows: rows,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_char_grid;
+    use std::collections::HashSet;
+
+    fn line_from_str(s: &str) -> BitLine {
+        let mut line = BitLine::new(s.len());
+        for (i, c) in s.chars().enumerate() {
+            line.set(i, c == '#');
+        }
+        return line;
+    }
+
+    fn grid_from_strs(strs: &Vec<&str>) -> BitGrid {
+        return BitGrid::from_grid(&parse_char_grid(strs.to_vec()), |c| c == '#');
+    }
+
+    #[test]
+    fn test_line_get_set() {
+        let mut line = BitLine::new(130);
+        line.set(0, true);
+        line.set(64, true);
+        line.set(129, true);
+        assert!(line.get(0));
+        assert!(!line.get(1));
+        assert!(line.get(64));
+        assert!(line.get(129));
+        assert_eq!(line.count_ones(), 3);
+        line.set(64, false);
+        assert_eq!(line.iter_ones().collect::<Vec<usize>>(), vec![0, 129]);
+    }
+
+    #[test]
+    fn test_line_shift() {
+        let line = line_from_str("#..#.#");
+        assert_eq!(line.shift_left(1), line_from_str("..#.#."));
+        assert_eq!(line.shift_right(1), line_from_str(".#..#."));
+        assert_eq!(line.shift_right(6), BitLine::new(6));
+    }
+
+    #[test]
+    fn test_line_shift_across_words() {
+        let mut line = BitLine::new(150);
+        line.set(10, true);
+        line.set(63, true);
+        line.set(149, true);
+
+        let right = line.shift_right(70);
+        assert_eq!(right.iter_ones().collect::<Vec<usize>>(), vec![80, 133]);
+        let left = line.shift_left(60);
+        assert_eq!(left.iter_ones().collect::<Vec<usize>>(), vec![3, 89]);
+        // Shifting off the end must not leave bits past the length.
+        assert_eq!(line.shift_right(1).count_ones(), 2);
+    }
+
+    #[test]
+    fn test_line_ranges() {
+        let mut line = BitLine::new(200);
+        line.set_range(60, 140, true);
+        assert_eq!(line.count_ones(), 80);
+        assert_eq!(line.count_ones_in(0, 200), 80);
+        assert_eq!(line.count_ones_in(0, 61), 1);
+        assert_eq!(line.count_ones_in(64, 128), 64);
+        assert_eq!(line.count_ones_in(139, 139), 0);
+        line.set_range(62, 138, false);
+        assert_eq!(
+            line.iter_ones().collect::<Vec<usize>>(),
+            vec![60, 61, 138, 139]
+        );
+    }
+
+    #[test]
+    fn test_line_ops() {
+        let a = line_from_str("##..");
+        let b = line_from_str(".#.#");
+        assert_eq!(&a & &b, line_from_str(".#.."));
+        assert_eq!(&a | &b, line_from_str("##.#"));
+        assert_eq!(&a ^ &b, line_from_str("#..#"));
+        assert_eq!(a.invert(), line_from_str("..##"));
+        assert_eq!(a.invert().count_ones(), 2);
+        assert_eq!(b.reverse(), line_from_str("#.#."));
+
+        let mut c = a.clone();
+        c |= &b;
+        assert_eq!(c, line_from_str("##.#"));
+        c &= &a;
+        assert_eq!(c, a);
+        c ^= &b;
+        assert_eq!(c, line_from_str("#..#"));
+    }
+
+    #[test]
+    fn test_from_grid() {
+        let grid = grid_from_strs(&vec!["#..", ".##"]);
+        assert_eq!(grid.get_width(), 3);
+        assert_eq!(grid.get_height(), 2);
+        assert_eq!(grid.count_ones(), 3);
+        assert_eq!(grid.get_value(GridCoordinate::new(0, 0)), Some(true));
+        assert_eq!(grid.get_value(GridCoordinate::new(0, 1)), Some(false));
+        assert_eq!(grid.get_value(GridCoordinate::new(3, 1)), None);
+        assert_eq!(grid.get_row(1), &line_from_str(".##"));
+        assert_eq!(grid.get_column(1), line_from_str(".#"));
+        assert_eq!(
+            grid.to_grid().data_copy(),
+            vec![true, false, false, false, true, true]
+        );
+        assert_eq!(
+            grid.iter_ones().collect::<Vec<GridCoordinate>>(),
+            vec![
+                GridCoordinate::new(0, 0),
+                GridCoordinate::new(1, 1),
+                GridCoordinate::new(2, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shift_grid() {
+        let grid = grid_from_strs(&vec!["#..", ".#.", "..#"]);
+        assert_eq!(
+            grid.shift(Direction::NORTH),
+            grid_from_strs(&vec![".#.", "..#", "..."])
+        );
+        assert_eq!(
+            grid.shift(Direction::SOUTH),
+            grid_from_strs(&vec!["...", "#..", ".#."])
+        );
+        assert_eq!(
+            grid.shift(Direction::EAST),
+            grid_from_strs(&vec![".#.", "..#", "..."])
+        );
+        assert_eq!(
+            grid.shift(Direction::SOUTHWEST),
+            grid_from_strs(&vec!["...", "...", "#.."])
+        );
+    }
+
+    #[test]
+    fn test_shift_no_rows() {
+        let grid = BitGrid::new(3, 0);
+        for direction in [Direction::NORTH, Direction::SOUTH, Direction::NORTHEAST] {
+            assert_eq!(grid.shift(direction), grid);
+        }
+    }
+
+    #[test]
+    fn test_grid_ops() {
+        let a = grid_from_strs(&vec!["##", ".."]);
+        let b = grid_from_strs(&vec!["#.", "#."]);
+        assert_eq!(&a & &b, grid_from_strs(&vec!["#.", ".."]));
+        assert_eq!(&a | &b, grid_from_strs(&vec!["##", "#."]));
+        assert_eq!(&a ^ &b, grid_from_strs(&vec![".#", "#."]));
+        assert_eq!(a.invert(), grid_from_strs(&vec!["..", "##"]));
+
+        let mut cleared = a.clone();
+        cleared.clear();
+        assert_eq!(cleared, BitGrid::new(2, 2));
+    }
+
+    #[test]
+    fn test_rotate_matches_grid() {
+        let strs = vec!["#..#", ".##.", "#..."];
+        let values: Vec<char> = strs.iter().flat_map(|s| s.chars()).collect();
+        let mut grid = Grid::new(4, 3, values);
+        let mut bits = grid_from_strs(&strs);
+        for _ in 0..4 {
+            grid.rotate_clockwise();
+            bits.rotate_clockwise();
+            assert_eq!(bits, BitGrid::from_grid(&grid, |c| c == '#'));
+        }
+        assert_eq!(bits, grid_from_strs(&strs));
+    }
+
+    #[test]
+    fn test_transpose() {
+        let grid = grid_from_strs(&vec!["#..", ".##"]);
+        assert_eq!(grid.transpose(), grid_from_strs(&vec!["#.", ".#", ".#"]));
+    }
+
+    #[test]
+    fn test_hash() {
+        let mut seen = HashSet::new();
+        seen.insert(grid_from_strs(&vec!["#.", ".#"]));
+        assert!(seen.contains(&grid_from_strs(&vec!["#.", ".#"])));
+        assert!(!seen.contains(&grid_from_strs(&vec![".#", "#."])));
+    }
+}
@@ -0,0 +1,260 @@
+use crate::direction::Direction;
+use crate::grid::{Grid, GridTraversable};
+use crate::gridcoord::GridCoordinate;
+
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Directed;
+use std::collections::HashMap;
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::NORTH,
+    Direction::EAST,
+    Direction::SOUTH,
+    Direction::WEST,
+];
+
+/// One way along a corridor between two nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorridorEdge {
+    pub from: usize,
+    pub to: usize,
+    /// Steps from one node to the other, one more than the cells between them.
+    pub length: usize,
+    /// Cells walked through in order, not counting either node.
+    pub cells: Vec<GridCoordinate>,
+}
+
+/// A grid of passable cells squashed down to the cells where paths branch, end, or were
+/// asked to be kept, with an edge for every corridor between two of them.
+///
+/// A loop with no branches on it gets one of its cells as a node, so nothing is lost.
+#[derive(Debug, Clone)]
+pub struct CorridorGraph {
+    nodes: Vec<GridCoordinate>,
+    node_index: HashMap<GridCoordinate, usize>,
+    edges: Vec<CorridorEdge>,
+    outgoing: Vec<Vec<usize>>,
+}
+
+/// Compress a grid where every corridor can be walked both ways. Cells in keep become
+/// nodes first, in that order.
+/// ```
+/// use gridlib::{compress_grid, Grid, GridCoordinate};
+/// // #.#
+/// // ...
+/// // #.#
+/// let grid = Grid::new(3, 3, "#.#...#.#".chars().collect());
+/// let graph = compress_grid(&grid, |c| c == '.', &vec![]);
+/// // The four ends and the middle, in reading order.
+/// assert_eq!(graph.get_nodes().len(), 5);
+/// assert_eq!(graph.node_index(GridCoordinate::new(1, 1)), Some(2));
+/// assert_eq!(graph.get_edges().len(), 8);
+/// ```
+pub fn compress_grid<T: Copy, P: Fn(T) -> bool>(
+    grid: &Grid<T>,
+    passable: P,
+    keep: &Vec<GridCoordinate>,
+) -> CorridorGraph {
+    return compress_grid_directed(grid, passable, |_, _| true, keep);
+}
+
+/// Compress a grid where can_leave says whether a cell with that value may be left in a
+/// direction, like a slope you can only walk down. A corridor is only an edge if every
+/// step along it is allowed.
+pub fn compress_grid_directed<T: Copy, P: Fn(T) -> bool, L: Fn(T, Direction) -> bool>(
+    grid: &Grid<T>,
+    passable: P,
+    can_leave: L,
+    keep: &Vec<GridCoordinate>,
+) -> CorridorGraph {
+    let is_open = |c: GridCoordinate| passable(grid.get_value(c).unwrap());
+    let open_neighbours = |c: GridCoordinate| -> Vec<(Direction, GridCoordinate)> {
+        return DIRECTIONS
+            .iter()
+            .filter_map(|d| grid.get_coordinate_by_direction(c, *d).map(|n| (*d, n)))
+            .filter(|(_, n)| is_open(*n))
+            .collect();
+    };
+
+    let mut graph = CorridorGraph {
+        nodes: vec![],
+        node_index: HashMap::new(),
+        edges: vec![],
+        outgoing: vec![],
+    };
+    for c in keep {
+        graph.add_node(*c);
+    }
+    for c in grid.coord_iter() {
+        if is_open(c) && open_neighbours(c).len() != 2 {
+            graph.add_node(c);
+        }
+    }
+
+    let width = grid.get_width();
+    let mut walked = vec![false; width * grid.get_height()];
+    let mut next_node = 0;
+    loop {
+        while next_node < graph.nodes.len() {
+            let from = next_node;
+            let start = graph.nodes[from];
+            walked[start.y * width + start.x] = true;
+            for (first_dir, first) in open_neighbours(start) {
+                let mut allowed = can_leave(grid.get_value(start).unwrap(), first_dir);
+                let mut previous = start;
+                let mut current = first;
+                let mut cells = vec![];
+                // Walk to the end even when a step isn't allowed, so every cell is seen.
+                let to = loop {
+                    if let Some(to) = graph.node_index(current) {
+                        break Some(to);
+                    }
+                    walked[current.y * width + current.x] = true;
+                    cells.push(current);
+                    let onward: Vec<(Direction, GridCoordinate)> = open_neighbours(current)
+                        .into_iter()
+                        .filter(|(_, n)| *n != previous)
+                        .collect();
+                    if onward.len() != 1 {
+                        // Not a node so it has exactly two ways, this is just a guard.
+                        break None;
+                    }
+                    let (dir, next) = onward[0];
+                    allowed = allowed && can_leave(grid.get_value(current).unwrap(), dir);
+                    previous = current;
+                    current = next;
+                };
+                if let (true, Some(to)) = (allowed, to) {
+                    graph.add_edge(CorridorEdge {
+                        from: from,
+                        to: to,
+                        length: cells.len() + 1,
+                        cells: cells,
+                    });
+                }
+            }
+            next_node += 1;
+        }
+
+        // Anything open not walked yet is a loop without branches.
+        let unwalked = grid
+            .coord_iter()
+            .find(|c| is_open(*c) && !walked[c.y * width + c.x]);
+        match unwalked {
+            Some(c) => graph.add_node(c),
+            None => break,
+        }
+    }
+    return graph;
+}
+
+impl CorridorGraph {
+    fn add_node(&mut self, coord: GridCoordinate) {
+        if self.node_index.contains_key(&coord) {
+            return;
+        }
+        self.node_index.insert(coord, self.nodes.len());
+        self.nodes.push(coord);
+        self.outgoing.push(vec![]);
+    }
+
+    fn add_edge(&mut self, edge: CorridorEdge) {
+        self.outgoing[edge.from].push(self.edges.len());
+        self.edges.push(edge);
+    }
+
+    pub fn get_nodes(&self) -> &Vec<GridCoordinate> {
+        return &self.nodes;
+    }
+
+    pub fn node_index(&self, coord: GridCoordinate) -> Option<usize> {
+        return self.node_index.get(&coord).copied();
+    }
+
+    pub fn get_edges(&self) -> &Vec<CorridorEdge> {
+        return &self.edges;
+    }
+
+    /// Edges leaving node.
+    pub fn edges_from(&self, node: usize) -> impl Iterator<Item = &CorridorEdge> + '_ {
+        return self.outgoing[node].iter().map(|e| &self.edges[*e]);
+    }
+
+    /// As a petgraph Graph weighted by corridor length. NodeIndex n is node n here.
+    pub fn to_graph(&self) -> Graph<GridCoordinate, usize, Directed> {
+        let mut graph = Graph::with_capacity(self.nodes.len(), self.edges.len());
+        for node in self.nodes.iter() {
+            graph.add_node(*node);
+        }
+        for edge in self.edges.iter() {
+            graph.add_edge(
+                NodeIndex::new(edge.from),
+                NodeIndex::new(edge.to),
+                edge.length,
+            );
+        }
+        return graph;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_char_grid;
+
+    #[test]
+    fn test_corridor_cells() {
+        let grid = parse_char_grid(vec!["#.###", "#...#", "###.#"]);
+        let graph = compress_grid(&grid, |c| c == '.', &vec![]);
+        assert_eq!(
+            graph.get_nodes(),
+            &vec![GridCoordinate::new(1, 0), GridCoordinate::new(3, 2)]
+        );
+        let edge = graph.edges_from(0).next().unwrap();
+        assert_eq!(edge.to, 1);
+        assert_eq!(edge.length, 4);
+        assert_eq!(
+            edge.cells,
+            vec![
+                GridCoordinate::new(1, 1),
+                GridCoordinate::new(2, 1),
+                GridCoordinate::new(3, 1)
+            ]
+        );
+        assert_eq!(graph.edges_from(1).next().unwrap().to, 0);
+    }
+
+    #[test]
+    fn test_directed_slope() {
+        let grid = parse_char_grid(vec!["#.###", "#.>.#", "###.#"]);
+        let graph = compress_grid_directed(
+            &grid,
+            |c| c != '#',
+            |c, d| c != '>' || d == Direction::EAST,
+            &vec![],
+        );
+        assert_eq!(graph.get_edges().len(), 1);
+        assert_eq!(graph.get_edges()[0].from, 0);
+    }
+
+    #[test]
+    fn test_plain_loop() {
+        let grid = parse_char_grid(vec!["...", ".#.", "..."]);
+        let graph = compress_grid(&grid, |c| c == '.', &vec![]);
+        assert_eq!(graph.get_nodes(), &vec![GridCoordinate::new(0, 0)]);
+        // Around the loop each way.
+        assert_eq!(graph.get_edges().len(), 2);
+        assert!(graph.get_edges().iter().all(|e| e.to == 0 && e.length == 8));
+    }
+
+    #[test]
+    fn test_to_graph() {
+        let grid = parse_char_grid(vec!["#.#", "...", "#.#"]);
+        let graph = compress_grid(&grid, |c| c == '.', &vec![GridCoordinate::new(1, 0)]);
+        let petgraph = graph.to_graph();
+        assert_eq!(petgraph.node_count(), 5);
+        assert_eq!(petgraph.edge_count(), 8);
+        assert_eq!(petgraph[NodeIndex::new(0)], GridCoordinate::new(1, 0));
+        assert!(petgraph.edge_weights().all(|w| *w == 1));
+    }
+}
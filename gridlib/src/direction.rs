@@ -12,6 +12,22 @@ pub enum Direction {
     NORTHWEST,
 }
 
+impl Direction {
+    /// Pointing the other way.
+    pub fn opposite(&self) -> Direction {
+        return match self {
+            Direction::NORTH => Direction::SOUTH,
+            Direction::EAST => Direction::WEST,
+            Direction::SOUTH => Direction::NORTH,
+            Direction::WEST => Direction::EAST,
+            Direction::NORTHEAST => Direction::SOUTHWEST,
+            Direction::SOUTHEAST => Direction::NORTHWEST,
+            Direction::SOUTHWEST => Direction::NORTHEAST,
+            Direction::NORTHWEST => Direction::SOUTHEAST,
+        };
+    }
+}
+
 impl Display for Direction {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -43,4 +59,12 @@ mod tests {
         assert_eq!(format!("{}", Direction::SOUTHEAST), "SOUTHEAST");
         assert_eq!(format!("{}", Direction::SOUTHWEST), "SOUTHWEST");
     }
+
+    #[test]
+    fn test_opposite() {
+        assert_eq!(Direction::NORTH.opposite(), Direction::SOUTH);
+        assert_eq!(Direction::WEST.opposite(), Direction::EAST);
+        assert_eq!(Direction::SOUTHWEST.opposite(), Direction::NORTHEAST);
+        assert_eq!(Direction::EAST.opposite().opposite(), Direction::EAST);
+    }
 }
@@ -104,70 +104,6 @@ pub trait GridTraversable {
         pos: GridCoordinate,
         direction: Direction,
     ) -> Option<GridCoordinate>;
-    fn get_adjacent_coordinates(&self, pos: GridCoordinate) -> Vec<GridCoordinate>;
-    fn get_diag_adjacent_coordinates(&self, pos: GridCoordinate) -> Vec<GridCoordinate>;
-}
-
-impl<T: Copy> GridTraversable for Grid<T> {
-    type Item = T;
-
-    fn get_value(&self, pos: GridCoordinate) -> Option<Self::Item> {
-        if pos.y >= self.height || pos.x >= self.width {
-            // y cannot exceed height, x cannot exceed width
-            return None;
-        }
-        let pos: usize = pos.x + pos.y * self.width;
-        return Some(*(self.values.iter().nth(pos)?));
-    }
-
-    fn set_value(&mut self, pos: GridCoordinate, value: Self::Item) {
-        if pos.y >= self.height || pos.x >= self.width {
-            // y cannot exceed height, x cannot exceed width
-            return;
-        }
-        let pos: usize = pos.x + pos.y * self.width;
-        self.values[pos] = value;
-    }
-
-    fn get_coordinate_by_direction(
-        &self,
-        pos: GridCoordinate,
-        direction: Direction,
-    ) -> Option<GridCoordinate> {
-        let mut possible_y: Option<usize> = Some(pos.y);
-        let mut possible_x: Option<usize> = Some(pos.x);
-        match direction {
-            Direction::NORTH => possible_y = pos.y.checked_sub(1),
-            Direction::EAST => possible_x = pos.x.checked_add(1),
-            Direction::SOUTH => possible_y = pos.y.checked_add(1),
-            Direction::WEST => possible_x = pos.x.checked_sub(1),
-            Direction::NORTHEAST => {
-                possible_x = pos.x.checked_add(1);
-                possible_y = pos.y.checked_sub(1);
-            }
-            Direction::SOUTHEAST => {
-                possible_x = pos.x.checked_add(1);
-                possible_y = pos.y.checked_add(1);
-            }
-            Direction::SOUTHWEST => {
-                possible_x = pos.x.checked_sub(1);
-                possible_y = pos.y.checked_add(1);
-            }
-            Direction::NORTHWEST => {
-                possible_x = pos.x.checked_sub(1);
-                possible_y = pos.y.checked_sub(1);
-            }
-        }
-        if let Some(new_x) = possible_x {
-            if let Some(new_y) = possible_y {
-                if new_x > self.width - 1 || new_y > self.height - 1 {
-                    return None;
-                }
-                return Some(GridCoordinate::new(new_x, new_y));
-            }
-        }
-        return None;
-    }
 
     fn get_adjacent_coordinates(&self, pos: GridCoordinate) -> Vec<GridCoordinate> {
         let opt_north = self.get_coordinate_by_direction(pos, Direction::NORTH);
@@ -209,6 +145,78 @@ impl<T: Copy> GridTraversable for Grid<T> {
     }
 }
 
+/// Step from pos in direction, None if that leaves a width x height grid.
+pub(crate) fn coordinate_by_direction(
+    width: usize,
+    height: usize,
+    pos: GridCoordinate,
+    direction: Direction,
+) -> Option<GridCoordinate> {
+    let mut possible_y: Option<usize> = Some(pos.y);
+    let mut possible_x: Option<usize> = Some(pos.x);
+    match direction {
+        Direction::NORTH => possible_y = pos.y.checked_sub(1),
+        Direction::EAST => possible_x = pos.x.checked_add(1),
+        Direction::SOUTH => possible_y = pos.y.checked_add(1),
+        Direction::WEST => possible_x = pos.x.checked_sub(1),
+        Direction::NORTHEAST => {
+            possible_x = pos.x.checked_add(1);
+            possible_y = pos.y.checked_sub(1);
+        }
+        Direction::SOUTHEAST => {
+            possible_x = pos.x.checked_add(1);
+            possible_y = pos.y.checked_add(1);
+        }
+        Direction::SOUTHWEST => {
+            possible_x = pos.x.checked_sub(1);
+            possible_y = pos.y.checked_add(1);
+        }
+        Direction::NORTHWEST => {
+            possible_x = pos.x.checked_sub(1);
+            possible_y = pos.y.checked_sub(1);
+        }
+    }
+    if let Some(new_x) = possible_x {
+        if let Some(new_y) = possible_y {
+            if new_x > width - 1 || new_y > height - 1 {
+                return None;
+            }
+            return Some(GridCoordinate::new(new_x, new_y));
+        }
+    }
+    return None;
+}
+
+impl<T: Copy> GridTraversable for Grid<T> {
+    type Item = T;
+
+    fn get_value(&self, pos: GridCoordinate) -> Option<Self::Item> {
+        if pos.y >= self.height || pos.x >= self.width {
+            // y cannot exceed height, x cannot exceed width
+            return None;
+        }
+        let pos: usize = pos.x + pos.y * self.width;
+        return Some(*(self.values.iter().nth(pos)?));
+    }
+
+    fn set_value(&mut self, pos: GridCoordinate, value: Self::Item) {
+        if pos.y >= self.height || pos.x >= self.width {
+            // y cannot exceed height, x cannot exceed width
+            return;
+        }
+        let pos: usize = pos.x + pos.y * self.width;
+        self.values[pos] = value;
+    }
+
+    fn get_coordinate_by_direction(
+        &self,
+        pos: GridCoordinate,
+        direction: Direction,
+    ) -> Option<GridCoordinate> {
+        return coordinate_by_direction(self.width, self.height, pos, direction);
+    }
+}
+
 pub trait GridRotation {
     type Item;
     fn rotate_clockwise(&mut self);
@@ -11,4 +11,54 @@ pub use crate::direction::Direction;
 mod grid;
 
 pub use crate::grid::Grid;
+pub use crate::grid::GridRotation;
 pub use crate::grid::GridTraversable;
+
+mod bitgrid;
+
+pub use crate::bitgrid::BitGrid;
+pub use crate::bitgrid::BitLine;
+
+mod corridor;
+
+pub use crate::corridor::compress_grid;
+pub use crate::corridor::compress_grid_directed;
+pub use crate::corridor::CorridorEdge;
+pub use crate::corridor::CorridorGraph;
+
+mod pipes;
+
+pub use crate::pipes::TileTable;
+
+mod region;
+
+pub use crate::region::fill_from_outside;
+pub use crate::region::fill_from_outside_upscaled;
+pub use crate::region::flood_fill;
+pub use crate::region::label_components;
+pub use crate::region::ComponentLabels;
+pub use crate::region::Connectivity;
+pub use crate::region::Region;
+
+mod scanner;
+
+pub use crate::scanner::scan_spans;
+pub use crate::scanner::Orientation;
+pub use crate::scanner::Span;
+
+mod turtle;
+
+pub use crate::turtle::parse_arrows;
+pub use crate::turtle::parse_direction;
+pub use crate::turtle::parse_hex_step;
+pub use crate::turtle::parse_step;
+pub use crate::turtle::PathParseError;
+pub use crate::turtle::PathStep;
+pub use crate::turtle::TurtlePath;
+
+// A grid of characters from its rows, for tests.
+#[cfg(test)]
+pub(crate) fn parse_char_grid(lines: Vec<&str>) -> Grid<char> {
+    let values = lines.iter().flat_map(|l| l.chars()).collect();
+    return Grid::new(lines[0].len(), lines.len(), values);
+}
@@ -0,0 +1,254 @@
+use crate::direction::Direction;
+use crate::grid::{Grid, GridTraversable};
+use crate::gridcoord::GridCoordinate;
+
+use std::collections::HashMap;
+
+const SIDES: [Direction; 4] = [
+    Direction::NORTH,
+    Direction::EAST,
+    Direction::SOUTH,
+    Direction::WEST,
+];
+
+/// Which sides each kind of tile joins up with, for pipe style grids of characters.
+///
+/// Characters that aren't registered connect to nothing, which is how a start tile
+/// like `S` is left to be inferred.
+#[derive(Debug, Clone)]
+pub struct TileTable {
+    connections: HashMap<char, Vec<Direction>>,
+}
+
+impl Default for TileTable {
+    fn default() -> Self {
+        return TileTable::new();
+    }
+}
+
+impl TileTable {
+    /// No tiles at all.
+    pub fn new() -> TileTable {
+        return TileTable {
+            connections: HashMap::new(),
+        };
+    }
+
+    /// `|`, `-`, `L`, `J`, `7` and `F` pipes, `.` for ground.
+    pub fn pipes() -> TileTable {
+        let mut table = TileTable::new();
+        table.register('|', &[Direction::NORTH, Direction::SOUTH]);
+        table.register('-', &[Direction::EAST, Direction::WEST]);
+        table.register('L', &[Direction::NORTH, Direction::EAST]);
+        table.register('J', &[Direction::NORTH, Direction::WEST]);
+        table.register('7', &[Direction::SOUTH, Direction::WEST]);
+        table.register('F', &[Direction::EAST, Direction::SOUTH]);
+        table.register('.', &[]);
+        return table;
+    }
+
+    /// Add or replace a tile.
+    pub fn register(&mut self, tile: char, directions: &[Direction]) {
+        self.connections.insert(tile, directions.to_vec());
+    }
+
+    /// The sides tile joins, None if it isn't registered.
+    pub fn connections(&self, tile: char) -> Option<&Vec<Direction>> {
+        return self.connections.get(&tile);
+    }
+
+    pub fn connects(&self, tile: char, direction: Direction) -> bool {
+        return match self.connections(tile) {
+            Some(c) => c.contains(&direction),
+            None => false,
+        };
+    }
+
+    /// Whether sub cell x, y of tile is pipe once the tile is blown up three by three,
+    /// for fill_from_outside_upscaled. The middle is, and so is the middle of each side it
+    /// joins. Tiles joining nothing, or not registered, are all open.
+    /// ```
+    /// use gridlib::TileTable;
+    /// let table = TileTable::pipes();
+    /// assert!(table.blocks_subcell('L', 1, 0));
+    /// assert!(!table.blocks_subcell('L', 1, 2));
+    /// assert!(!table.blocks_subcell('.', 1, 1));
+    /// ```
+    pub fn blocks_subcell(&self, tile: char, x: usize, y: usize) -> bool {
+        let joins_something = match self.connections(tile) {
+            Some(c) => !c.is_empty(),
+            None => false,
+        };
+        if !joins_something {
+            return false;
+        }
+        return match (x, y) {
+            (1, 1) => true,
+            (1, 0) => self.connects(tile, Direction::NORTH),
+            (2, 1) => self.connects(tile, Direction::EAST),
+            (1, 2) => self.connects(tile, Direction::SOUTH),
+            (0, 1) => self.connects(tile, Direction::WEST),
+            _ => false,
+        };
+    }
+
+    /// The tile joining exactly these sides, in any order. If more than one does, the
+    /// lowest character wins.
+    /// ```
+    /// use gridlib::{Direction, TileTable};
+    /// let table = TileTable::pipes();
+    /// assert_eq!(table.tile_connecting(&[Direction::SOUTH, Direction::EAST]), Some('F'));
+    /// assert_eq!(table.tile_connecting(&[Direction::NORTH]), None);
+    /// ```
+    pub fn tile_connecting(&self, directions: &[Direction]) -> Option<char> {
+        return self
+            .connections
+            .iter()
+            .filter(|(_, c)| {
+                return c.len() == directions.len() && directions.iter().all(|d| c.contains(d));
+            })
+            .map(|(tile, _)| *tile)
+            .min();
+    }
+
+    /// What the tile at pos must be for it to close a loop. Every two sided tile whose
+    /// sides all have a neighbour pointing back is tried, lowest character first, so a
+    /// stray pipe pointing at pos from off the loop doesn't get in the way.
+    /// ```
+    /// use gridlib::{Grid, GridCoordinate, TileTable};
+    /// // .....
+    /// // .S-7.
+    /// // .|.|.
+    /// // .L-J.
+    /// let grid = Grid::new(5, 4, "......S-7..|.|..L-J.".chars().collect());
+    /// let table = TileTable::pipes();
+    /// assert_eq!(table.infer_tile(&grid, GridCoordinate::new(1, 1)), Some('F'));
+    /// ```
+    pub fn infer_tile(&self, grid: &Grid<char>, pos: GridCoordinate) -> Option<char> {
+        let pointing: Vec<Direction> = SIDES
+            .iter()
+            .filter(|d| match grid.get_coordinate_by_direction(pos, **d) {
+                Some(n) => self.connects(grid.get_value(n).unwrap(), d.opposite()),
+                None => false,
+            })
+            .copied()
+            .collect();
+        let mut candidates: Vec<char> = self
+            .connections
+            .iter()
+            .filter(|(_, c)| c.len() == 2 && c.iter().all(|d| pointing.contains(d)))
+            .map(|(tile, _)| *tile)
+            .collect();
+        candidates.sort();
+        return candidates
+            .into_iter()
+            .find(|tile| self.walk_loop(grid, pos, &self.connections[tile]).is_some());
+    }
+
+    /// The loop running through start, in order, beginning with start. A start tile that
+    /// isn't registered is inferred. None if it doesn't close, or runs into a tile that
+    /// doesn't have exactly two sides.
+    pub fn extract_loop(
+        &self,
+        grid: &Grid<char>,
+        start: GridCoordinate,
+    ) -> Option<Vec<GridCoordinate>> {
+        let start_tile = match self.connections(grid.get_value(start).unwrap()) {
+            Some(_) => grid.get_value(start).unwrap(),
+            None => self.infer_tile(grid, start)?,
+        };
+        return self.walk_loop(grid, start, self.connections(start_tile).unwrap());
+    }
+
+    // Follow the pipes out of start, treating it as joining start_connections.
+    fn walk_loop(
+        &self,
+        grid: &Grid<char>,
+        start: GridCoordinate,
+        start_connections: &[Direction],
+    ) -> Option<Vec<GridCoordinate>> {
+        if start_connections.len() != 2 {
+            return None;
+        }
+
+        let mut path = vec![start];
+        let mut pos = start;
+        let mut direction = start_connections[0];
+        loop {
+            let next = grid.get_coordinate_by_direction(pos, direction)?;
+            let arrived_from = direction.opposite();
+            if next == start {
+                if !start_connections.contains(&arrived_from) {
+                    return None;
+                }
+                return Some(path);
+            }
+            let connections = self.connections(grid.get_value(next).unwrap())?;
+            if connections.len() != 2 || !connections.contains(&arrived_from) {
+                return None;
+            }
+            direction = *connections.iter().find(|d| **d != arrived_from).unwrap();
+            path.push(next);
+            pos = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_char_grid;
+
+    #[test]
+    fn test_extract_loop() {
+        let grid = parse_char_grid(vec!["7-F7-", ".FJ|7", "SJLL7", "|F--J", "LJ.LJ"]);
+        let table = TileTable::pipes();
+        let start = GridCoordinate::new(0, 2);
+        assert_eq!(table.infer_tile(&grid, start), Some('F'));
+        let path = table.extract_loop(&grid, start).unwrap();
+        assert_eq!(path.len(), 16);
+        assert_eq!(path[0], start);
+        // F goes east before south.
+        assert_eq!(path[1], GridCoordinate::new(1, 2));
+        assert_eq!(path[15], GridCoordinate::new(0, 3));
+    }
+
+    #[test]
+    fn test_broken_loop() {
+        let grid = parse_char_grid(vec![".....", ".S-7.", ".|.|.", ".L-..", "....."]);
+        let table = TileTable::pipes();
+        assert_eq!(table.extract_loop(&grid, GridCoordinate::new(1, 1)), None);
+    }
+
+    #[test]
+    fn test_decoy_next_to_start() {
+        // The - west of S points at it without being on the loop.
+        let grid = parse_char_grid(vec![".....", "-S-7.", ".|.|.", ".L-J.", "....."]);
+        let table = TileTable::pipes();
+        let start = GridCoordinate::new(1, 1);
+        assert_eq!(table.infer_tile(&grid, start), Some('F'));
+        assert_eq!(table.extract_loop(&grid, start).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn test_custom_tiles() {
+        // Arrows for pipes, and a start already given.
+        let mut table = TileTable::new();
+        table.register('>', &[Direction::EAST, Direction::WEST]);
+        table.register('^', &[Direction::NORTH, Direction::SOUTH]);
+        table.register('a', &[Direction::EAST, Direction::SOUTH]);
+        table.register('b', &[Direction::SOUTH, Direction::WEST]);
+        table.register('c', &[Direction::NORTH, Direction::EAST]);
+        table.register('d', &[Direction::NORTH, Direction::WEST]);
+        let grid = parse_char_grid(vec!["a>b", "^.^", "c>d"]);
+        let path = table
+            .extract_loop(&grid, GridCoordinate::new(0, 0))
+            .unwrap();
+        assert_eq!(path.len(), 8);
+        assert_eq!(
+            table.tile_connecting(&[Direction::WEST, Direction::NORTH]),
+            Some('d')
+        );
+        assert!(!table.connects('?', Direction::NORTH));
+    }
+}
@@ -0,0 +1,323 @@
+use crate::bitgrid::BitGrid;
+use crate::direction::Direction;
+use crate::grid::{Grid, GridTraversable};
+use crate::gridcoord::GridCoordinate;
+
+use std::collections::VecDeque;
+
+/// Which neighbours count as touching.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Connectivity {
+    /// North, east, south and west.
+    Four,
+    /// The four sides and the four diagonals.
+    Eight,
+}
+
+impl Connectivity {
+    pub(crate) fn neighbours<G: GridTraversable>(
+        &self,
+        grid: &G,
+        pos: GridCoordinate,
+    ) -> Vec<GridCoordinate> {
+        let mut result = grid.get_adjacent_coordinates(pos);
+        if *self == Connectivity::Eight {
+            result.extend(grid.get_diag_adjacent_coordinates(pos));
+        }
+        return result;
+    }
+}
+
+/// Every cell reachable from seed through cells whose value matches, seed included.
+/// Empty if the seed itself doesn't match.
+/// ```
+/// use gridlib::{flood_fill, Connectivity, Grid, GridCoordinate};
+/// // .#.
+/// // #..
+/// // .#.
+/// let grid = Grid::new(3, 3, ".#.#...#.".chars().collect());
+/// let fill = flood_fill(&grid, GridCoordinate::new(2, 0), Connectivity::Four, |c| c == '.');
+/// assert_eq!(fill.count_ones(), 4);
+/// let fill = flood_fill(&grid, GridCoordinate::new(2, 0), Connectivity::Eight, |c| c == '.');
+/// assert_eq!(fill.count_ones(), 6);
+/// ```
+pub fn flood_fill<T: Copy, P: Fn(T) -> bool>(
+    grid: &Grid<T>,
+    seed: GridCoordinate,
+    connectivity: Connectivity,
+    matches: P,
+) -> BitGrid {
+    let mut filled = BitGrid::new(grid.get_width(), grid.get_height());
+    if !matches(grid.get_value(seed).unwrap()) {
+        return filled;
+    }
+    filled.set_value(seed, true);
+    let mut queue = VecDeque::from([seed]);
+    while let Some(pos) = queue.pop_front() {
+        for next in connectivity.neighbours(grid, pos) {
+            if !filled.get_value(next).unwrap() && matches(grid.get_value(next).unwrap()) {
+                filled.set_value(next, true);
+                queue.push_back(next);
+            }
+        }
+    }
+    return filled;
+}
+
+/// One connected region found by label_components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Number of cells.
+    pub size: usize,
+    /// Top left corner of the bounding box.
+    pub min: GridCoordinate,
+    /// Bottom right corner of the bounding box, inclusive.
+    pub max: GridCoordinate,
+    /// Cell sides facing something outside the region, the grid edge included.
+    pub perimeter: usize,
+}
+
+/// Every cell of a grid labelled with the region it belongs to.
+#[derive(Debug, Clone)]
+pub struct ComponentLabels {
+    labels: Grid<usize>,
+    regions: Vec<Region>,
+}
+
+impl ComponentLabels {
+    /// Label of the region at pos, regions are numbered in reading order of their first cell.
+    pub fn get_label(&self, pos: GridCoordinate) -> usize {
+        return self.labels.get_value(pos).unwrap();
+    }
+
+    pub fn get_regions(&self) -> &Vec<Region> {
+        return &self.regions;
+    }
+
+    pub fn get_region(&self, label: usize) -> &Region {
+        return &self.regions[label];
+    }
+
+    /// The cells of one region.
+    pub fn to_bitgrid(&self, label: usize) -> BitGrid {
+        return BitGrid::from_grid(&self.labels, |l| l == label);
+    }
+}
+
+/// Split the whole grid into regions, neighbours join the same region when same_region
+/// says so for their two values.
+/// ```
+/// use gridlib::{label_components, Connectivity, Grid, GridCoordinate};
+/// // AAB
+/// // ABB
+/// // CCB
+/// let grid = Grid::new(3, 3, "AABABBCCB".chars().collect());
+/// let labels = label_components(&grid, Connectivity::Four, |a, b| a == b);
+/// assert_eq!(labels.get_regions().len(), 3);
+/// let b = labels.get_region(labels.get_label(GridCoordinate::new(2, 0)));
+/// assert_eq!(b.size, 4);
+/// assert_eq!(b.perimeter, 10);
+/// assert_eq!(b.min, GridCoordinate::new(1, 0));
+/// assert_eq!(b.max, GridCoordinate::new(2, 2));
+/// ```
+pub fn label_components<T: Copy, S: Fn(T, T) -> bool>(
+    grid: &Grid<T>,
+    connectivity: Connectivity,
+    same_region: S,
+) -> ComponentLabels {
+    let width = grid.get_width();
+    let height = grid.get_height();
+    let mut labels = Grid::new(width, height, vec![usize::MAX; width * height]);
+    let mut regions = vec![];
+
+    for seed in grid.coord_iter() {
+        if labels.get_value(seed).unwrap() != usize::MAX {
+            continue;
+        }
+        let label = regions.len();
+        let mut region = Region {
+            size: 0,
+            min: seed,
+            max: seed,
+            perimeter: 0,
+        };
+        labels.set_value(seed, label);
+        let mut queue = VecDeque::from([seed]);
+        while let Some(pos) = queue.pop_front() {
+            let value = grid.get_value(pos).unwrap();
+            region.size += 1;
+            region.min = GridCoordinate::new(region.min.x.min(pos.x), region.min.y.min(pos.y));
+            region.max = GridCoordinate::new(region.max.x.max(pos.x), region.max.y.max(pos.y));
+            for next in connectivity.neighbours(grid, pos) {
+                if labels.get_value(next).unwrap() == usize::MAX
+                    && same_region(value, grid.get_value(next).unwrap())
+                {
+                    labels.set_value(next, label);
+                    queue.push_back(next);
+                }
+            }
+        }
+        regions.push(region);
+    }
+
+    // Perimeter is always about sides, whichever connectivity joined the cells.
+    for pos in grid.coord_iter() {
+        let label = labels.get_value(pos).unwrap();
+        let inside = labels
+            .get_adjacent_coordinates(pos)
+            .into_iter()
+            .filter(|n| labels.get_value(*n).unwrap() == label)
+            .count();
+        regions[label].perimeter += 4 - inside;
+    }
+
+    return ComponentLabels {
+        labels: labels,
+        regions: regions,
+    };
+}
+
+/// Cells that can be reached from beyond the grid's edge without crossing a blocked one.
+/// ```
+/// use gridlib::{fill_from_outside, Grid, GridCoordinate, GridTraversable};
+/// // ....
+/// // .##.
+/// // #..#
+/// // .##.
+/// let grid = Grid::new(4, 4, ".....##.#..#.##.".chars().collect());
+/// let outside = fill_from_outside(&grid, |c| c == '#');
+/// assert_eq!(outside.get_value(GridCoordinate::new(1, 2)), Some(false));
+/// assert_eq!(outside.get_value(GridCoordinate::new(0, 3)), Some(true));
+/// assert_eq!(outside.count_ones(), 8);
+/// ```
+pub fn fill_from_outside<T: Copy, B: Fn(T) -> bool>(grid: &Grid<T>, blocked: B) -> BitGrid {
+    return fill_from_outside_upscaled(grid, 1, |value, _, _| blocked(value));
+}
+
+/// fill_from_outside with every cell blown up to factor by factor sub cells first, so paths
+/// can squeeze between cells that touch without joining, like two pipes side by side.
+/// blocked gets the cell's value and the sub cell's x and y. A cell counts as reached if its
+/// middle sub cell was.
+pub fn fill_from_outside_upscaled<T: Copy, B: Fn(T, usize, usize) -> bool>(
+    grid: &Grid<T>,
+    factor: usize,
+    blocked: B,
+) -> BitGrid {
+    assert!(factor >= 1, "Upscale factor must be at least 1");
+    // A ring of open sub cells all round, so the outside is one connected space.
+    let width = grid.get_width() * factor + 2;
+    let height = grid.get_height() * factor + 2;
+    let mut open = BitGrid::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let is_open = if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                true
+            } else {
+                let cell = GridCoordinate::new((x - 1) / factor, (y - 1) / factor);
+                !blocked(
+                    grid.get_value(cell).unwrap(),
+                    (x - 1) % factor,
+                    (y - 1) % factor,
+                )
+            };
+            open.set_value(GridCoordinate::new(x, y), is_open);
+        }
+    }
+
+    let mut reached = BitGrid::new(width, height);
+    let corner = GridCoordinate::new(0, 0);
+    reached.set_value(corner, true);
+    let mut queue = VecDeque::from([corner]);
+    while let Some(pos) = queue.pop_front() {
+        for direction in [
+            Direction::NORTH,
+            Direction::EAST,
+            Direction::SOUTH,
+            Direction::WEST,
+        ] {
+            if let Some(next) = open.get_coordinate_by_direction(pos, direction) {
+                if open.get_value(next).unwrap() && !reached.get_value(next).unwrap() {
+                    reached.set_value(next, true);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    let mut result = BitGrid::new(grid.get_width(), grid.get_height());
+    for cell in grid.coord_iter() {
+        let middle = GridCoordinate::new(
+            cell.x * factor + factor / 2 + 1,
+            cell.y * factor + factor / 2 + 1,
+        );
+        result.set_value(cell, reached.get_value(middle).unwrap());
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_char_grid;
+    use crate::pipes::TileTable;
+
+    #[test]
+    fn test_squeeze_between_pipes() {
+        let grid = parse_char_grid(vec![
+            "..........",
+            ".F------7.",
+            ".|F----7|.",
+            ".||....||.",
+            ".||....||.",
+            ".|L-7F-J|.",
+            ".|..||..|.",
+            ".L--JL--J.",
+            "..........",
+        ]);
+        let table = TileTable::pipes();
+        let outside = fill_from_outside_upscaled(&grid, 3, |c, x, y| table.blocks_subcell(c, x, y));
+        let enclosed = grid
+            .coord_iter()
+            .filter(|c| grid.get_value(*c).unwrap() == '.' && !outside.get_value(*c).unwrap())
+            .count();
+        assert_eq!(enclosed, 4);
+        // Without upscaling the gap between the pipes is shut.
+        let outside = fill_from_outside(&grid, |c| c != '.');
+        let enclosed = grid
+            .coord_iter()
+            .filter(|c| grid.get_value(*c).unwrap() == '.' && !outside.get_value(*c).unwrap())
+            .count();
+        assert_eq!(enclosed, 12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_upscale_factor_zero() {
+        let grid = parse_char_grid(vec!["."]);
+        fill_from_outside_upscaled(&grid, 0, |_, _, _| false);
+    }
+
+    #[test]
+    fn test_flood_fill_blocked_seed() {
+        let grid = parse_char_grid(vec!["#.", ".."]);
+        let fill = flood_fill(&grid, GridCoordinate::new(0, 0), Connectivity::Four, |c| {
+            c == '.'
+        });
+        assert_eq!(fill.count_ones(), 0);
+    }
+
+    #[test]
+    fn test_label_diagonal() {
+        let grid = parse_char_grid(vec!["#.#", ".#.", "#.#"]);
+        let four = label_components(&grid, Connectivity::Four, |a, b| a == b);
+        assert_eq!(four.get_regions().len(), 9);
+        let eight = label_components(&grid, Connectivity::Eight, |a, b| a == b);
+        assert_eq!(eight.get_regions().len(), 2);
+        let walls = eight.get_region(eight.get_label(GridCoordinate::new(0, 0)));
+        assert_eq!(walls.size, 5);
+        // No two wall cells share a side.
+        assert_eq!(walls.perimeter, 20);
+        assert_eq!(walls.max, GridCoordinate::new(2, 2));
+        assert_eq!(eight.to_bitgrid(1).count_ones(), 4);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::grid::{Grid, GridTraversable};
+use crate::gridcoord::GridCoordinate;
+use crate::region::Connectivity;
+
+/// Which way a span runs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Orientation {
+    /// Along a row, left to right.
+    Horizontal,
+    /// Down a column, top to bottom.
+    Vertical,
+}
+
+/// A maximal run of matching cells in one row or column, like a number in a grid of
+/// characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub orientation: Orientation,
+    /// In reading order, never empty.
+    pub cells: Vec<GridCoordinate>,
+}
+
+impl Span {
+    pub fn get_start(&self) -> GridCoordinate {
+        return self.cells[0];
+    }
+
+    pub fn len(&self) -> usize {
+        return self.cells.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.cells.is_empty();
+    }
+
+    /// The span's values, in order.
+    pub fn values<T: Copy>(&self, grid: &Grid<T>) -> Vec<T> {
+        return self
+            .cells
+            .iter()
+            .map(|c| grid.get_value(*c).unwrap())
+            .collect();
+    }
+
+    /// Cells touching the span that aren't part of it, in reading order. With
+    /// Connectivity::Eight that includes the corners at either end.
+    /// ```
+    /// use gridlib::{scan_spans, Connectivity, Grid, GridCoordinate, Orientation};
+    /// // ....
+    /// // .12.
+    /// // ....
+    /// let grid = Grid::new(4, 3, ".....12.....".chars().collect());
+    /// let spans = scan_spans(&grid, Orientation::Horizontal, |c| c.is_ascii_digit());
+    /// assert_eq!(spans[0].neighbours(&grid, Connectivity::Four).len(), 6);
+    /// assert_eq!(spans[0].neighbours(&grid, Connectivity::Eight).len(), 10);
+    /// ```
+    pub fn neighbours<G: GridTraversable>(
+        &self,
+        grid: &G,
+        connectivity: Connectivity,
+    ) -> Vec<GridCoordinate> {
+        let mut result: Vec<GridCoordinate> = self
+            .cells
+            .iter()
+            .flat_map(|c| connectivity.neighbours(grid, *c))
+            .filter(|n| !self.cells.contains(n))
+            .collect();
+        result.sort_by_key(|c| (c.y, c.x));
+        result.dedup();
+        return result;
+    }
+}
+
+/// Every maximal run of cells matching the predicate, row by row or column by column.
+/// ```
+/// use gridlib::{scan_spans, Grid, GridCoordinate, Orientation};
+/// // 467..
+/// // ..3.5
+/// let grid = Grid::new(5, 2, "467....3.5".chars().collect());
+/// let spans = scan_spans(&grid, Orientation::Horizontal, |c| c.is_ascii_digit());
+/// assert_eq!(spans.len(), 3);
+/// assert_eq!(spans[0].values(&grid), vec!['4', '6', '7']);
+/// assert_eq!(spans[1].get_start(), GridCoordinate::new(2, 1));
+/// let spans = scan_spans(&grid, Orientation::Vertical, |c| c.is_ascii_digit());
+/// assert_eq!(spans.len(), 4);
+/// ```
+pub fn scan_spans<T: Copy, P: Fn(T) -> bool>(
+    grid: &Grid<T>,
+    orientation: Orientation,
+    matches: P,
+) -> Vec<Span> {
+    let (lines, line_len) = match orientation {
+        Orientation::Horizontal => (grid.get_height(), grid.get_width()),
+        Orientation::Vertical => (grid.get_width(), grid.get_height()),
+    };
+    let mut spans = vec![];
+    for line in 0..lines {
+        let mut cells = vec![];
+        for along in 0..line_len {
+            let coord = match orientation {
+                Orientation::Horizontal => GridCoordinate::new(along, line),
+                Orientation::Vertical => GridCoordinate::new(line, along),
+            };
+            if matches(grid.get_value(coord).unwrap()) {
+                cells.push(coord);
+            } else if !cells.is_empty() {
+                spans.push(Span {
+                    orientation: orientation,
+                    cells: std::mem::take(&mut cells),
+                });
+            }
+        }
+        // A run reaching the edge doesn't continue onto the next line.
+        if !cells.is_empty() {
+            spans.push(Span {
+                orientation: orientation,
+                cells: cells,
+            });
+        }
+    }
+    return spans;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_char_grid;
+
+    #[test]
+    fn test_runs_stop_at_line_end() {
+        let grid = parse_char_grid(vec!["..12", "34.."]);
+        let spans = scan_spans(&grid, Orientation::Horizontal, |c| c.is_ascii_digit());
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].values(&grid), vec!['1', '2']);
+        assert_eq!(spans[1].values(&grid), vec!['3', '4']);
+    }
+
+    #[test]
+    fn test_vertical_words() {
+        let grid = parse_char_grid(vec!["c.a", "a.x", "t.e"]);
+        let spans = scan_spans(&grid, Orientation::Vertical, |c| c.is_ascii_alphabetic());
+        let words: Vec<String> = spans
+            .iter()
+            .map(|s| s.values(&grid).into_iter().collect())
+            .collect();
+        assert_eq!(words, vec!["cat", "axe"]);
+        assert_eq!(spans[1].orientation, Orientation::Vertical);
+    }
+
+    #[test]
+    fn test_neighbours_at_corner() {
+        let grid = parse_char_grid(vec!["12.", "..."]);
+        let spans = scan_spans(&grid, Orientation::Horizontal, |c| c.is_ascii_digit());
+        assert_eq!(
+            spans[0].neighbours(&grid, Connectivity::Eight),
+            vec![
+                GridCoordinate::new(2, 0),
+                GridCoordinate::new(0, 1),
+                GridCoordinate::new(1, 1),
+                GridCoordinate::new(2, 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use crate::direction::Direction;
+use crate::gridcoord::GridCoordinateInf64;
+
+use std::fmt::{Display, Formatter};
+
+/// Move `distance` tiles in a direction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PathStep {
+    pub direction: Direction,
+    pub distance: u64,
+}
+
+impl PathStep {
+    pub fn new(direction: Direction, distance: u64) -> PathStep {
+        return PathStep {
+            direction: direction,
+            distance: distance,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathParseError {
+    UnknownDirection(String),
+    BadDistance(String),
+    BadHex(String),
+}
+
+impl Display for PathParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            PathParseError::UnknownDirection(s) => write!(f, "Unknown direction '{}'", s),
+            PathParseError::BadDistance(s) => write!(f, "Bad distance '{}'", s),
+            PathParseError::BadHex(s) => write!(f, "Bad hex step '{}'", s),
+        };
+    }
+}
+
+/// `U`, `D`, `L`, `R`, or the arrows `^`, `v`, `<`, `>`.
+pub fn parse_direction(s: &str) -> Result<Direction, PathParseError> {
+    return match s {
+        "U" | "^" => Ok(Direction::NORTH),
+        "D" | "v" => Ok(Direction::SOUTH),
+        "L" | "<" => Ok(Direction::WEST),
+        "R" | ">" => Ok(Direction::EAST),
+        _ => Err(PathParseError::UnknownDirection(s.to_string())),
+    };
+}
+
+/// A direction then a distance, like `R 6`. Anything after the distance is ignored.
+/// ```
+/// use gridlib::{parse_step, Direction, PathStep};
+/// assert_eq!(parse_step("R 6 (#70c710)"), Ok(PathStep::new(Direction::EAST, 6)));
+/// assert_eq!(parse_step("^ 2"), Ok(PathStep::new(Direction::NORTH, 2)));
+/// assert!(parse_step("X 2").is_err());
+/// ```
+pub fn parse_step(line: &str) -> Result<PathStep, PathParseError> {
+    let mut fields = line.split_whitespace();
+    let direction = parse_direction(fields.next().unwrap_or(""))?;
+    let distance_s = fields.next().unwrap_or("");
+    let distance = distance_s
+        .parse()
+        .map_err(|_| PathParseError::BadDistance(distance_s.to_string()))?;
+    return Ok(PathStep::new(direction, distance));
+}
+
+/// Six hex digits, the first five the distance, the last the direction: 0 right, 1 down,
+/// 2 left, 3 up. Uses the last field of the line, and ignores a `#` or brackets around it.
+/// ```
+/// use gridlib::{parse_hex_step, Direction, PathStep};
+/// assert_eq!(parse_hex_step("R 6 (#70c710)"), Ok(PathStep::new(Direction::EAST, 461937)));
+/// assert_eq!(parse_hex_step("#0dc571"), Ok(PathStep::new(Direction::SOUTH, 56407)));
+/// ```
+pub fn parse_hex_step(line: &str) -> Result<PathStep, PathParseError> {
+    let field = line.split_whitespace().last().unwrap_or("");
+    let hex = field.trim_matches(|c| c == '(' || c == ')' || c == '#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(PathParseError::BadHex(field.to_string()));
+    }
+    let direction = match &hex[5..] {
+        "0" => Direction::EAST,
+        "1" => Direction::SOUTH,
+        "2" => Direction::WEST,
+        "3" => Direction::NORTH,
+        _ => return Err(PathParseError::BadHex(field.to_string())),
+    };
+    let distance = u64::from_str_radix(&hex[..5], 16)
+        .map_err(|_| PathParseError::BadHex(field.to_string()))?;
+    return Ok(PathStep::new(direction, distance));
+}
+
+/// One single tile step per character, like `^^>v<`.
+pub fn parse_arrows(s: &str) -> Result<Vec<PathStep>, PathParseError> {
+    return s
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| parse_direction(&c.to_string()).map(|d| PathStep::new(d, 1)))
+        .collect();
+}
+
+// Unit offset of a direction, north is -y.
+fn unit_offset(direction: Direction) -> (i64, i64) {
+    let moved = GridCoordinateInf64::new(0, 0).move_dir(direction);
+    return (moved.x, moved.y);
+}
+
+// A straight run start + k * offset for k in 0..=len.
+#[derive(Debug, Copy, Clone)]
+struct Segment {
+    start: GridCoordinateInf64,
+    offset: (i64, i64),
+    len: i64,
+}
+
+impl Segment {
+    fn at(&self, k: i64) -> GridCoordinateInf64 {
+        return GridCoordinateInf64::new(
+            self.start.x + k * self.offset.0,
+            self.start.y + k * self.offset.1,
+        );
+    }
+
+    // Smallest k, at least min_k, where this segment touches other.
+    fn first_touch(&self, other: &Segment, min_k: i64) -> Option<i64> {
+        let (dx, dy) = self.offset;
+        let (ox, oy) = other.offset;
+        let rx = other.start.x - self.start.x;
+        let ry = other.start.y - self.start.y;
+        let det = dx * oy - dy * ox;
+
+        if det == 0 {
+            // Parallel, they only touch if they're on the same line.
+            if rx * dy - ry * dx != 0 {
+                return None;
+            }
+            // Both offsets are the same length, so other.at(j) is self.at(base + sign * j).
+            let sign = if (dx, dy) == (ox, oy) { 1 } else { -1 };
+            let base = if dx != 0 { rx / dx } else { ry / dy };
+            let (low, high) = if sign == 1 {
+                (base, base + other.len)
+            } else {
+                (base - other.len, base)
+            };
+            let low = low.max(min_k);
+            let high = high.min(self.len);
+            if low > high {
+                return None;
+            }
+            return Some(low);
+        }
+
+        // Solve self.at(k) == other.at(j), both need to be whole steps in range.
+        let k_num = rx * oy - ry * ox;
+        let j_num = rx * dy - ry * dx;
+        if k_num % det != 0 || j_num % det != 0 {
+            return None;
+        }
+        let k = k_num / det;
+        let j = j_num / det;
+        if k < min_k || k > self.len || j < 0 || j > other.len {
+            return None;
+        }
+        return Some(k);
+    }
+}
+
+/// A path drawn by following steps from a start point, like a turtle.
+/// ```
+/// use gridlib::{parse_step, GridCoordinateInf64, TurtlePath};
+/// let steps = vec!["R 2", "D 2", "L 2", "U 2"]
+///     .iter()
+///     .map(|s| parse_step(s).unwrap())
+///     .collect();
+/// let path = TurtlePath::from_steps(GridCoordinateInf64::new(0, 0), &steps);
+/// assert!(path.is_closed());
+/// assert_eq!(path.self_intersection(), None);
+/// assert_eq!(path.get_vertices()[2], GridCoordinateInf64::new(2, 2));
+/// assert_eq!(path.unit_steps().count(), 9);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurtlePath {
+    steps: Vec<PathStep>,
+    vertices: Vec<GridCoordinateInf64>,
+}
+
+impl TurtlePath {
+    pub fn new(start: GridCoordinateInf64) -> TurtlePath {
+        return TurtlePath {
+            steps: vec![],
+            vertices: vec![start],
+        };
+    }
+
+    pub fn from_steps(start: GridCoordinateInf64, steps: &Vec<PathStep>) -> TurtlePath {
+        let mut path = TurtlePath::new(start);
+        for step in steps {
+            path.push(*step);
+        }
+        return path;
+    }
+
+    /// Move on from the current end.
+    pub fn push(&mut self, step: PathStep) {
+        let distance: i64 = step.distance.try_into().unwrap();
+        let next = self.get_end().move_dir_dist(step.direction, distance);
+        self.steps.push(step);
+        self.vertices.push(next);
+    }
+
+    pub fn get_steps(&self) -> &Vec<PathStep> {
+        return &self.steps;
+    }
+
+    /// The start, then the end of every step.
+    pub fn get_vertices(&self) -> &Vec<GridCoordinateInf64> {
+        return &self.vertices;
+    }
+
+    pub fn get_start(&self) -> GridCoordinateInf64 {
+        return self.vertices[0];
+    }
+
+    pub fn get_end(&self) -> GridCoordinateInf64 {
+        return *self.vertices.last().unwrap();
+    }
+
+    /// Total distance moved.
+    pub fn length(&self) -> u64 {
+        return self.steps.iter().map(|s| s.distance).sum();
+    }
+
+    /// Ends back where it started, having moved.
+    pub fn is_closed(&self) -> bool {
+        return self.length() > 0 && self.get_start() == self.get_end();
+    }
+
+    /// Every tile the path passes through in order, starting with the start.
+    pub fn unit_steps(&self) -> impl Iterator<Item = GridCoordinateInf64> + '_ {
+        let start = std::iter::once(self.get_start());
+        let rest = self
+            .segments()
+            .flat_map(|segment| (1..=segment.len).map(move |k| segment.at(k)));
+        return start.chain(rest);
+    }
+
+    /// The first tile, following the path, that it has already been through.
+    ///
+    /// Coming back to the start on the very last tile closes the path, and doesn't count.
+    pub fn self_intersection(&self) -> Option<GridCoordinateInf64> {
+        let segments: Vec<Segment> = self.segments().collect();
+        let closed = self.is_closed();
+        for (later_index, later) in segments.iter().enumerate() {
+            let mut first: Option<i64> = None;
+            for (earlier_index, earlier) in segments[..later_index].iter().enumerate() {
+                // The join to the segment before isn't a crossing.
+                let min_k = if earlier_index + 1 == later_index {
+                    1
+                } else {
+                    0
+                };
+                let Some(k) = later.first_touch(earlier, min_k) else {
+                    continue;
+                };
+                // Touching only at the very end of the path, back at the start, closes it.
+                let closing = closed
+                    && earlier_index == 0
+                    && later_index == segments.len() - 1
+                    && k == later.len;
+                if closing {
+                    continue;
+                }
+                first = Some(first.map_or(k, |f| f.min(k)));
+            }
+            if let Some(k) = first {
+                return Some(later.at(k));
+            }
+        }
+        return None;
+    }
+
+    // Steps that move, as segments.
+    fn segments(&self) -> impl Iterator<Item = Segment> + '_ {
+        return self
+            .steps
+            .iter()
+            .zip(self.vertices.iter())
+            .filter(|(step, _)| step.distance > 0)
+            .map(|(step, start)| Segment {
+                start: *start,
+                offset: unit_offset(step.direction),
+                len: step.distance.try_into().unwrap(),
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_from(lines: &Vec<&str>) -> TurtlePath {
+        let steps = lines.iter().map(|s| parse_step(s).unwrap()).collect();
+        return TurtlePath::from_steps(GridCoordinateInf64::new(0, 0), &steps);
+    }
+
+    #[test]
+    fn test_parse_direction() {
+        assert_eq!(parse_direction("v"), Ok(Direction::SOUTH));
+        assert_eq!(parse_direction("<"), Ok(Direction::WEST));
+        assert_eq!(
+            parse_direction("Q"),
+            Err(PathParseError::UnknownDirection("Q".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_step("R x"),
+            Err(PathParseError::BadDistance("x".to_string()))
+        );
+        assert_eq!(
+            parse_hex_step("(#70c714)"),
+            Err(PathParseError::BadHex("(#70c714)".to_string()))
+        );
+        assert_eq!(
+            parse_hex_step("#70c7"),
+            Err(PathParseError::BadHex("#70c7".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_arrows() {
+        let steps = parse_arrows("^>v").unwrap();
+        let path = TurtlePath::from_steps(GridCoordinateInf64::new(0, 0), &steps);
+        assert_eq!(
+            path.get_vertices(),
+            &vec![
+                GridCoordinateInf64::new(0, 0),
+                GridCoordinateInf64::new(0, -1),
+                GridCoordinateInf64::new(1, -1),
+                GridCoordinateInf64::new(1, 0),
+            ]
+        );
+        assert!(!path.is_closed());
+        assert!(parse_arrows("^x").is_err());
+    }
+
+    #[test]
+    fn test_unit_steps() {
+        let path = path_from(&vec!["R 2", "D 1"]);
+        let tiles: Vec<GridCoordinateInf64> = path.unit_steps().collect();
+        assert_eq!(
+            tiles,
+            vec![
+                GridCoordinateInf64::new(0, 0),
+                GridCoordinateInf64::new(1, 0),
+                GridCoordinateInf64::new(2, 0),
+                GridCoordinateInf64::new(2, 1),
+            ]
+        );
+        assert_eq!(path.length(), 3);
+    }
+
+    #[test]
+    fn test_crossing() {
+        // Goes round, then cuts back across the first step.
+        let path = path_from(&vec!["R 4", "D 2", "L 2", "U 4"]);
+        assert_eq!(
+            path.self_intersection(),
+            Some(GridCoordinateInf64::new(2, 0))
+        );
+    }
+
+    #[test]
+    fn test_doubling_back() {
+        let path = path_from(&vec!["R 4", "L 1"]);
+        assert_eq!(
+            path.self_intersection(),
+            Some(GridCoordinateInf64::new(3, 0))
+        );
+        let path = path_from(&vec!["R 4", "R 1"]);
+        assert_eq!(path.self_intersection(), None);
+    }
+
+    #[test]
+    fn test_touching_corner() {
+        // Touches the start without ending there.
+        let path = path_from(&vec!["R 2", "D 2", "L 2", "U 2", "L 1"]);
+        assert!(!path.is_closed());
+        assert_eq!(
+            path.self_intersection(),
+            Some(GridCoordinateInf64::new(0, 0))
+        );
+    }
+
+    #[test]
+    fn test_closed_overlapping_start() {
+        // Closes, but runs back over the first step to get there.
+        let path = path_from(&vec!["R 3", "D 2", "L 1", "U 2", "L 2"]);
+        assert!(path.is_closed());
+        assert_eq!(
+            path.self_intersection(),
+            Some(GridCoordinateInf64::new(2, 0))
+        );
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let steps = vec![
+            PathStep::new(Direction::SOUTHEAST, 2),
+            PathStep::new(Direction::NORTH, 2),
+            PathStep::new(Direction::SOUTHWEST, 2),
+        ];
+        let path = TurtlePath::from_steps(GridCoordinateInf64::new(0, 0), &steps);
+        // The two diagonals cross at (1, 1).
+        assert_eq!(
+            path.self_intersection(),
+            Some(GridCoordinateInf64::new(1, 1))
+        );
+
+        // These cross between tiles, so never share one.
+        let steps = vec![
+            PathStep::new(Direction::SOUTHEAST, 1),
+            PathStep::new(Direction::NORTH, 1),
+            PathStep::new(Direction::SOUTHWEST, 1),
+        ];
+        let path = TurtlePath::from_steps(GridCoordinateInf64::new(0, 0), &steps);
+        assert_eq!(path.self_intersection(), None);
+    }
+
+    #[test]
+    fn test_zero_steps() {
+        let path = path_from(&vec!["R 0"]);
+        assert!(!path.is_closed());
+        assert_eq!(path.self_intersection(), None);
+        assert_eq!(path.unit_steps().count(), 1);
+    }
+}
@@ -0,0 +1,126 @@
+/// One axis of points where every coordinate with no point on it is stretched to factor
+/// wide, like empty rows of space expanding.
+///
+/// Only the distinct occupied coordinates are kept, sorted. The number of empty coordinates
+/// before any position is that position minus how many occupied ones come before it, so
+/// expanding is one binary search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpandedAxis {
+    occupied: Vec<u64>,
+    factor: u64,
+}
+
+impl ExpandedAxis {
+    /// coords may repeat and come in any order. A factor of 1 leaves everything alone.
+    pub fn new(coords: &Vec<u64>, factor: u64) -> ExpandedAxis {
+        assert!(factor >= 1, "Expansion factor must be at least 1");
+        let mut occupied = coords.clone();
+        occupied.sort();
+        occupied.dedup();
+        return ExpandedAxis {
+            occupied: occupied,
+            factor: factor,
+        };
+    }
+
+    /// Empty coordinates strictly before coord.
+    pub fn empty_before(&self, coord: u64) -> u64 {
+        let occupied_before = self.occupied.partition_point(|c| *c < coord) as u64;
+        return coord - occupied_before;
+    }
+
+    /// Where coord ends up once the empty coordinates before it are stretched.
+    /// ```
+    /// let axis = mathlib::ExpandedAxis::new(&vec![0, 3, 3, 7], 10);
+    /// assert_eq!(axis.expand(0), 0);
+    /// // 1 and 2 are empty, so 10 wide each.
+    /// assert_eq!(axis.expand(3), 21);
+    /// assert_eq!(axis.expand(7), 21 + 1 + 30);
+    /// ```
+    pub fn expand(&self, coord: u64) -> u64 {
+        return coord + self.empty_before(coord) * (self.factor - 1);
+    }
+}
+
+/// Sum of |a - b| over every pair of values, sorting then keeping a running total of
+/// everything smaller.
+/// ```
+/// assert_eq!(mathlib::sum_pairwise_distances(&vec![1, 5, 3]), 2 + 4 + 2);
+/// ```
+pub fn sum_pairwise_distances(values: &Vec<u64>) -> u64 {
+    let mut sorted = values.clone();
+    sorted.sort();
+    let mut below = 0;
+    let mut total = 0;
+    for (i, value) in sorted.into_iter().enumerate() {
+        total += value * i as u64 - below;
+        below += value;
+    }
+    return total;
+}
+
+/// Sum of the Manhattan distance between every pair of points, after stretching every
+/// empty row and column to factor wide.
+/// ```
+/// // #..
+/// // ...
+/// // ..#
+/// assert_eq!(mathlib::expanded_manhattan_sum(&vec![(0, 0), (2, 2)], 2), 6);
+/// ```
+pub fn expanded_manhattan_sum(points: &Vec<(u64, u64)>, factor: u64) -> u64 {
+    let xs: Vec<u64> = points.iter().map(|p| p.0).collect();
+    let ys: Vec<u64> = points.iter().map(|p| p.1).collect();
+    let x_axis = ExpandedAxis::new(&xs, factor);
+    let y_axis = ExpandedAxis::new(&ys, factor);
+    let expanded_xs: Vec<u64> = xs.into_iter().map(|x| x_axis.expand(x)).collect();
+    let expanded_ys: Vec<u64> = ys.into_iter().map(|y| y_axis.expand(y)).collect();
+    return sum_pairwise_distances(&expanded_xs) + sum_pairwise_distances(&expanded_ys);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_factor_one_is_identity() {
+        let axis = ExpandedAxis::new(&vec![5, 2], 1);
+        for c in 0..10 {
+            assert_eq!(axis.expand(c), c);
+        }
+    }
+
+    #[test]
+    fn test_empty_before() {
+        let axis = ExpandedAxis::new(&vec![2, 4, 5], 3);
+        assert_eq!(axis.empty_before(0), 0);
+        assert_eq!(axis.empty_before(2), 2);
+        assert_eq!(axis.empty_before(5), 3);
+        assert_eq!(axis.empty_before(9), 6);
+    }
+
+    #[test]
+    fn test_matches_every_pair() {
+        let points = vec![
+            (3, 0),
+            (7, 1),
+            (0, 2),
+            (6, 4),
+            (1, 5),
+            (9, 6),
+            (7, 8),
+            (0, 9),
+        ];
+        for factor in [1, 2, 10, 1000000] {
+            let x_axis = ExpandedAxis::new(&points.iter().map(|p| p.0).collect(), factor);
+            let y_axis = ExpandedAxis::new(&points.iter().map(|p| p.1).collect(), factor);
+            let mut expected = 0;
+            for (i, a) in points.iter().enumerate() {
+                for b in points[i + 1..].iter() {
+                    expected += x_axis.expand(a.0).abs_diff(x_axis.expand(b.0));
+                    expected += y_axis.expand(a.1).abs_diff(y_axis.expand(b.1));
+                }
+            }
+            assert_eq!(expanded_manhattan_sum(&points, factor), expected);
+        }
+    }
+}
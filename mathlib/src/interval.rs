@@ -0,0 +1,386 @@
+use std::ops::{Add, Mul, Sub};
+
+/// Anything that can be the bound of an interval.
+///
+/// Blanket implemented, so all the integer types (and anything else with these) work.
+pub trait IntervalBound:
+    Copy + Ord + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + From<u8>
+{
+}
+
+impl<T: Copy + Ord + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + From<u8>> IntervalBound
+    for T
+{
+}
+
+/// Inclusive interval of integers, start..=end.
+///
+/// An interval is never empty, operations that could produce nothing return an Option or Vec.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Interval<T: IntervalBound> {
+    start: T,
+    end: T,
+}
+
+impl<T: IntervalBound> Interval<T> {
+    pub fn new(start: T, end: T) -> Interval<T> {
+        assert!(start <= end, "Interval must have start <= end");
+        return Interval {
+            start: start,
+            end: end,
+        };
+    }
+
+    /// Interval of `len` values starting at `start`, None if len is 0.
+    pub fn from_len(start: T, len: T) -> Option<Interval<T>> {
+        let zero: T = 0.into();
+        let one: T = 1.into();
+        if len == zero {
+            return None;
+        }
+        return Some(Interval::new(start, start + (len - one)));
+    }
+
+    pub fn get_start(&self) -> T {
+        return self.start;
+    }
+
+    pub fn get_end(&self) -> T {
+        return self.end;
+    }
+
+    /// Number of values in the interval.
+    pub fn size(&self) -> T {
+        let one: T = 1.into();
+        return self.end - self.start + one;
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        return self.start <= value && value <= self.end;
+    }
+
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        return self.start <= other.end && other.start <= self.end;
+    }
+
+    pub fn intersect(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        return Some(Interval::new(
+            self.start.max(other.start),
+            self.end.min(other.end),
+        ));
+    }
+
+    /// Values in self but not in other, in increasing order.
+    pub fn difference(&self, other: &Interval<T>) -> Vec<Interval<T>> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+        let one: T = 1.into();
+        let mut result = vec![];
+        if self.start < other.start {
+            result.push(Interval::new(self.start, other.start - one));
+        }
+        if other.end < self.end {
+            result.push(Interval::new(other.end + one, self.end));
+        }
+        return result;
+    }
+
+    /// Split into the values below threshold, and the values at or above it.
+    pub fn split_at(&self, threshold: T) -> (Option<Interval<T>>, Option<Interval<T>>) {
+        let one: T = 1.into();
+        if threshold <= self.start {
+            return (None, Some(*self));
+        }
+        if threshold > self.end {
+            return (Some(*self), None);
+        }
+        return (
+            Some(Interval::new(self.start, threshold - one)),
+            Some(Interval::new(threshold, self.end)),
+        );
+    }
+
+    /// Move the interval so that `from` lands on `to`.
+    ///
+    /// Done as `value - from + to`, so unsigned types work as long as from <= start.
+    pub fn rebase(&self, from: T, to: T) -> Interval<T> {
+        return Interval::new(self.start - from + to, self.end - from + to);
+    }
+}
+
+/// Merge overlapping and touching intervals, returned sorted by start.
+pub fn normalize_intervals<T: IntervalBound>(intervals: &Vec<Interval<T>>) -> Vec<Interval<T>> {
+    let one: T = 1.into();
+    let mut sorted = intervals.clone();
+    sorted.sort_by_key(|i| (i.start, i.end));
+    let mut result: Vec<Interval<T>> = vec![];
+    for interval in sorted {
+        if let Some(last) = result.last_mut() {
+            // Written to avoid overflowing past the end of T.
+            if interval.start <= last.end || interval.start - last.end == one {
+                last.end = last.end.max(interval.end);
+                continue;
+            }
+        }
+        result.push(interval);
+    }
+    return result;
+}
+
+/// An N dimensional box, one inclusive interval per dimension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalBox<T: IntervalBound, const N: usize> {
+    dims: [Interval<T>; N],
+}
+
+impl<T: IntervalBound, const N: usize> IntervalBox<T, N> {
+    pub fn new(dims: [Interval<T>; N]) -> IntervalBox<T, N> {
+        return IntervalBox { dims: dims };
+    }
+
+    pub fn get(&self, dim: usize) -> Interval<T> {
+        return self.dims[dim];
+    }
+
+    /// Copy of this box with one dimension replaced.
+    pub fn with(&self, dim: usize, interval: Interval<T>) -> IntervalBox<T, N> {
+        let mut dims = self.dims;
+        dims[dim] = interval;
+        return IntervalBox { dims: dims };
+    }
+
+    pub fn contains(&self, point: &[T; N]) -> bool {
+        return self
+            .dims
+            .iter()
+            .zip(point.iter())
+            .all(|(d, p)| d.contains(*p));
+    }
+
+    /// Number of points in the box.
+    pub fn volume(&self) -> T {
+        let one: T = 1.into();
+        return self.dims.iter().fold(one, |acc, d| acc * d.size());
+    }
+
+    pub fn intersect(&self, other: &IntervalBox<T, N>) -> Option<IntervalBox<T, N>> {
+        let mut dims = self.dims;
+        for (dim, interval) in dims.iter_mut().enumerate() {
+            *interval = interval.intersect(&other.dims[dim])?;
+        }
+        return Some(IntervalBox { dims: dims });
+    }
+
+    /// Split along one dimension into points below threshold, and points at or above it.
+    pub fn split_at(
+        &self,
+        dim: usize,
+        threshold: T,
+    ) -> (Option<IntervalBox<T, N>>, Option<IntervalBox<T, N>>) {
+        let (below, above) = self.dims[dim].split_at(threshold);
+        return (
+            below.map(|i| self.with(dim, i)),
+            above.map(|i| self.with(dim, i)),
+        );
+    }
+
+    /// Points in self but not other, as disjoint boxes.
+    pub fn difference(&self, other: &IntervalBox<T, N>) -> Vec<IntervalBox<T, N>> {
+        let overlap = match self.intersect(other) {
+            Some(overlap) => overlap,
+            None => return vec![*self],
+        };
+        // Peel off the slabs outside the overlap one dimension at a time,
+        // what is left of remaining after all dimensions is exactly the overlap.
+        let mut result = vec![];
+        let mut remaining = *self;
+        for dim in 0..N {
+            for piece in remaining.dims[dim].difference(&overlap.dims[dim]) {
+                result.push(remaining.with(dim, piece));
+            }
+            remaining = remaining.with(dim, overlap.dims[dim]);
+        }
+        return result;
+    }
+}
+
+/// Rewrite possibly overlapping boxes as disjoint boxes covering the same points.
+pub fn disjoint_boxes<T: IntervalBound, const N: usize>(
+    boxes: &Vec<IntervalBox<T, N>>,
+) -> Vec<IntervalBox<T, N>> {
+    let mut result: Vec<IntervalBox<T, N>> = vec![];
+    for b in boxes {
+        let mut pieces = vec![*b];
+        for existing in result.iter() {
+            pieces = pieces.iter().flat_map(|p| p.difference(existing)).collect();
+        }
+        result.extend(pieces);
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_size_and_contains() {
+        let a = Interval::new(3u64, 7);
+        assert_eq!(a.size(), 5);
+        assert!(a.contains(3));
+        assert!(a.contains(7));
+        assert!(!a.contains(8));
+        assert_eq!(Interval::from_len(3u64, 5), Some(a));
+        assert_eq!(Interval::from_len(3u64, 0), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_interval_backwards() {
+        Interval::new(5, 4);
+    }
+
+    #[test]
+    fn test_interval_intersect() {
+        let a = Interval::new(3, 7);
+        assert_eq!(
+            a.intersect(&Interval::new(5, 10)),
+            Some(Interval::new(5, 7))
+        );
+        assert_eq!(a.intersect(&Interval::new(0, 3)), Some(Interval::new(3, 3)));
+        assert_eq!(a.intersect(&Interval::new(4, 5)), Some(Interval::new(4, 5)));
+        assert_eq!(a.intersect(&Interval::new(8, 10)), None);
+    }
+
+    #[test]
+    fn test_interval_difference() {
+        let a = Interval::new(3u64, 7);
+        assert_eq!(a.difference(&Interval::new(8, 10)), vec![a]);
+        assert_eq!(
+            a.difference(&Interval::new(4, 5)),
+            vec![Interval::new(3, 3), Interval::new(6, 7)]
+        );
+        assert_eq!(
+            a.difference(&Interval::new(0, 4)),
+            vec![Interval::new(5, 7)]
+        );
+        assert_eq!(
+            a.difference(&Interval::new(6, 9)),
+            vec![Interval::new(3, 5)]
+        );
+        assert_eq!(a.difference(&Interval::new(0, 9)), vec![]);
+    }
+
+    #[test]
+    fn test_interval_split_at() {
+        let a = Interval::new(1u64, 4000);
+        assert_eq!(
+            a.split_at(1351),
+            (
+                Some(Interval::new(1, 1350)),
+                Some(Interval::new(1351, 4000))
+            )
+        );
+        assert_eq!(a.split_at(1), (None, Some(a)));
+        assert_eq!(a.split_at(0), (None, Some(a)));
+        assert_eq!(a.split_at(4001), (Some(a), None));
+    }
+
+    #[test]
+    fn test_interval_rebase() {
+        let a = Interval::new(53u64, 60);
+        assert_eq!(a.rebase(50, 52), Interval::new(55, 62));
+        assert_eq!(a.rebase(53, 0), Interval::new(0, 7));
+    }
+
+    #[test]
+    fn test_normalize_intervals() {
+        let list = vec![
+            Interval::new(10, 12),
+            Interval::new(1, 3),
+            Interval::new(4, 5),
+            Interval::new(11, 20),
+            Interval::new(30, 30),
+        ];
+        assert_eq!(
+            normalize_intervals(&list),
+            vec![
+                Interval::new(1, 5),
+                Interval::new(10, 20),
+                Interval::new(30, 30)
+            ]
+        );
+        let edge = vec![Interval::new(u8::MAX, u8::MAX), Interval::new(0, 254)];
+        assert_eq!(normalize_intervals(&edge), vec![Interval::new(0, u8::MAX)]);
+    }
+
+    fn square(start: i64, end: i64) -> IntervalBox<i64, 2> {
+        return IntervalBox::new([Interval::new(start, end), Interval::new(start, end)]);
+    }
+
+    #[test]
+    fn test_box_volume_contains() {
+        let b = IntervalBox::new([
+            Interval::new(1u64, 4000),
+            Interval::new(1, 4000),
+            Interval::new(1, 4000),
+            Interval::new(1, 4000),
+        ]);
+        assert_eq!(b.volume(), 256000000000000);
+        assert!(b.contains(&[1, 4000, 20, 3]));
+        assert!(!b.contains(&[1, 4001, 20, 3]));
+    }
+
+    #[test]
+    fn test_box_intersect() {
+        assert_eq!(square(0, 5).intersect(&square(3, 10)), Some(square(3, 5)));
+        assert_eq!(square(0, 5).intersect(&square(6, 10)), None);
+    }
+
+    #[test]
+    fn test_box_split_at() {
+        let (low, high) = square(0, 5).split_at(1, 2);
+        assert_eq!(
+            low,
+            Some(IntervalBox::new([Interval::new(0, 5), Interval::new(0, 1)]))
+        );
+        assert_eq!(
+            high,
+            Some(IntervalBox::new([Interval::new(0, 5), Interval::new(2, 5)]))
+        );
+        assert_eq!(square(0, 5).split_at(0, 10), (Some(square(0, 5)), None));
+    }
+
+    #[test]
+    fn test_box_difference() {
+        let outer = square(0, 9);
+        let inner = square(3, 5);
+        let pieces = outer.difference(&inner);
+        // One slab either side per dimension
+        assert_eq!(pieces.len(), 4);
+        let total: i64 = pieces.iter().map(|p| p.volume()).sum();
+        assert_eq!(total, 100 - 9);
+        for p in pieces.iter() {
+            assert_eq!(p.intersect(&inner), None);
+        }
+        assert_eq!(outer.difference(&square(20, 30)), vec![outer]);
+        assert_eq!(inner.difference(&outer), vec![]);
+    }
+
+    #[test]
+    fn test_disjoint_boxes() {
+        let boxes = vec![square(0, 9), square(5, 14), square(2, 3)];
+        let disjoint = disjoint_boxes(&boxes);
+        let total: i64 = disjoint.iter().map(|p| p.volume()).sum();
+        assert_eq!(total, 100 + 100 - 25);
+        for (i, a) in disjoint.iter().enumerate() {
+            for b in disjoint[i + 1..].iter() {
+                assert_eq!(a.intersect(b), None);
+            }
+        }
+    }
+}
@@ -1,3 +1,17 @@
+mod compress;
+
+pub use crate::compress::expanded_manhattan_sum;
+pub use crate::compress::sum_pairwise_distances;
+pub use crate::compress::ExpandedAxis;
+
+mod interval;
+
+pub use crate::interval::disjoint_boxes;
+pub use crate::interval::normalize_intervals;
+pub use crate::interval::Interval;
+pub use crate::interval::IntervalBound;
+pub use crate::interval::IntervalBox;
+
 // modulus function.
 pub fn modulus(a: i32, b: i32) -> i32 {
     // % is actually the remainder function, not the modulus function
@@ -0,0 +1,178 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+// Worker threads parallel_map uses, 0 means one per CPU.
+static JOBS: AtomicUsize = AtomicUsize::new(0);
+
+/// One worker per CPU, or 1 if that can't be found.
+pub fn default_jobs() -> usize {
+    return thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+}
+
+/// Set how many worker threads parallel_map uses, 0 goes back to one per CPU.
+pub fn set_jobs(jobs: usize) {
+    JOBS.store(jobs, Ordering::Relaxed);
+}
+
+/// How many worker threads parallel_map uses.
+pub fn get_jobs() -> usize {
+    let jobs = JOBS.load(Ordering::Relaxed);
+    if jobs == 0 {
+        return default_jobs();
+    }
+    return jobs;
+}
+
+/// Find `--jobs N`, `--jobs=N` or `-j N` in program arguments.
+/// ```
+/// let args: Vec<String> = vec!["day16.day", "--jobs", "4"].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(parallellib::parse_jobs(&args), Some(4));
+/// let args: Vec<String> = vec!["day16.day", "--jobs=2"].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(parallellib::parse_jobs(&args), Some(2));
+/// let args: Vec<String> = vec!["day16.day"].iter().map(|s| s.to_string()).collect();
+/// assert_eq!(parallellib::parse_jobs(&args), None);
+/// ```
+pub fn parse_jobs(args: &Vec<String>) -> Option<usize> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let value = if arg == "--jobs" || arg == "-j" {
+            iter.next().map(|s| s.as_str())
+        } else if let Some(value) = arg.strip_prefix("--jobs=") {
+            Some(value)
+        } else {
+            continue;
+        };
+        return match value.and_then(|v| v.parse::<usize>().ok()) {
+            Some(jobs) if jobs > 0 => Some(jobs),
+            _ => panic!("{} needs a positive number of jobs", arg),
+        };
+    }
+    return None;
+}
+
+/// Set the jobs from this program's `--jobs` argument, if it was given.
+pub fn set_jobs_from_args() {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(jobs) = parse_jobs(&args) {
+        set_jobs(jobs);
+    }
+}
+
+/// Apply f to every item on a pool of get_jobs() worker threads.
+///
+/// Results come back in the same order as items, however the work was shared out.
+/// ```
+/// let starts: Vec<u64> = (1..=20).collect();
+/// let squares = parallellib::parallel_map(&starts, |n| n * n);
+/// assert_eq!(squares[0], 1);
+/// assert_eq!(squares[19], 400);
+/// ```
+pub fn parallel_map<T, R, F>(items: &Vec<T>, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    return parallel_map_with_jobs(items, get_jobs(), f);
+}
+
+/// As parallel_map, with at most `jobs` worker threads.
+pub fn parallel_map_with_jobs<T, R, F>(items: &Vec<T>, jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let workers = jobs.min(items.len());
+    if workers <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    // Each worker takes the next unclaimed item until there are none left.
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<R>> = (0..items.len()).map(|_| None).collect();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                return scope.spawn(|| {
+                    let mut done = vec![];
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= items.len() {
+                            return done;
+                        }
+                        done.push((index, f(&items[index])));
+                    }
+                });
+            })
+            .collect();
+        for handle in handles {
+            for (index, result) in handle.join().unwrap() {
+                results[index] = Some(result);
+            }
+        }
+    });
+    return results.into_iter().map(|r| r.unwrap()).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    #[test]
+    fn test_keeps_order() {
+        let items: Vec<u64> = (0..100).collect();
+        // Early items take longest, so they finish last.
+        let results = parallel_map_with_jobs(&items, 4, |n| {
+            thread::sleep(Duration::from_micros(100 - n));
+            return n * 2;
+        });
+        assert_eq!(results, (0..100).map(|n| n * 2).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_bounded_workers() {
+        let items: Vec<u32> = (0..40).collect();
+        let running = AtomicUsize::new(0);
+        let most = AtomicUsize::new(0);
+        parallel_map_with_jobs(&items, 3, |_| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            most.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(1));
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+        assert!(most.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn test_single_job_and_empty() {
+        let items: Vec<u32> = vec![3, 1, 2];
+        assert_eq!(parallel_map_with_jobs(&items, 1, |n| n + 1), vec![4, 2, 3]);
+        assert_eq!(parallel_map_with_jobs(&items, 0, |n| n + 1), vec![4, 2, 3]);
+        let empty: Vec<u32> = vec![];
+        assert_eq!(parallel_map_with_jobs(&empty, 8, |n| n + 1), vec![]);
+    }
+
+    #[test]
+    fn test_parse_jobs_short() {
+        let args: Vec<String> = vec!["prog", "-j", "3"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(parse_jobs(&args), Some(3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_parse_jobs_bad() {
+        let args: Vec<String> = vec!["prog", "--jobs", "0"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        parse_jobs(&args);
+    }
+}